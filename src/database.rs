@@ -4,18 +4,37 @@
 
 mod logger;
 
+mod aggregate;
+
 mod in_memory_table;
 
 mod command;
 
+mod evaluator;
+
+mod explain;
+
 pub mod config;
 
 mod file_io;
 
+mod functions;
+
+mod join;
+
 mod parser;
 
+pub mod prepared_statement;
+
+mod set_operation;
+
+mod subquery;
+
 pub mod steeldb;
 
+#[cfg(test)]
 mod tests;
 
 mod virtual_machine;
+
+mod window;
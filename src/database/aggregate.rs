@@ -0,0 +1,232 @@
+//! Hash aggregation, which reduces the rows of an [InMemoryTable] into one row per group.
+use crate::database::evaluator::{aggregate_type, evaluate, infer_type, resolve, EvaluationError};
+use crate::database::in_memory_table::InMemoryTable;
+use std::collections::{HashMap, HashSet};
+use steeldb_core::{DataType, RowKey};
+use steeldb_parser::{AggregateFunction, Expression};
+
+/// The running state of an aggregate, for a single group or window frame.
+/// The count only includes the values that are not NULL.
+/// The values already seen are only kept for a DISTINCT aggregate.
+#[derive(Clone, Default)]
+pub struct Accumulator {
+    count: usize,
+    integer_sum: i64,
+    float_sum: f64,
+    extreme: Option<DataType>,
+    seen: HashSet<RowKey>,
+}
+
+impl Accumulator {
+    /// Adds the value of the aggregate argument for a row of the group.
+    /// The argument of `COUNT(*)` is `None`, while NULL arguments are skipped,
+    /// as are the values already seen when the aggregate is `distinct`.
+    pub fn add(
+        &mut self,
+        function: &AggregateFunction,
+        distinct: bool,
+        value: Option<DataType>,
+    ) -> Result<(), EvaluationError> {
+        if value == Some(DataType::Null) {
+            return Ok(());
+        }
+        if let (true, Some(value)) = (distinct, &value) {
+            if !self.seen.insert(RowKey(vec![value.clone()])) {
+                return Ok(());
+            }
+        }
+        self.count += 1;
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        match function {
+            AggregateFunction::Count => {}
+            AggregateFunction::Sum | AggregateFunction::Avg => match value {
+                DataType::Integer32(i) => {
+                    self.integer_sum = match self.integer_sum.checked_add(i as i64) {
+                        Some(sum) => sum,
+                        None => {
+                            return Err(EvaluationError::OutOfRange(format!(
+                                "{} is out of range",
+                                function
+                            )));
+                        }
+                    };
+                    self.float_sum += i as f64;
+                }
+                DataType::Float32(f) => self.float_sum += f as f64,
+                DataType::Null => {}
+                DataType::String(_) => {
+                    return Err(EvaluationError::TypeMismatch(format!(
+                        "Cannot apply {} to String",
+                        function
+                    )));
+                }
+            },
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let replace = match &self.extreme {
+                    None => true,
+                    Some(extreme) => {
+                        let ordering = value.total_cmp(extreme);
+                        if *function == AggregateFunction::Min {
+                            ordering.is_lt()
+                        } else {
+                            ordering.is_gt()
+                        }
+                    }
+                };
+                if replace {
+                    self.extreme = Some(value);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// Computes the aggregate of the group, converted into the output type.
+    /// Without any value that is not NULL, such as when aggregating an empty table without GROUP BY,
+    /// COUNT is zero while SUM, AVG, MIN and MAX are NULL.
+    pub fn finish(
+        &self,
+        function: &AggregateFunction,
+        output_type: &DataType,
+    ) -> Result<DataType, EvaluationError> {
+        match function {
+            AggregateFunction::Count => match i32::try_from(self.count) {
+                Ok(count) => return Ok(DataType::Integer32(count)),
+                Err(_) => {
+                    return Err(EvaluationError::OutOfRange(
+                        "COUNT is out of range for i32".to_string(),
+                    ));
+                }
+            },
+            AggregateFunction::Sum | AggregateFunction::Avg if self.count == 0 => {
+                return Ok(DataType::Null);
+            }
+            AggregateFunction::Sum => match output_type {
+                DataType::Integer32(_) => match i32::try_from(self.integer_sum) {
+                    Ok(sum) => return Ok(DataType::Integer32(sum)),
+                    Err(_) => {
+                        return Err(EvaluationError::OutOfRange(format!(
+                            "SUM {} is out of range for i32",
+                            self.integer_sum
+                        )));
+                    }
+                },
+                _ => return Ok(DataType::Float32(self.float_sum as f32)),
+            },
+            AggregateFunction::Avg => {
+                return Ok(DataType::Float32(
+                    (self.float_sum / self.count as f64) as f32,
+                ));
+            }
+            AggregateFunction::Min | AggregateFunction::Max => match &self.extreme {
+                Some(extreme) => return Ok(extreme.clone()),
+                None => return Ok(DataType::Null),
+            },
+        }
+    }
+}
+
+/// Groups the rows of the table by the values of the grouping keys, then computes the aggregates of each group.
+/// The result has a column per grouping key followed by a column per aggregate,
+/// each named after its expression, e.g. `dept` and `COUNT(*)`.
+/// Groups are kept in the order they first appear.
+/// Without grouping keys, the whole table is a single group, even when it is empty.
+/// The keys and the aggregates are expected without duplicates.
+pub fn aggregate(
+    table: &InMemoryTable,
+    group_by: &[Expression],
+    aggregates: &[Expression],
+) -> Result<InMemoryTable, EvaluationError> {
+    // the result columns are named after the expressions as given, not as resolved
+    let mut resolved_keys = Vec::<Expression>::new();
+    for key in group_by.iter() {
+        resolved_keys.push(resolve(key, table)?);
+    }
+    let mut resolved_aggregates = Vec::<Expression>::new();
+    for expression in aggregates.iter() {
+        resolved_aggregates.push(resolve(expression, table)?);
+    }
+
+    let mut functions = Vec::<(AggregateFunction, Option<&Expression>, bool)>::new();
+    let mut output_types = Vec::<DataType>::new();
+    for expression in resolved_aggregates.iter() {
+        match expression {
+            Expression::Aggregate(function, argument, distinct) => {
+                let argument_type = match argument {
+                    Some(argument) => Some(infer_type(argument, table)?),
+                    None => None,
+                };
+                output_types.push(aggregate_type(function, argument_type)?);
+                functions.push((*function, argument.as_deref(), *distinct));
+            }
+            _ => {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "Expected an aggregate, found {}",
+                    expression
+                )));
+            }
+        }
+    }
+
+    let mut group_indexes = HashMap::<RowKey, usize>::new();
+    let mut group_keys = Vec::<Vec<DataType>>::new();
+    let mut accumulators = Vec::<Vec<Accumulator>>::new();
+    for row in 0..table.number_of_rows() {
+        let mut key = Vec::<DataType>::new();
+        for expression in resolved_keys.iter() {
+            key.push(evaluate(expression, table, row)?);
+        }
+        let index = match group_indexes.get(&RowKey(key.clone())) {
+            Some(index) => *index,
+            None => {
+                group_indexes.insert(RowKey(key.clone()), group_keys.len());
+                group_keys.push(key);
+                accumulators.push(vec![Accumulator::default(); functions.len()]);
+                group_keys.len() - 1
+            }
+        };
+        for (accumulator, (function, argument, distinct)) in
+            accumulators[index].iter_mut().zip(&functions)
+        {
+            let value = match argument {
+                Some(argument) => Some(evaluate(argument, table, row)?),
+                None => None,
+            };
+            accumulator.add(function, *distinct, value)?;
+        }
+    }
+    if group_by.is_empty() && group_keys.is_empty() {
+        group_keys.push(vec![]);
+        accumulators.push(vec![Accumulator::default(); functions.len()]);
+    }
+
+    let mut schema = Vec::<(String, DataType)>::new();
+    for (expression, resolved) in group_by.iter().zip(resolved_keys.iter()) {
+        schema.push((expression.to_string(), infer_type(resolved, table)?));
+    }
+    for (expression, output_type) in aggregates.iter().zip(output_types.iter()) {
+        schema.push((expression.to_string(), output_type.clone()));
+    }
+    let mut result = InMemoryTable::from_schema(table.name.clone(), schema);
+    for (key, group_accumulators) in group_keys.into_iter().zip(accumulators) {
+        for (expression, value) in group_by.iter().zip(key) {
+            result
+                .columns
+                .get_mut(&expression.to_string())
+                .unwrap()
+                .push(value);
+        }
+        for (index, accumulator) in group_accumulators.iter().enumerate() {
+            let value = accumulator.finish(&functions[index].0, &output_types[index])?;
+            result
+                .columns
+                .get_mut(&aggregates[index].to_string())
+                .unwrap()
+                .push(value);
+        }
+    }
+    return Ok(result);
+}
@@ -1,13 +1,87 @@
 #![allow(unused)]
 //! Defines commands that the VirtualMachine may execute.
-use steeldb_core::Table;
+use steeldb_core::{DataType, Table};
+use steeldb_parser::{
+    AlterTableAction, Expression, JoinKind, OrderBy, Parameter, SelectItem, SetOperator,
+};
 
 /// All known commands are defined in this enum.
+/// Commands that take a table as input consume the table produced by the previous command,
+/// or the two tables produced by the previous commands for joins.
+#[derive(Clone)]
 pub enum Command {
     /// The Select From Command, returns columns and table_name parsed from the string.
     SelectFrom(Vec<String>, String),
-    /// Stub, will be removed once a second command is added.
-    Stub,
+    /// Loads a table under an alias, qualifying its columns as `alias.column`.
+    /// Only the columns that the given column references may refer to are read.
+    /// Holds the column references, the table name and the alias.
+    SelectFromAs(Vec<String>, String, String),
+    /// Returns a table with a single row and no columns, read by a select without a FROM clause.
+    SingleRow,
+    /// Joins the two previous tables, keeping the pairs of rows for which the predicate holds.
+    Join(JoinKind, Expression),
+    /// Combines the rows of the two previous tables, keeping duplicate rows when the flag is set.
+    SetOperation(SetOperator, bool),
+    /// Keeps only the rows of the previous table for which the predicate holds.
+    Filter(Expression),
+    /// Computes the items of a SELECT clause from the previous table, in the given order.
+    /// Each result column is named by its alias, or else as written, even when it is found
+    /// by a qualified or unqualified name.
+    Project(Vec<SelectItem>),
+    /// Creates an empty table with the given name and schema.
+    /// The flag tells whether an already existing table should be ignored (IF NOT EXISTS).
+    CreateTable(String, Vec<(String, DataType)>, bool),
+    /// Appends rows of values to a table.
+    /// The columns are optional, when omitted the values follow the table schema order.
+    /// The values given by parameters are listed by their row, position and parameter,
+    /// and must be bound before the command is executed.
+    Insert(
+        String,
+        Option<Vec<String>>,
+        Vec<Vec<DataType>>,
+        Vec<(usize, usize, Parameter)>,
+    ),
+    /// Deletes a table. The flag tells whether a missing table should be ignored (IF EXISTS).
+    DropTable(String, bool),
+    /// Deletes all rows of a table, keeping its schema.
+    TruncateTable(String),
+    /// Changes the schema of a table, or renames it.
+    AlterTable(String, AlterTableAction),
+    /// Sets columns to new values, computed from each row, for the rows matching the optional predicate.
+    Update(String, Vec<(String, Expression)>, Option<Expression>),
+    /// Groups the rows of the previous table by the given keys, computing the given aggregates for each group.
+    /// The result has a column per key and per aggregate, named after their expressions.
+    Aggregate(Vec<Expression>, Vec<Expression>),
+    /// Sorts the rows of the previous table by the given keys, from the most to the least significant.
+    Sort(Vec<OrderBy>),
+    /// Computes the window functions over the rows of the previous table, adding a column for each.
+    Window(Vec<Expression>),
+    /// Removes the duplicate rows of the previous table, comparing their selected columns.
+    Distinct,
+    /// Skips the given number of rows of the previous table, then keeps at most the given limit.
+    Limit(Option<usize>, usize),
+    /// Removes the rows matching the optional predicate, or every row without one.
+    Delete(String, Option<Expression>),
+    /// Executes the given subqueries for each row of the previous table,
+    /// adding a column per subquery, named after it, that the following commands read.
+    /// These columns are not selected, so they are never part of the result.
+    EvaluateSubqueries(Vec<Expression>),
+    /// Qualifies the columns of the previous table with the given alias, as for a derived table.
+    Qualify(String),
+    /// Executes the given commands and saves their result as a table of a WITH clause,
+    /// which the following commands read by its name instead of a stored table.
+    /// Holds the name, the optional column names and the commands.
+    With(String, Vec<String>, Vec<Command>),
+    /// Saves the result of a recursive table of a WITH clause. The initial commands give the first rows,
+    /// then the recursive commands are executed with the rows added by the previous step as the table,
+    /// until they add no new rows. Duplicate rows are kept only when the flag is set (UNION ALL).
+    /// Holds the name, the optional column names, the initial commands, the recursive commands and the flag.
+    WithRecursive(String, Vec<String>, Vec<Command>, Vec<Command>, bool),
+    /// Returns a table describing the given commands instead of executing them, see [explain](super::explain::explain).
+    Explain(Vec<Command>),
+    /// Replaces the commands of a select that breaks the rules checked by `SelectStatement::validate`,
+    /// holding the message. It is reported by [validate](super::functions::validate) before any command runs.
+    Invalid(String),
 }
 
 /// Defines possible results from a command execution.
@@ -18,4 +92,6 @@ pub enum CommandResult {
     Error(String),
     /// Command succeded but has no output.
     VoidSuccess,
+    /// Command succeded and modified the given number of rows.
+    RowsAffected(usize),
 }
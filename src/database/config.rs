@@ -1,6 +1,7 @@
 //! The database config. For now, everything is hardcoded.
 
-/// The default table name when no table is supplied.
-pub const DEFAULT_TABLE: &str = "test_table";
 /// The default directory where the data (tables) of the SteelDB is saved.
 pub const DATA_DIR: &str = ".steeldb/data";
+/// The maximum number of times the recursive select of a WITH RECURSIVE query is executed,
+/// so that a query that never stops adding rows fails instead of looping forever.
+pub const MAX_RECURSIVE_ITERATIONS: usize = 1000;
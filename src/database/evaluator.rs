@@ -0,0 +1,718 @@
+//! Evaluates parsed expressions against the rows of an [InMemoryTable].
+use crate::database::functions::lookup;
+use crate::database::in_memory_table::InMemoryTable;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use steeldb_core::{DataType, RowKey};
+use steeldb_parser::{
+    AggregateFunction, ArithmeticOperator, ComparisonOperator, Expression, OrderBy, SelectItem,
+};
+
+/// The errors that might happen when evaluating an expression.
+/// This is forwarded back by the VirtualMachine, through its `Debug` formatting.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum EvaluationError {
+    /// The operands have types that cannot be used together, or an expression
+    /// was used where a value of another kind was expected.
+    TypeMismatch(String),
+    /// The expression references a column that is not in the table.
+    ColumnNotFound(String),
+    /// The result does not fit in its type, such as a sum that overflows an i32.
+    OutOfRange(String),
+    /// A division or modulo by zero.
+    DivisionByZero(String),
+    /// No scalar function has the given name.
+    UnknownFunction(String),
+    /// A function received more or fewer arguments than it accepts.
+    WrongNumberOfArguments(String),
+    /// An aggregate was used where its rows were not grouped.
+    MisplacedAggregate(String),
+    /// An unqualified column name matches a column in more than one of the joined tables.
+    AmbiguousColumn(String),
+    /// A subquery failed, returned more than a single value, or was used where it cannot be evaluated.
+    Subquery(String),
+    /// The query breaks a rule the grammar alone cannot express, such as an ungrouped column in a grouped select.
+    InvalidQuery(String),
+}
+
+/// Finds the column of the table that a name refers to.
+/// Besides exact matches, a name qualified by the table name refers to its unqualified column,
+/// and an unqualified name refers to the only qualified column with that name, e.g. `x` to `a.x`.
+pub fn resolve_column(table: &InMemoryTable, name: &str) -> Result<String, EvaluationError> {
+    if table.fields.contains_key(name) {
+        return Ok(name.to_string());
+    }
+    if let Some((qualifier, column)) = name.split_once('.') {
+        if qualifier == table.name && table.fields.contains_key(column) {
+            return Ok(column.to_string());
+        }
+        return Err(EvaluationError::ColumnNotFound(name.to_string()));
+    }
+    let mut candidates = table.fields.keys().filter(|field| {
+        return field.split_once('.').map(|(_, column)| column) == Some(name);
+    });
+    match (candidates.next(), candidates.next()) {
+        (Some(field), None) => return Ok(field.clone()),
+        (Some(_), Some(_)) => return Err(EvaluationError::AmbiguousColumn(name.to_string())),
+        (None, _) => return Err(EvaluationError::ColumnNotFound(name.to_string())),
+    }
+}
+
+/// Rewrites the column references of an expression into the names of the table columns they refer to,
+/// see [resolve_column]. Aggregates and subqueries that were already computed into a column are kept as they are.
+/// The branches of a CASE are also checked to have compatible types, even if no row reaches them.
+pub fn resolve(
+    expression: &Expression,
+    table: &InMemoryTable,
+) -> Result<Expression, EvaluationError> {
+    match expression {
+        Expression::Column(name) => return Ok(Expression::Column(resolve_column(table, name)?)),
+        _ if table.fields.contains_key(&expression.to_string()) => return Ok(expression.clone()),
+        Expression::Case(_, _, _) => {
+            let resolved = expression.map_children(|child| resolve(child, table))?;
+            infer_type(&resolved, table)?;
+            return Ok(resolved);
+        }
+        _ => return expression.map_children(|child| resolve(child, table)),
+    }
+}
+
+/// Evaluates an expression into a value, for the given row of the table.
+pub fn evaluate(
+    expression: &Expression,
+    table: &InMemoryTable,
+    row: usize,
+) -> Result<DataType, EvaluationError> {
+    match expression {
+        Expression::Literal(value) => return Ok(value.clone()),
+        Expression::Column(name) => match table.columns.get(name) {
+            Some(column) => return Ok(column[row].clone()),
+            None => return Err(EvaluationError::ColumnNotFound(name.clone())),
+        },
+        // aggregates are computed beforehand, into a column named after them
+        Expression::Aggregate(_, _, _) => match table.columns.get(&expression.to_string()) {
+            Some(column) => return Ok(column[row].clone()),
+            None => return Err(EvaluationError::MisplacedAggregate(expression.to_string())),
+        },
+        // as are window functions
+        Expression::WindowFunction(_, _, _) => match table.columns.get(&expression.to_string()) {
+            Some(column) => return Ok(column[row].clone()),
+            None => return Err(misplaced_window_function(expression)),
+        },
+        // and subqueries
+        Expression::Subquery(_) => return evaluated_subquery(expression, table, row),
+        Expression::Parameter(_) => return Err(unbound_parameter(expression)),
+        Expression::Arithmetic(left, operator, right) => {
+            let left = evaluate(left, table, row)?;
+            let right = evaluate(right, table, row)?;
+            return arithmetic(expression, left, operator, right);
+        }
+        Expression::Case(operand, branches, default) => {
+            let operand = match operand {
+                Some(operand) => Some(evaluate(operand, table, row)?),
+                None => None,
+            };
+            for (condition, value) in branches.iter() {
+                let matched = match &operand {
+                    Some(DataType::Null) => false,
+                    Some(operand) => match evaluate(condition, table, row)? {
+                        DataType::Null => false,
+                        candidate => compare(operand, &candidate)? == Some(Ordering::Equal),
+                    },
+                    None => evaluate_condition(condition, table, row)? == Some(true),
+                };
+                if matched {
+                    return evaluate(value, table, row);
+                }
+            }
+            match default {
+                Some(default) => return evaluate(default, table, row),
+                None => return Ok(DataType::Null),
+            }
+        }
+        Expression::Function(name, arguments) => {
+            let function = lookup(name)?;
+            let mut values = Vec::<DataType>::new();
+            for argument in arguments.iter() {
+                values.push(evaluate(argument, table, row)?);
+            }
+            return function.call(&values);
+        }
+        Expression::Negate(inner) => match evaluate(inner, table, row)? {
+            DataType::Integer32(i) => match i.checked_neg() {
+                Some(negated) => return Ok(DataType::Integer32(negated)),
+                None => return Err(out_of_range(expression)),
+            },
+            DataType::Float32(f) => return Ok(DataType::Float32(-f)),
+            DataType::Null => return Ok(DataType::Null),
+            value => {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "Cannot negate {}",
+                    value.name()
+                )));
+            }
+        },
+        _ => {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Expected a value, found boolean expression {:?}",
+                expression
+            )));
+        }
+    }
+}
+
+/// Evaluates an expression into a boolean, for the given row of the table.
+/// Rows for which the expression is unknown, because it compares NULLs, do not match.
+pub fn evaluate_predicate(
+    expression: &Expression,
+    table: &InMemoryTable,
+    row: usize,
+) -> Result<bool, EvaluationError> {
+    return Ok(evaluate_condition(expression, table, row)? == Some(true));
+}
+
+/// Evaluates an expression with the three-valued logic of SQL, where `None` is unknown.
+/// Comparing NULL with any value is unknown, and unknown propagates through NOT,
+/// while AND is false and OR is true as soon as one of their operands is.
+fn evaluate_condition(
+    expression: &Expression,
+    table: &InMemoryTable,
+    row: usize,
+) -> Result<Option<bool>, EvaluationError> {
+    match expression {
+        Expression::Comparison(left, operator, right) => {
+            let left = evaluate(left, table, row)?;
+            let right = evaluate(right, table, row)?;
+            if left == DataType::Null || right == DataType::Null {
+                return Ok(None);
+            }
+            let ordering = compare(&left, &right)?;
+            return Ok(Some(apply_comparison(ordering, operator)));
+        }
+        Expression::IsNull(inner, negated) => {
+            let is_null = evaluate(inner, table, row)? == DataType::Null;
+            return Ok(Some(is_null != *negated));
+        }
+        Expression::And(left, right) => {
+            let left = evaluate_condition(left, table, row)?;
+            let right = evaluate_condition(right, table, row)?;
+            match (left, right) {
+                (Some(false), _) | (_, Some(false)) => return Ok(Some(false)),
+                (Some(true), Some(true)) => return Ok(Some(true)),
+                _ => return Ok(None),
+            }
+        }
+        Expression::Or(left, right) => {
+            let left = evaluate_condition(left, table, row)?;
+            let right = evaluate_condition(right, table, row)?;
+            match (left, right) {
+                (Some(true), _) | (_, Some(true)) => return Ok(Some(true)),
+                (Some(false), Some(false)) => return Ok(Some(false)),
+                _ => return Ok(None),
+            }
+        }
+        Expression::Not(inner) => {
+            return Ok(evaluate_condition(inner, table, row)?.map(|value| !value));
+        }
+        Expression::Exists(_) | Expression::InSubquery(_, _, _) => {
+            let negated = matches!(expression, Expression::InSubquery(_, _, true));
+            match evaluated_subquery(expression, table, row)? {
+                DataType::Integer32(found) => return Ok(Some((found == 1) != negated)),
+                _ => return Ok(None),
+            }
+        }
+        _ => {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Expected a boolean expression, found {:?}",
+                expression
+            )));
+        }
+    }
+}
+
+/// Applies an arithmetic operator to two values, with NULL when either of them is NULL.
+/// Integers stay integers, dividing with truncation towards zero, and are widened into floats
+/// when the other operand is a float. An integer result that overflows an i32, or an infinite float
+/// computed from finite operands, is out of range. Dividing by zero is an error for both types.
+fn arithmetic(
+    expression: &Expression,
+    left: DataType,
+    operator: &ArithmeticOperator,
+    right: DataType,
+) -> Result<DataType, EvaluationError> {
+    let is_zero = match right {
+        DataType::Integer32(r) => r == 0,
+        DataType::Float32(r) => r == 0.0,
+        _ => false,
+    };
+    let is_division = matches!(
+        operator,
+        ArithmeticOperator::Divide | ArithmeticOperator::Modulo
+    );
+    match (left, right) {
+        (DataType::Null, _) | (_, DataType::Null) => return Ok(DataType::Null),
+        (DataType::Integer32(_) | DataType::Float32(_), _) if is_zero && is_division => {
+            return Err(EvaluationError::DivisionByZero(expression.to_string()));
+        }
+        (DataType::Integer32(l), DataType::Integer32(r)) => {
+            let result = match operator {
+                ArithmeticOperator::Add => l.checked_add(r),
+                ArithmeticOperator::Subtract => l.checked_sub(r),
+                ArithmeticOperator::Multiply => l.checked_mul(r),
+                ArithmeticOperator::Divide => l.checked_div(r),
+                ArithmeticOperator::Modulo => l.checked_rem(r),
+            };
+            match result {
+                Some(result) => return Ok(DataType::Integer32(result)),
+                None => return Err(out_of_range(expression)),
+            }
+        }
+        (DataType::Integer32(l), DataType::Float32(r)) => {
+            return float_arithmetic(expression, l as f32, operator, r);
+        }
+        (DataType::Float32(l), DataType::Integer32(r)) => {
+            return float_arithmetic(expression, l, operator, r as f32);
+        }
+        (DataType::Float32(l), DataType::Float32(r)) => {
+            return float_arithmetic(expression, l, operator, r);
+        }
+        (left, right) => {
+            let name = match left {
+                DataType::String(_) => left.name(),
+                _ => right.name(),
+            };
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Cannot apply {} to {}",
+                operator, name
+            )));
+        }
+    }
+}
+
+/// Applies an arithmetic operator to two floats, see [arithmetic].
+fn float_arithmetic(
+    expression: &Expression,
+    left: f32,
+    operator: &ArithmeticOperator,
+    right: f32,
+) -> Result<DataType, EvaluationError> {
+    let result = match operator {
+        ArithmeticOperator::Add => left + right,
+        ArithmeticOperator::Subtract => left - right,
+        ArithmeticOperator::Multiply => left * right,
+        ArithmeticOperator::Divide => left / right,
+        ArithmeticOperator::Modulo => left % right,
+    };
+    if result.is_infinite() && left.is_finite() && right.is_finite() {
+        return Err(out_of_range(expression));
+    }
+    return Ok(DataType::Float32(result));
+}
+
+fn out_of_range(expression: &Expression) -> EvaluationError {
+    return EvaluationError::OutOfRange(format!("{} is out of range", expression));
+}
+
+/// The error of a window function that was not computed beforehand, since it is only allowed
+/// in the SELECT and ORDER BY clauses.
+fn misplaced_window_function(expression: &Expression) -> EvaluationError {
+    return EvaluationError::TypeMismatch(format!(
+        "Window function cannot be used here: {}",
+        expression
+    ));
+}
+
+/// The error for a parameter without a value, as parameters are only bound by executing a prepared statement.
+fn unbound_parameter(expression: &Expression) -> EvaluationError {
+    return EvaluationError::TypeMismatch(format!(
+        "Parameter {} has no value, the statement must be prepared",
+        expression
+    ));
+}
+
+/// Reads the value of a subquery for the given row, from the column named after it.
+/// EXISTS and IN are stored as 1 when true, 0 when false and NULL when unknown.
+fn evaluated_subquery(
+    expression: &Expression,
+    table: &InMemoryTable,
+    row: usize,
+) -> Result<DataType, EvaluationError> {
+    match table.columns.get(&expression.to_string()) {
+        Some(column) => return Ok(column[row].clone()),
+        None => {
+            return Err(EvaluationError::Subquery(format!(
+                "Subquery cannot be used here: {}",
+                expression
+            )));
+        }
+    }
+}
+
+/// Infers the type of the values an expression evaluates into, without evaluating it.
+/// As in the table fields, the type is represented by a [DataType] holding a default value.
+pub fn infer_type(
+    expression: &Expression,
+    table: &InMemoryTable,
+) -> Result<DataType, EvaluationError> {
+    match expression {
+        Expression::Literal(value) => return Ok(default_value(value)),
+        Expression::Parameter(_) => return Err(unbound_parameter(expression)),
+        Expression::Column(name) => match table.fields.get(name) {
+            Some(data_type) => return Ok(default_value(data_type)),
+            None => return Err(EvaluationError::ColumnNotFound(name.clone())),
+        },
+        Expression::Aggregate(function, argument, _) => {
+            if let Some(data_type) = table.fields.get(&expression.to_string()) {
+                return Ok(default_value(data_type));
+            }
+            let argument_type = match argument {
+                Some(argument) => Some(infer_type(argument, table)?),
+                None => None,
+            };
+            return aggregate_type(function, argument_type);
+        }
+        Expression::WindowFunction(_, _, _) => match table.fields.get(&expression.to_string()) {
+            Some(data_type) => return Ok(default_value(data_type)),
+            None => return Err(misplaced_window_function(expression)),
+        },
+        Expression::Subquery(_) => match table.fields.get(&expression.to_string()) {
+            Some(data_type) => return Ok(default_value(data_type)),
+            None => {
+                return Err(EvaluationError::Subquery(format!(
+                    "Subquery cannot be used here: {}",
+                    expression
+                )));
+            }
+        },
+        Expression::Arithmetic(left, operator, right) => {
+            let left = infer_type(left, table)?;
+            let right = infer_type(right, table)?;
+            match (left, right) {
+                (DataType::String(_), _) | (_, DataType::String(_)) => {
+                    return Err(EvaluationError::TypeMismatch(format!(
+                        "Cannot apply {} to String",
+                        operator
+                    )));
+                }
+                (DataType::Integer32(_), DataType::Integer32(_)) => {
+                    return Ok(DataType::Integer32(0))
+                }
+                // the type of NULL is unknown, so the other operand decides
+                (DataType::Null, data_type) | (data_type, DataType::Null) => return Ok(data_type),
+                _ => return Ok(DataType::Float32(0.0)),
+            }
+        }
+        // the branches must have a common type, and the values of a simple CASE must be comparable with its operand
+        Expression::Case(operand, branches, default) => {
+            if let Some(operand) = operand {
+                let operand_type = infer_type(operand, table)?;
+                for (condition, _) in branches.iter() {
+                    let condition_type = infer_type(condition, table)?;
+                    if operand_type != DataType::Null && condition_type != DataType::Null {
+                        compare(&operand_type, &condition_type)?;
+                    }
+                }
+            }
+            let mut types = Vec::<DataType>::new();
+            for (_, value) in branches.iter() {
+                types.push(infer_type(value, table)?);
+            }
+            if let Some(default) = default {
+                types.push(infer_type(default, table)?);
+            }
+            return common_type(&types);
+        }
+        Expression::Function(name, arguments) => {
+            let function = lookup(name)?;
+            let mut types = Vec::<DataType>::new();
+            for argument in arguments.iter() {
+                types.push(infer_type(argument, table)?);
+            }
+            return function.result_type(&types);
+        }
+        Expression::Negate(inner) => match infer_type(inner, table)? {
+            DataType::String(_) => {
+                return Err(EvaluationError::TypeMismatch(
+                    "Cannot negate String".to_string(),
+                ));
+            }
+            data_type => return Ok(data_type),
+        },
+        _ => {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Expected a value, found boolean expression {:?}",
+                expression
+            )));
+        }
+    }
+}
+
+/// Returns the type of an aggregate, given the type of its argument.
+/// COUNT is always an i32 and AVG always a f32, SUM keeps the numeric type of its argument,
+/// while MIN and MAX work for any type.
+pub fn aggregate_type(
+    function: &AggregateFunction,
+    argument_type: Option<DataType>,
+) -> Result<DataType, EvaluationError> {
+    match (function, argument_type) {
+        (AggregateFunction::Count, _) => return Ok(DataType::Integer32(0)),
+        (_, None) => {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "{} expects an argument",
+                function
+            )));
+        }
+        (_, Some(DataType::String(_)))
+            if matches!(function, AggregateFunction::Sum | AggregateFunction::Avg) =>
+        {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Cannot apply {} to String",
+                function
+            )));
+        }
+        (AggregateFunction::Avg, _) => return Ok(DataType::Float32(0.0)),
+        (_, Some(data_type)) => return Ok(data_type),
+    }
+}
+
+/// Returns the default value of the given type: zero for numbers and the empty string.
+pub fn default_value(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::Null => return DataType::Null,
+        DataType::String(_) => return DataType::String(String::new()),
+        DataType::Integer32(_) => return DataType::Integer32(0),
+        DataType::Float32(_) => return DataType::Float32(0.0),
+    }
+}
+
+/// Returns the type that values of every given type can be converted into, see [coerce].
+/// Integers are widened into floats, and NULL takes any type.
+pub fn common_type(types: &[DataType]) -> Result<DataType, EvaluationError> {
+    let mut common = DataType::Null;
+    for argument in types.iter() {
+        common = match (&common, argument) {
+            (_, DataType::Null) => common,
+            (DataType::Null, _) | (DataType::Integer32(_), DataType::Float32(_)) => {
+                argument.clone()
+            }
+            (DataType::Float32(_), DataType::Integer32(_)) => common,
+            _ if common.name() == argument.name() => common,
+            _ => {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "Cannot mix {} with {}",
+                    common.name(),
+                    argument.name()
+                )));
+            }
+        };
+    }
+    return Ok(common);
+}
+
+/// Converts a value into the type of the target, used when storing values into a column.
+/// Integers are widened into floats, NULL is kept, and any other conversion is a type mismatch.
+pub fn coerce(value: DataType, target: &DataType) -> Result<DataType, EvaluationError> {
+    match (value, target) {
+        (DataType::Null, _) => return Ok(DataType::Null),
+        (DataType::Integer32(i), DataType::Float32(_)) => return Ok(DataType::Float32(i as f32)),
+        (value, target) => {
+            if value.name() == target.name() {
+                return Ok(value);
+            }
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Cannot convert {} to {}",
+                value.name(),
+                target.name()
+            )));
+        }
+    }
+}
+
+/// Compares two values. Integers and floats can be compared with each other,
+/// while strings can only be compared with strings.
+/// Returns `None` when the values are unordered, which happens when a float is NaN.
+pub fn compare(left: &DataType, right: &DataType) -> Result<Option<Ordering>, EvaluationError> {
+    match (left, right) {
+        (DataType::String(l), DataType::String(r)) => return Ok(Some(l.cmp(r))),
+        (DataType::Integer32(l), DataType::Integer32(r)) => return Ok(Some(l.cmp(r))),
+        (DataType::Float32(l), DataType::Float32(r)) => return Ok(l.partial_cmp(r)),
+        (DataType::Integer32(l), DataType::Float32(r)) => {
+            return Ok((*l as f64).partial_cmp(&(*r as f64)))
+        }
+        (DataType::Float32(l), DataType::Integer32(r)) => {
+            return Ok((*l as f64).partial_cmp(&(*r as f64)))
+        }
+        _ => {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "Cannot compare {} with {}",
+                left.name(),
+                right.name()
+            )));
+        }
+    }
+}
+
+/// Maps the result of a comparison into a boolean, according to the operator.
+/// Unordered values are only different from each other.
+fn apply_comparison(ordering: Option<Ordering>, operator: &ComparisonOperator) -> bool {
+    match ordering {
+        Some(ordering) => match operator {
+            ComparisonOperator::Equal => ordering == Ordering::Equal,
+            ComparisonOperator::NotEqual => ordering != Ordering::Equal,
+            ComparisonOperator::LessThan => ordering == Ordering::Less,
+            ComparisonOperator::LessThanOrEqual => ordering != Ordering::Greater,
+            ComparisonOperator::GreaterThan => ordering == Ordering::Greater,
+            ComparisonOperator::GreaterThanOrEqual => ordering != Ordering::Less,
+        },
+        None => *operator == ComparisonOperator::NotEqual,
+    }
+}
+
+/// Computes the items of a SELECT clause into a new table, holding one column per item, in the given order.
+/// The wildcard expands into every column of the table, which keep their unqualified names
+/// unless that would be ambiguous. Columns are copied, while any other expression is evaluated for every row.
+/// Each item is named as in [SelectItem::name], and two different items cannot have the same name.
+pub fn project(
+    table: &InMemoryTable,
+    items: &[SelectItem],
+) -> Result<InMemoryTable, EvaluationError> {
+    let mut selected = Vec::<(Expression, String)>::new();
+    for item in items.iter() {
+        match item {
+            SelectItem::Wildcard => {
+                for column in table.select_columns.iter() {
+                    let unqualified = match column.split_once('.') {
+                        Some((_, unqualified)) => unqualified,
+                        None => column,
+                    };
+                    let name = match resolve_column(table, unqualified) {
+                        Ok(resolved) if resolved == *column => unqualified.to_string(),
+                        _ => column.clone(),
+                    };
+                    selected.push((Expression::Column(column.clone()), name));
+                }
+            }
+            SelectItem::Expression(expression, _) => {
+                selected.push((resolve(expression, table)?, item.name()));
+            }
+        }
+    }
+
+    let mut result = InMemoryTable::new();
+    result.name = table.name.clone();
+    let mut sources = HashMap::<String, Expression>::new();
+    for (expression, name) in selected {
+        match sources.get(&name) {
+            Some(source) if *source != expression => {
+                return Err(EvaluationError::AmbiguousColumn(name));
+            }
+            Some(_) => {}
+            None => {
+                let (data_type, values) = match &expression {
+                    Expression::Column(column) => (
+                        table.fields.get(column).unwrap().clone(),
+                        table.columns.get(column).unwrap().clone(),
+                    ),
+                    _ => {
+                        // values are converted to the column type, as a function may return an integer
+                        // for some rows and a float for others
+                        let data_type = infer_type(&expression, table)?;
+                        let mut values = Vec::<DataType>::new();
+                        for row in 0..table.number_of_rows() {
+                            let value = evaluate(&expression, table, row)?;
+                            if value == DataType::Null || data_type == DataType::Null {
+                                values.push(value);
+                            } else {
+                                values.push(coerce(value, &data_type)?);
+                            }
+                        }
+                        (data_type, values)
+                    }
+                };
+                result.fields.insert(name.clone(), data_type);
+                result.columns.insert(name.clone(), values);
+                sources.insert(name.clone(), expression);
+            }
+        }
+        result.select_columns.push(name);
+    }
+    return Ok(result);
+}
+
+/// Returns a new table with only the rows for which the predicate holds.
+pub fn filter(
+    table: &InMemoryTable,
+    predicate: &Expression,
+) -> Result<InMemoryTable, EvaluationError> {
+    let predicate = resolve(predicate, table)?;
+    let mut rows = Vec::<usize>::new();
+    for row in 0..table.number_of_rows() {
+        if evaluate_predicate(&predicate, table, row)? {
+            rows.push(row);
+        }
+    }
+    return Ok(table.select_rows(&rows));
+}
+
+/// Returns a new table without duplicate rows, keeping the first of each set of rows with equal selected values.
+/// Values are compared as in [RowKey], so NULLs are equal to each other.
+pub fn distinct(table: &InMemoryTable) -> InMemoryTable {
+    let mut seen = HashSet::<RowKey>::new();
+    let mut rows = Vec::<usize>::new();
+    for row in 0..table.number_of_rows() {
+        let values = table
+            .select_columns
+            .iter()
+            .map(|column| table.columns.get(column).unwrap()[row].clone())
+            .collect();
+        if seen.insert(RowKey(values)) {
+            rows.push(row);
+        }
+    }
+    return table.select_rows(&rows);
+}
+
+/// Returns a new table with the rows sorted by the given keys, from the most to the least significant.
+/// Keys are compared with [DataType::total_cmp], and rows with equal keys keep their original order.
+/// NULLs are placed before or after the other values as requested, regardless of the direction.
+pub fn sort(table: &InMemoryTable, order_by: &[OrderBy]) -> Result<InMemoryTable, EvaluationError> {
+    let mut expressions = Vec::<Expression>::new();
+    for key in order_by.iter() {
+        expressions.push(resolve(&key.expression, table)?);
+    }
+    let mut keys = Vec::<Vec<DataType>>::new();
+    for row in 0..table.number_of_rows() {
+        let mut row_keys = Vec::<DataType>::new();
+        for expression in expressions.iter() {
+            row_keys.push(evaluate(expression, table, row)?);
+        }
+        keys.push(row_keys);
+    }
+
+    let mut rows: Vec<usize> = (0..table.number_of_rows()).collect();
+    rows.sort_by(|left, right| compare_keys(&keys[*left], &keys[*right], order_by));
+    return Ok(table.select_rows(&rows));
+}
+
+/// Compares the values of the sort keys of two rows, as described in [sort].
+pub fn compare_keys(left: &[DataType], right: &[DataType], order_by: &[OrderBy]) -> Ordering {
+    for (index, key) in order_by.iter().enumerate() {
+        let (left, right) = (&left[index], &right[index]);
+        let mut ordering = match (left, right) {
+            (DataType::Null, DataType::Null) => Ordering::Equal,
+            (DataType::Null, _) if key.nulls_first => Ordering::Less,
+            (DataType::Null, _) => Ordering::Greater,
+            (_, DataType::Null) if key.nulls_first => Ordering::Greater,
+            (_, DataType::Null) => Ordering::Less,
+            (left, right) => left.total_cmp(right),
+        };
+        if key.descending && left != &DataType::Null && right != &DataType::Null {
+            ordering = ordering.reverse();
+        }
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    return Ordering::Equal;
+}
@@ -0,0 +1,187 @@
+//! Describes the commands planned for a statement, as shown by EXPLAIN.
+use crate::database::command::Command;
+use crate::database::in_memory_table::InMemoryTable;
+use steeldb_core::DataType;
+use steeldb_parser::{AlterTableAction, Expression};
+
+/// Returns a table describing the commands in the order they would be executed, with a row per command.
+/// Its columns are the `step` number, starting at 1, the `operation` and its `detail`,
+/// such as the columns a scan reads or the predicate of a filter.
+/// The commands of a WITH clause follow the command that saves their result,
+/// with their operations indented by their depth.
+pub fn explain(commands: &[Command]) -> InMemoryTable {
+    let schema = vec![
+        ("step".to_string(), DataType::Integer32(0)),
+        ("operation".to_string(), DataType::String(String::new())),
+        ("detail".to_string(), DataType::String(String::new())),
+    ];
+    let mut table = InMemoryTable::from_schema(String::new(), schema);
+    let mut rows = Vec::<(String, String)>::new();
+    describe_commands(commands, 0, &mut rows);
+    for (step, (operation, detail)) in rows.into_iter().enumerate() {
+        let values = [
+            DataType::Integer32(step as i32 + 1),
+            DataType::String(operation),
+            DataType::String(detail),
+        ];
+        for (name, value) in ["step", "operation", "detail"].iter().zip(values) {
+            table.columns.get_mut(*name).unwrap().push(value);
+        }
+    }
+    return table;
+}
+
+/// Adds the operation and detail of each command to the rows, including the ones of nested commands.
+fn describe_commands(commands: &[Command], depth: usize, rows: &mut Vec<(String, String)>) {
+    for command in commands.iter() {
+        let (operation, detail) = describe(command);
+        rows.push((format!("{}{}", "  ".repeat(depth), operation), detail));
+        match command {
+            Command::With(_, _, nested) | Command::Explain(nested) => {
+                describe_commands(nested, depth + 1, rows);
+            }
+            Command::WithRecursive(_, _, initial, recursive, all) => {
+                describe_commands(initial, depth + 1, rows);
+                let operator = if *all { "UNION ALL" } else { "UNION" };
+                let detail = "with the rows added by the previous step".to_string();
+                rows.push((
+                    format!("{}Recursive {}", "  ".repeat(depth + 1), operator),
+                    detail,
+                ));
+                describe_commands(recursive, depth + 2, rows);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the operation of a command and its detail.
+fn describe(command: &Command) -> (String, String) {
+    let (operation, detail) = match command {
+        Command::SelectFrom(columns, table) => {
+            ("Scan", format!("{}, columns {}", table, columns.join(", ")))
+        }
+        Command::SingleRow => ("Single row", String::new()),
+        Command::SelectFromAs(references, table, alias) => {
+            // only the references qualified by the alias, or unqualified, may be columns of this table
+            let qualifier = format!("{}.", alias);
+            let columns: Vec<&String> = references
+                .iter()
+                .filter(|reference| reference.starts_with(&qualifier) || !reference.contains('.'))
+                .collect();
+            let columns = if columns.is_empty() {
+                "*".to_string()
+            } else {
+                join(&columns)
+            };
+            (
+                "Scan",
+                format!("{} AS {}, columns {}", table, alias, columns),
+            )
+        }
+        Command::Join(kind, condition) => ("Join", format!("{} ON {}", kind, condition)),
+        Command::SetOperation(operator, all) => {
+            let all = if *all { " ALL" } else { "" };
+            ("Set operation", format!("{}{}", operator, all))
+        }
+        Command::Filter(predicate) => ("Filter", predicate.to_string()),
+        Command::Project(items) => ("Project", join(items)),
+        Command::CreateTable(table, schema, if_not_exists) => {
+            let columns: Vec<&String> = schema.iter().map(|(name, _)| name).collect();
+            let if_not_exists = if *if_not_exists { " IF NOT EXISTS" } else { "" };
+            (
+                "Create table",
+                format!("{}{}, columns {}", table, if_not_exists, join(&columns)),
+            )
+        }
+        Command::Insert(table, columns, rows, _) => {
+            let columns = match columns {
+                Some(columns) => format!(" ({})", columns.join(", ")),
+                None => String::new(),
+            };
+            (
+                "Insert",
+                format!("{}{}, {} row(s)", table, columns, rows.len()),
+            )
+        }
+        Command::DropTable(table, if_exists) => {
+            let if_exists = if *if_exists { " IF EXISTS" } else { "" };
+            ("Drop table", format!("{}{}", table, if_exists))
+        }
+        Command::TruncateTable(table) => ("Truncate table", table.clone()),
+        Command::AlterTable(table, action) => {
+            let action = match action {
+                AlterTableAction::AddColumn(column, _) => format!("ADD COLUMN {}", column.name),
+                AlterTableAction::DropColumn(column) => format!("DROP COLUMN {}", column),
+                AlterTableAction::RenameColumn(old_name, new_name) => {
+                    format!("RENAME COLUMN {} TO {}", old_name, new_name)
+                }
+                AlterTableAction::RenameTable(new_name) => format!("RENAME TO {}", new_name),
+            };
+            ("Alter table", format!("{}, {}", table, action))
+        }
+        Command::Update(table, assignments, filter) => {
+            let assignments: Vec<String> = assignments
+                .iter()
+                .map(|(column, expression)| format!("{} = {}", column, expression))
+                .collect();
+            let detail = format!(
+                "{} SET {}{}",
+                table,
+                assignments.join(", "),
+                where_clause(filter)
+            );
+            ("Update", detail)
+        }
+        Command::Delete(table, filter) => ("Delete", format!("{}{}", table, where_clause(filter))),
+        Command::Aggregate(group_by, aggregates) => {
+            let mut detail = join(aggregates);
+            if !group_by.is_empty() {
+                detail = format!("{} GROUP BY {}", detail, join(group_by));
+            }
+            ("Aggregate", detail)
+        }
+        Command::Sort(order_by) => ("Sort", join(order_by)),
+        Command::Window(windows) => ("Window", join(windows)),
+        Command::Distinct => ("Distinct", String::new()),
+        Command::Limit(limit, offset) => {
+            let mut detail = match limit {
+                Some(limit) => format!("LIMIT {}", limit),
+                None => String::new(),
+            };
+            if *offset > 0 {
+                detail = format!("{} OFFSET {}", detail, offset).trim().to_string();
+            }
+            ("Limit", detail)
+        }
+        Command::EvaluateSubqueries(subqueries) => ("Subqueries", join(subqueries)),
+        Command::Qualify(alias) => ("Derived table", alias.clone()),
+        Command::With(name, columns, _) | Command::WithRecursive(name, columns, _, _, _) => {
+            let mut detail = name.clone();
+            if !columns.is_empty() {
+                detail = format!("{} ({})", detail, columns.join(", "));
+            }
+            match command {
+                Command::With(..) => ("With", detail),
+                _ => ("With recursive", detail),
+            }
+        }
+        Command::Explain(_) => ("Explain", String::new()),
+        Command::Invalid(message) => ("Invalid", message.clone()),
+    };
+    return (operation.to_string(), detail);
+}
+
+/// Formats the values, separated by commas.
+fn join<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    return values.join(", ");
+}
+
+/// Formats the optional predicate of a WHERE clause.
+fn where_clause(filter: &Option<Expression>) -> String {
+    match filter {
+        Some(predicate) => return format!(" WHERE {}", predicate),
+        None => return String::new(),
+    }
+}
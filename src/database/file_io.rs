@@ -7,27 +7,37 @@ use steeldb_core::DataType;
 /// Defines the string 'TABLE COLUMNAR FORMAT HEADER\n' that goes to the top of the columnar file.
 const COLUMNAR_HEADER: [u8; 29] = *b"TABLE COLUMNAR FORMAT HEADER\n";
 
+/// Defines the line '\N' that stores a NULL value, in a column of any type.
+/// A string starting with a backslash is stored with one more, so that it is never read as NULL.
+const NULL_VALUE: &str = "\\N";
+
 // Traits
 /// The public interface of a table Writer. Used for dynamic dispatching in runtime.
 pub trait Writer {
     /// Write table to disk.
+    /// Fields listed in `column_order` are written first, in that order.
     fn write(
         &self,
         fields: &HashMap<String, DataType>,
         columns: &HashMap<String, Vec<DataType>>,
+        column_order: &[String],
         file_: File,
     ) -> Result<usize, std::io::Error>;
-    /// Append data to disk.
+    /// Append rows to a table already on disk, without rewriting the stored ones.
+    /// Fields listed in `column_order` are written first, in that order.
     fn append(
         &self,
         fields: &HashMap<String, DataType>,
         columns: &HashMap<String, Vec<DataType>>,
+        column_order: &[String],
         file_: File,
     ) -> Result<usize, std::io::Error>;
 }
 
 /// The errors that might happen when reading a table from disk.
 /// This implicitly defines errors for ColumnarFormat only.
+/// The payloads are only consumed through the `Debug` formatting, when the error is forwarded.
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum ReadError {
     /// The table has an invalid size.
@@ -40,13 +50,34 @@ pub enum ReadError {
     /// Generic fallback standard I/O error.
     StdIoError(std::io::Error),
 }
+/// The table data returned by a Reader: the fields (schema) and the columns.
+pub type TableData = (HashMap<String, DataType>, HashMap<String, Vec<DataType>>);
+
 /// The public interface of a table Reader. Used for dynamic dispatching in runtime.
 pub trait Reader {
-    fn read(
-        &self,
-        file_: File,
-        select_columns: Vec<String>,
-    ) -> Result<(HashMap<String, DataType>, HashMap<String, Vec<DataType>>), ReadError>;
+    /// Read the selected columns of a table from disk.
+    fn read(&self, file_: File, select_columns: Vec<String>) -> Result<TableData, ReadError>;
+    /// Read the table schema from disk, in the order the fields are stored.
+    fn read_schema(&self, file_: File) -> Result<Vec<(String, DataType)>, ReadError>;
+}
+
+/// Returns the field names in the order they should be written.
+/// Fields in `column_order` come first, the remaining ones follow sorted by name.
+fn ordered_field_names<'a>(
+    fields: &'a HashMap<String, DataType>,
+    column_order: &'a [String],
+) -> Vec<&'a String> {
+    let mut names: Vec<&String> = column_order
+        .iter()
+        .filter(|name| fields.contains_key(*name))
+        .collect();
+    let mut remaining: Vec<&String> = fields
+        .keys()
+        .filter(|name| !column_order.contains(name))
+        .collect();
+    remaining.sort();
+    names.extend(remaining);
+    return names;
 }
 
 // Writer Implementations
@@ -58,26 +89,19 @@ impl ColumnarWriter {
     pub fn new() -> Box<ColumnarWriter> {
         return Box::new(ColumnarWriter {});
     }
-}
-
-impl Writer for ColumnarWriter {
-    /// Write table to disk in columnar format.
-    fn write(
-        &self,
+    /// Writes a block for each field, made of its metadata line followed by a line per value.
+    fn write_fields(
         fields: &HashMap<String, DataType>,
         columns: &HashMap<String, Vec<DataType>>,
-        mut file_: File,
+        column_order: &[String],
+        file_: &mut File,
     ) -> Result<usize, std::io::Error> {
-        if fields.len() == 0 {
-            panic!("Cannot write empty table without schema - TODO: Handle this case, it should propagate an error and not panic");
-        }
-
         let mut written_bytes: usize = 0;
+        let empty_column = Vec::<DataType>::new();
 
-        written_bytes += file_.write(&COLUMNAR_HEADER)?;
-
-        for (key, value) in fields.iter() {
-            let column = columns.get(key).unwrap();
+        for key in ordered_field_names(fields, column_order) {
+            let value = fields.get(key).unwrap();
+            let column = columns.get(key).unwrap_or(&empty_column);
             let s = format!(
                 "Field name: {}; Type: {}; Number of elements: {}\n",
                 key,
@@ -89,6 +113,10 @@ impl Writer for ColumnarWriter {
 
             for value in column.iter() {
                 match value {
+                    DataType::String(str) if str.starts_with('\\') => {
+                        let s = format!("\\{}\n", str);
+                        written_bytes += file_.write(s.as_bytes())?;
+                    }
                     DataType::String(str) => {
                         let s = format!("{}\n", str);
                         written_bytes += file_.write(s.as_bytes())?;
@@ -101,20 +129,52 @@ impl Writer for ColumnarWriter {
                         let s = format!("{}\n", str);
                         written_bytes += file_.write(s.as_bytes())?;
                     }
+                    DataType::Null => {
+                        let s = format!("{}\n", NULL_VALUE);
+                        written_bytes += file_.write(s.as_bytes())?;
+                    }
                 }
             }
         }
+        return Ok(written_bytes);
+    }
+}
+
+impl Writer for ColumnarWriter {
+    /// Write table to disk in columnar format.
+    /// A field without a column is written as an empty column, e.g. for a newly created table.
+    fn write(
+        &self,
+        fields: &HashMap<String, DataType>,
+        columns: &HashMap<String, Vec<DataType>>,
+        column_order: &[String],
+        mut file_: File,
+    ) -> Result<usize, std::io::Error> {
+        if fields.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Cannot write table without schema",
+            ));
+        }
+
+        let mut written_bytes: usize = 0;
+        written_bytes += file_.write(&COLUMNAR_HEADER)?;
+
+        written_bytes += ColumnarWriter::write_fields(fields, columns, column_order, &mut file_)?;
 
         return Ok(written_bytes);
     }
-    /// Not implemented. Should append data to the columnar format.
+    /// Append rows to a table in columnar format, writing a block for each field after the stored ones.
+    /// The reader adds the rows of a field's later blocks to its first one, so the stored rows are never rewritten.
+    /// The file must be opened for appending, and the caller checks that the rows match the stored schema.
     fn append(
         &self,
-        _fields: &HashMap<String, DataType>,
-        _columns: &HashMap<String, Vec<DataType>>,
-        _file_: File,
+        fields: &HashMap<String, DataType>,
+        columns: &HashMap<String, Vec<DataType>>,
+        column_order: &[String],
+        mut file_: File,
     ) -> Result<usize, std::io::Error> {
-        return Ok(0 as usize);
+        return ColumnarWriter::write_fields(fields, columns, column_order, &mut file_);
     }
 }
 
@@ -174,7 +234,7 @@ impl ColumnarReader {
         let field_type = type_split.get(1).unwrap().replace(" ", "");
 
         // collect field name
-        let name_split: Vec<&str> = field_meta.get(0).unwrap().split(":").collect();
+        let name_split: Vec<&str> = field_meta.first().unwrap().split(":").collect();
         if name_split.len() != 2 {
             return Err(ReadError::InvalidFieldMeta(
                 "Could not split meta 'name'".to_string(),
@@ -188,21 +248,42 @@ impl ColumnarReader {
 
 impl Reader for ColumnarReader {
     /// The SimpleColumnar reader method.
-    fn read(
-        &self,
-        mut file_: File,
-        select_columns: Vec<String>,
-    ) -> Result<(HashMap<String, DataType>, HashMap<String, Vec<DataType>>), ReadError> {
-        // Prepare return output
-        let mut fields = HashMap::<String, DataType>::new();
-        let mut columns = HashMap::<String, Vec<DataType>>::new();
-
+    fn read(&self, mut file_: File, select_columns: Vec<String>) -> Result<TableData, ReadError> {
         // Read file
         let mut buffer = String::new();
         let result = file_.read_to_string(&mut buffer);
         if result.is_err() {
             return Err(ReadError::StdIoError(result.unwrap_err()));
         }
+        let (_, table_data) = ColumnarReader::parse(&buffer, Some(&select_columns))?;
+        return Ok(table_data);
+    }
+    /// Reads only the field metadata, skipping over the data blocks.
+    fn read_schema(&self, mut file_: File) -> Result<Vec<(String, DataType)>, ReadError> {
+        let mut buffer = String::new();
+        let result = file_.read_to_string(&mut buffer);
+        if result.is_err() {
+            return Err(ReadError::StdIoError(result.unwrap_err()));
+        }
+        let (schema, _) = ColumnarReader::parse(&buffer, Some(&vec![]))?;
+        return Ok(schema);
+    }
+}
+
+impl ColumnarReader {
+    /// Parses the content of a SimpleColumnar file.
+    /// Returns the schema of all fields, in the order they are stored,
+    /// and the data of the selected columns. When `select_columns` is `None`, every column is collected.
+    /// A field has a further block for each append, whose rows follow the ones of its previous blocks.
+    fn parse(
+        buffer: &str,
+        select_columns: Option<&Vec<String>>,
+    ) -> Result<(Vec<(String, DataType)>, TableData), ReadError> {
+        // Prepare return output
+        let mut schema = Vec::<(String, DataType)>::new();
+        let mut fields = HashMap::<String, DataType>::new();
+        let mut columns = HashMap::<String, Vec<DataType>>::new();
+
         // if result
         let lines: Vec<&str> = buffer.split("\n").collect();
         if lines.len() < 2 {
@@ -228,24 +309,42 @@ impl Reader for ColumnarReader {
                 return Err(ReadError::InvalidFileSize);
             }
 
-            // collect data only if requested
-            if select_columns.contains(&field_name) {
-                let dtype: DataType;
-                if field_type == "i32" {
-                    dtype = DataType::Integer32(0);
-                } else if field_type == "f32" {
-                    dtype = DataType::Float32(0.0);
-                } else {
-                    dtype = DataType::String(field_name.to_string());
+            let dtype: DataType;
+            if field_type == "i32" {
+                dtype = DataType::Integer32(0);
+            } else if field_type == "f32" {
+                dtype = DataType::Float32(0.0);
+            } else {
+                dtype = DataType::String(field_name.to_string());
+            }
+            // the rows appended later are stored in further blocks of the same fields
+            match schema.iter().find(|(name, _)| *name == field_name) {
+                Some((_, stored)) if stored.name() != dtype.name() => {
+                    return Err(ReadError::InvalidFieldMeta(format!(
+                        "Error at line: {}. Field {} was stored with type {}",
+                        line - 1,
+                        field_name,
+                        stored.name()
+                    )));
                 }
+                Some(_) => {}
+                None => schema.push((field_name.to_string(), dtype.clone())),
+            }
 
+            // collect data only if requested
+            let is_selected = match select_columns {
+                Some(select_columns) => select_columns.contains(&field_name),
+                None => true,
+            };
+            if is_selected {
                 fields.insert(field_name.to_string(), dtype);
-                columns.insert(field_name.to_string(), vec![]);
-                let column = columns.get_mut(&field_name).unwrap();
+                let column = columns.entry(field_name.to_string()).or_default();
                 for i in line..block_end {
                     let line = lines.get(i as usize).unwrap();
                     let val: DataType;
-                    if field_type == "i32" {
+                    if *line == NULL_VALUE {
+                        val = DataType::Null;
+                    } else if field_type == "i32" {
                         let result = line.parse::<i32>();
                         if result.is_err() {
                             return Err(ReadError::FieldParseError(format!(
@@ -264,6 +363,8 @@ impl Reader for ColumnarReader {
                         }
                         val = DataType::Float32(result.unwrap());
                     } else {
+                        // the backslash added to a string starting with one is dropped
+                        let line = line.strip_prefix('\\').unwrap_or(line);
                         val = DataType::String(line.to_string());
                     }
                     column.push(val);
@@ -276,12 +377,12 @@ impl Reader for ColumnarReader {
                 break;
             }
             let unwrapped_line = lines.get(line as usize).unwrap();
-            if unwrapped_line.len() == 0 {
+            if unwrapped_line.is_empty() {
                 break;
             }
 
             // Read next field metadata
-            let result = ColumnarReader::read_metadata(&unwrapped_line, line);
+            let result = ColumnarReader::read_metadata(unwrapped_line, line);
             if result.is_err() {
                 return Err(result.unwrap_err());
             }
@@ -290,6 +391,6 @@ impl Reader for ColumnarReader {
             line += 1;
         }
 
-        return Ok((fields, columns));
+        return Ok((schema, (fields, columns)));
     }
 }
@@ -0,0 +1,478 @@
+//! The scalar functions, which compute a value from the values of their arguments, e.g. `UPPER(name)`.
+//!
+//! Functions are found by name in a registry, which knows how many arguments they take and of which types.
+//! The parser accepts any function name, so the calls are checked before executing a query, see [validate],
+//! while the types of the arguments are checked when the calls are typed or evaluated.
+use crate::database::command::Command;
+use crate::database::evaluator::{coerce, common_type, EvaluationError};
+use crate::database::parser::subquery_commands;
+use steeldb_core::DataType;
+use steeldb_parser::{Expression, SelectItem};
+
+/// The types of values a parameter accepts. NULL is accepted by every parameter.
+#[derive(Clone, Copy)]
+enum Parameter {
+    /// A String.
+    Text,
+    /// An i32.
+    Integer,
+    /// An i32 or a f32.
+    Number,
+    /// A value of any type.
+    Any,
+}
+
+impl Parameter {
+    /// Whether the parameter accepts a value of the given type.
+    fn accepts(&self, value: &DataType) -> bool {
+        match (self, value) {
+            (_, DataType::Null) | (Parameter::Any, _) => return true,
+            (Parameter::Text, DataType::String(_)) => return true,
+            (Parameter::Integer, DataType::Integer32(_)) => return true,
+            (Parameter::Number, DataType::Integer32(_) | DataType::Float32(_)) => return true,
+            _ => return false,
+        }
+    }
+
+    /// Describes the accepted types, for error messages.
+    fn description(&self) -> &'static str {
+        match self {
+            Parameter::Text => return "String",
+            Parameter::Integer => return "i32",
+            Parameter::Number => return "a number",
+            Parameter::Any => return "any value",
+        }
+    }
+}
+
+/// A function of the registry.
+pub struct ScalarFunction {
+    /// The name of the function, in upper case.
+    name: &'static str,
+    /// The parameters of the function, in order.
+    parameters: &'static [Parameter],
+    /// How many of the last parameters may be omitted.
+    optional: usize,
+    /// Whether the last parameter may be repeated any number of times.
+    variadic: bool,
+    /// Whether the function returns NULL as soon as one of its arguments is NULL.
+    /// Otherwise the function receives the NULL arguments, as COALESCE does.
+    strict: bool,
+    /// Computes the type of the result from the types of the arguments, once they are accepted.
+    result_type: fn(&[DataType]) -> Result<DataType, EvaluationError>,
+    /// Computes the result from the arguments, once they are accepted.
+    call: fn(&[DataType]) -> Result<DataType, EvaluationError>,
+}
+
+/// The registry of every scalar function.
+const FUNCTIONS: &[ScalarFunction] = &[
+    ScalarFunction {
+        name: "UPPER",
+        parameters: &[Parameter::Text],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: text_type,
+        call: upper,
+    },
+    ScalarFunction {
+        name: "LOWER",
+        parameters: &[Parameter::Text],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: text_type,
+        call: lower,
+    },
+    ScalarFunction {
+        name: "LENGTH",
+        parameters: &[Parameter::Text],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: integer_type,
+        call: length,
+    },
+    ScalarFunction {
+        name: "SUBSTR",
+        parameters: &[Parameter::Text, Parameter::Integer, Parameter::Integer],
+        optional: 1,
+        variadic: false,
+        strict: true,
+        result_type: text_type,
+        call: substr,
+    },
+    ScalarFunction {
+        name: "TRIM",
+        parameters: &[Parameter::Text],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: text_type,
+        call: trim,
+    },
+    ScalarFunction {
+        name: "REPLACE",
+        parameters: &[Parameter::Text, Parameter::Text, Parameter::Text],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: text_type,
+        call: replace,
+    },
+    ScalarFunction {
+        name: "ABS",
+        parameters: &[Parameter::Number],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: first_argument_type,
+        call: abs,
+    },
+    ScalarFunction {
+        name: "ROUND",
+        parameters: &[Parameter::Number, Parameter::Integer],
+        optional: 1,
+        variadic: false,
+        strict: true,
+        result_type: first_argument_type,
+        call: round,
+    },
+    ScalarFunction {
+        name: "FLOOR",
+        parameters: &[Parameter::Number],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: first_argument_type,
+        call: floor,
+    },
+    ScalarFunction {
+        name: "CEIL",
+        parameters: &[Parameter::Number],
+        optional: 0,
+        variadic: false,
+        strict: true,
+        result_type: first_argument_type,
+        call: ceil,
+    },
+    ScalarFunction {
+        name: "COALESCE",
+        parameters: &[Parameter::Any],
+        optional: 0,
+        variadic: true,
+        strict: false,
+        result_type: common_type,
+        call: coalesce,
+    },
+];
+
+/// Finds a function of the registry by its name, ignoring case.
+pub fn lookup(name: &str) -> Result<&'static ScalarFunction, EvaluationError> {
+    match FUNCTIONS
+        .iter()
+        .find(|function| function.name.eq_ignore_ascii_case(name))
+    {
+        Some(function) => return Ok(function),
+        None => return Err(EvaluationError::UnknownFunction(name.to_string())),
+    }
+}
+
+/// Checks that every select of the commands is valid, see [Command::Invalid], and that every function
+/// called by the commands exists and receives the right number of arguments,
+/// including the ones of their subqueries and WITH clauses.
+pub fn validate(commands: &[Command]) -> Result<(), EvaluationError> {
+    for command in commands.iter() {
+        match command {
+            Command::With(_, _, nested) | Command::Explain(nested) => validate(nested)?,
+            Command::Invalid(message) => {
+                return Err(EvaluationError::InvalidQuery(message.clone()));
+            }
+            Command::WithRecursive(_, _, initial, recursive, _) => {
+                validate(initial)?;
+                validate(recursive)?;
+            }
+            _ => {}
+        }
+        for expression in command_expressions(command) {
+            validate_expression(expression)?;
+        }
+    }
+    return Ok(());
+}
+
+fn validate_expression(expression: &Expression) -> Result<(), EvaluationError> {
+    match expression {
+        Expression::Function(name, arguments) => lookup(name)?.check_arity(arguments.len())?,
+        Expression::Subquery(statement)
+        | Expression::Exists(statement)
+        | Expression::InSubquery(_, statement, _) => {
+            validate(&subquery_commands(*statement.clone()))?
+        }
+        _ => {}
+    }
+    for child in expression.children() {
+        validate_expression(child)?;
+    }
+    return Ok(());
+}
+
+/// Returns the expressions held by a command.
+fn command_expressions(command: &Command) -> Vec<&Expression> {
+    match command {
+        Command::Join(_, condition) | Command::Filter(condition) => return vec![condition],
+        Command::Project(items) => {
+            return items
+                .iter()
+                .filter_map(|item| match item {
+                    SelectItem::Wildcard => None,
+                    SelectItem::Expression(expression, _) => Some(expression),
+                })
+                .collect()
+        }
+        Command::Update(_, assignments, filter) => {
+            let mut expressions: Vec<&Expression> = assignments
+                .iter()
+                .map(|(_, expression)| expression)
+                .collect();
+            expressions.extend(filter.iter());
+            return expressions;
+        }
+        Command::Delete(_, filter) => return filter.iter().collect(),
+        Command::Aggregate(group_by, aggregates) => {
+            return group_by.iter().chain(aggregates.iter()).collect()
+        }
+        Command::Sort(order_by) => {
+            return order_by
+                .iter()
+                .map(|order_by| &order_by.expression)
+                .collect()
+        }
+        Command::EvaluateSubqueries(subqueries) | Command::Window(subqueries) => {
+            return subqueries.iter().collect()
+        }
+        _ => return vec![],
+    }
+}
+
+impl ScalarFunction {
+    /// Checks that the function accepts the given number of arguments.
+    pub fn check_arity(&self, count: usize) -> Result<(), EvaluationError> {
+        let minimum = self.parameters.len() - self.optional;
+        let maximum = self.parameters.len();
+        if count >= minimum && (count <= maximum || self.variadic) {
+            return Ok(());
+        }
+        let expected = if self.variadic {
+            format!("at least {}", minimum)
+        } else if minimum == maximum {
+            format!("{}", minimum)
+        } else {
+            format!("{} to {}", minimum, maximum)
+        };
+        return Err(EvaluationError::WrongNumberOfArguments(format!(
+            "{} expects {} argument(s), found {}",
+            self.name, expected, count
+        )));
+    }
+
+    /// Checks the number and the types of the arguments, given as values or as the default values of their types,
+    /// returning the type of the result.
+    pub fn result_type(&self, arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+        self.check_arity(arguments.len())?;
+        for (index, argument) in arguments.iter().enumerate() {
+            let parameter = self.parameters[index.min(self.parameters.len() - 1)];
+            if !parameter.accepts(argument) {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "{} expects {} as argument {}, found {}",
+                    self.name,
+                    parameter.description(),
+                    index + 1,
+                    argument.name()
+                )));
+            }
+        }
+        return (self.result_type)(arguments);
+    }
+
+    /// Calls the function, after checking its arguments.
+    pub fn call(&self, arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+        let result_type = self.result_type(arguments)?;
+        if self.strict && arguments.contains(&DataType::Null) {
+            return Ok(DataType::Null);
+        }
+        let result = (self.call)(arguments)?;
+        if result == DataType::Null || result_type == DataType::Null {
+            return Ok(result);
+        }
+        return coerce(result, &result_type);
+    }
+}
+
+fn text_type(_: &[DataType]) -> Result<DataType, EvaluationError> {
+    return Ok(DataType::String(String::new()));
+}
+
+fn integer_type(_: &[DataType]) -> Result<DataType, EvaluationError> {
+    return Ok(DataType::Integer32(0));
+}
+
+fn first_argument_type(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    return Ok(arguments[0].clone());
+}
+
+/// The error for arguments that the registry should have rejected.
+fn unexpected_arguments(arguments: &[DataType]) -> EvaluationError {
+    return EvaluationError::TypeMismatch(format!("Unexpected arguments {:?}", arguments));
+}
+
+fn upper(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::String(s)] => return Ok(DataType::String(s.to_uppercase())),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+fn lower(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::String(s)] => return Ok(DataType::String(s.to_lowercase())),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+/// The number of characters of a string.
+fn length(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::String(s)] => return Ok(DataType::Integer32(s.chars().count() as i32)),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+/// The characters of a string from a position, counted from 1, up to an optional length.
+/// Positions before the first character are counted, but select nothing.
+fn substr(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    let (s, start, length) = match arguments {
+        [DataType::String(s), DataType::Integer32(start)] => (s, *start as i64, None),
+        [DataType::String(s), DataType::Integer32(start), DataType::Integer32(length)] => {
+            (s, *start as i64, Some(*length as i64))
+        }
+        _ => return Err(unexpected_arguments(arguments)),
+    };
+    if let Some(length) = length {
+        if length < 0 {
+            return Err(EvaluationError::OutOfRange(format!(
+                "SUBSTR length cannot be negative, found {}",
+                length
+            )));
+        }
+    }
+    let end = length.map(|length| start + length);
+    let substring = s
+        .chars()
+        .enumerate()
+        .filter(|(index, _)| {
+            let position = *index as i64 + 1;
+            return position >= start && end.is_none_or(|end| position < end);
+        })
+        .map(|(_, c)| c)
+        .collect();
+    return Ok(DataType::String(substring));
+}
+
+/// Removes the leading and trailing whitespace of a string.
+fn trim(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::String(s)] => return Ok(DataType::String(s.trim().to_string())),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+/// Replaces every occurrence of a string by another. Replacing the empty string changes nothing.
+fn replace(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::String(s), DataType::String(from), _] if from.is_empty() => {
+            return Ok(DataType::String(s.clone()))
+        }
+        [DataType::String(s), DataType::String(from), DataType::String(to)] => {
+            return Ok(DataType::String(s.replace(from.as_str(), to)))
+        }
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+fn abs(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::Integer32(i)] => match i.checked_abs() {
+            Some(absolute) => return Ok(DataType::Integer32(absolute)),
+            None => {
+                return Err(EvaluationError::OutOfRange(format!(
+                    "ABS({}) is out of range",
+                    i
+                )))
+            }
+        },
+        [DataType::Float32(f)] => return Ok(DataType::Float32(f.abs())),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+/// Rounds a number to the given number of decimal places, zero by default, with halves rounded away from zero.
+/// A negative number of places rounds to the left of the decimal point, e.g. `ROUND(1250, -2)` is 1300.
+fn round(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    let (value, places) = match arguments {
+        [value] => (value, 0),
+        [value, DataType::Integer32(places)] => (value, *places),
+        _ => return Err(unexpected_arguments(arguments)),
+    };
+    match value {
+        DataType::Integer32(i) if places >= 0 => return Ok(DataType::Integer32(*i)),
+        DataType::Integer32(i) => {
+            let factor = 10_i64
+                .checked_pow(places.unsigned_abs())
+                .unwrap_or(i64::MAX);
+            let magnitude = (i.unsigned_abs() as i64 + factor / 2) / factor * factor;
+            match i32::try_from(magnitude * i.signum() as i64) {
+                Ok(rounded) => return Ok(DataType::Integer32(rounded)),
+                Err(_) => {
+                    return Err(EvaluationError::OutOfRange(format!(
+                        "ROUND({}, {}) is out of range",
+                        i, places
+                    )))
+                }
+            }
+        }
+        DataType::Float32(f) => {
+            // f32 has no more than 38 decimal places on either side of the decimal point
+            let factor = 10_f64.powi(places.clamp(-38, 38));
+            return Ok(DataType::Float32(
+                ((*f as f64 * factor).round() / factor) as f32,
+            ));
+        }
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+fn floor(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::Integer32(i)] => return Ok(DataType::Integer32(*i)),
+        [DataType::Float32(f)] => return Ok(DataType::Float32(f.floor())),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+fn ceil(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    match arguments {
+        [DataType::Integer32(i)] => return Ok(DataType::Integer32(*i)),
+        [DataType::Float32(f)] => return Ok(DataType::Float32(f.ceil())),
+        _ => return Err(unexpected_arguments(arguments)),
+    }
+}
+
+/// The first argument that is not NULL, or NULL when they all are.
+fn coalesce(arguments: &[DataType]) -> Result<DataType, EvaluationError> {
+    let value = arguments
+        .iter()
+        .find(|argument| **argument != DataType::Null)
+        .cloned();
+    return Ok(value.unwrap_or(DataType::Null));
+}
@@ -4,7 +4,8 @@ use crate::database::config::DATA_DIR;
 use crate::database::file_io::{ColumnarReader, ColumnarWriter, Reader, Writer};
 use log::info;
 use std::collections::HashMap;
-use std::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
 use std::path::Path;
 use steeldb_core::{DataType, FileFormat, SaveMode, Table, TableErrors};
 
@@ -13,7 +14,7 @@ use steeldb_core::{DataType, FileFormat, SaveMode, Table, TableErrors};
 /// This is currently in a columnar format.
 /// Most of the exposed functionality here is a low level API meant to be used during the
 /// database development. It is not meant to be used directly by database users.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InMemoryTable {
     /// The table name, this is used as an identifier for retrieving the correct table.
     pub name: String,
@@ -48,6 +49,253 @@ impl InMemoryTable {
             select_columns: Vec::<String>::new(),
         }
     }
+    /// Returns a table with a single row and no columns, as read by a select without a FROM clause.
+    /// The row is held by a column outside of the schema, since the rows are counted from the columns,
+    /// so it can never be selected.
+    pub fn single_row() -> InMemoryTable {
+        let mut table = InMemoryTable::new();
+        table.columns.insert(String::new(), vec![DataType::Null]);
+        return table;
+    }
+    /// Deletes the table file from disk.
+    pub fn drop_table(table_name: &String, format: FileFormat) -> Result<(), TableErrors> {
+        let s = InMemoryTable::get_table_path(table_name, &format);
+        info!("Dropping table in format {:?} at path: {:?}", format, s);
+        let result = std::fs::remove_file(&s);
+        if result.is_err() {
+            let error = result.unwrap_err();
+            if error.kind() == ErrorKind::NotFound {
+                return Err(TableErrors::TableNotFound(table_name.clone()));
+            }
+            return Err(TableErrors::WriteError(format!("{:?}", error)));
+        }
+        return Ok(());
+    }
+    /// Renames the table file on disk.
+    pub fn rename_table(
+        table_name: &String,
+        new_name: &String,
+        format: FileFormat,
+    ) -> Result<(), TableErrors> {
+        let s = InMemoryTable::get_table_path(table_name, &format);
+        let new_s = InMemoryTable::get_table_path(new_name, &format);
+        info!("Renaming table at path {:?} to {:?}", s, new_s);
+        if !Path::new(&s).exists() {
+            return Err(TableErrors::TableNotFound(table_name.clone()));
+        }
+        if Path::new(&new_s).exists() {
+            return Err(TableErrors::TableAlreadyExists);
+        }
+        let result = std::fs::rename(&s, &new_s);
+        if result.is_err() {
+            return Err(TableErrors::WriteError(format!(
+                "{:?}",
+                result.unwrap_err()
+            )));
+        }
+        return Ok(());
+    }
+    /// Deletes all rows of the table, keeping its schema and fields order.
+    pub fn truncate_table(table_name: &String, format: FileFormat) -> Result<(), TableErrors> {
+        let schema = InMemoryTable::read_schema(table_name, format)?;
+        let table = InMemoryTable::from_schema(table_name.clone(), schema);
+        return table.replace(format);
+    }
+    /// Replaces the table stored on disk with this one.
+    /// The table is first written to a temporary file, which is then renamed over the existing one.
+    /// This way, a failure halfway leaves the previous file untouched.
+    pub fn replace(&self, format: FileFormat) -> Result<(), TableErrors> {
+        let s = InMemoryTable::get_table_path(&self.name, &format);
+        let temporary_path = format!("{}.tmp", s);
+        info!("Replacing table in format {:?} at path: {:?}", format, s);
+
+        let writer: Box<dyn Writer> = match format {
+            FileFormat::SimpleColumnar => ColumnarWriter::new(),
+        };
+        let f = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temporary_path);
+        if f.is_err() {
+            return Err(TableErrors::WriteError(format!("{:?}", f.unwrap_err())));
+        }
+        let write_result = writer.write(
+            &self.fields,
+            &self.columns,
+            &self.select_columns,
+            f.unwrap(),
+        );
+        if write_result.is_err() {
+            let _ = std::fs::remove_file(&temporary_path);
+            return Err(TableErrors::WriteError(format!(
+                "{:?}",
+                write_result.unwrap_err()
+            )));
+        }
+        let rename_result = std::fs::rename(&temporary_path, &s);
+        if rename_result.is_err() {
+            let _ = std::fs::remove_file(&temporary_path);
+            return Err(TableErrors::WriteError(format!(
+                "{:?}",
+                rename_result.unwrap_err()
+            )));
+        }
+        return Ok(());
+    }
+    /// Creates an empty table with the given schema.
+    /// The fields order is kept in `select_columns`, so it is also the order they are saved in.
+    pub fn from_schema(name: String, schema: Vec<(String, DataType)>) -> InMemoryTable {
+        let mut table = InMemoryTable::new();
+        table.name = name;
+        for (field_name, data_type) in schema {
+            table.select_columns.push(field_name.clone());
+            table.columns.insert(field_name.clone(), vec![]);
+            table.fields.insert(field_name, data_type);
+        }
+        return table;
+    }
+    /// Returns the number of rows held in memory.
+    pub fn number_of_rows(&self) -> usize {
+        match self.columns.values().next() {
+            Some(column) => column.len(),
+            None => 0,
+        }
+    }
+    /// Returns a copy of this table with only the given rows, in the given order.
+    pub fn select_rows(&self, rows: &[usize]) -> InMemoryTable {
+        let mut columns = HashMap::<String, Vec<DataType>>::new();
+        for (name, column) in self.columns.iter() {
+            let selected: Vec<DataType> = rows.iter().map(|row| column[*row].clone()).collect();
+            columns.insert(name.clone(), selected);
+        }
+        return InMemoryTable {
+            name: self.name.clone(),
+            fields: self.fields.clone(),
+            columns,
+            select_columns: self.select_columns.clone(),
+        };
+    }
+    /// Qualifies every column with the given table alias, so that `x` becomes `alias.x`.
+    /// This keeps the columns of joined tables apart, and the table takes the alias as its name.
+    pub fn qualify(self, alias: &str) -> InMemoryTable {
+        let qualified = |name: String| format!("{}.{}", alias, name);
+        return InMemoryTable {
+            name: alias.to_string(),
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(name, data_type)| (qualified(name), data_type))
+                .collect(),
+            columns: self
+                .columns
+                .into_iter()
+                .map(|(name, column)| (qualified(name), column))
+                .collect(),
+            select_columns: self.select_columns.into_iter().map(qualified).collect(),
+        };
+    }
+    /// Restricts the table to the given columns, dropping the others from memory.
+    pub fn project(mut self, select_columns: Vec<String>) -> Result<InMemoryTable, TableErrors> {
+        for column in select_columns.iter() {
+            if !self.fields.contains_key(column) {
+                return Err(TableErrors::ColumnNotFound(column.clone()));
+            }
+        }
+        self.fields.retain(|name, _| select_columns.contains(name));
+        self.columns.retain(|name, _| select_columns.contains(name));
+        self.select_columns = select_columns;
+        return Ok(self);
+    }
+    /// Picks up the correct reader for the format.
+    fn get_reader(format: &FileFormat) -> Box<dyn Reader> {
+        match format {
+            FileFormat::SimpleColumnar => ColumnarReader::new(),
+        }
+    }
+    /// Opens the table file for reading.
+    fn open_table_file(table_name: &String, format: &FileFormat) -> Result<File, TableErrors> {
+        let s = InMemoryTable::get_table_path(table_name, format);
+        let path = Path::new(&s);
+        info!("Loading table in format {:?} from path: {:?}", format, path);
+
+        let file_ = OpenOptions::new().read(true).open(path);
+        if file_.is_err() {
+            let error = file_.unwrap_err();
+            if error.kind() == ErrorKind::NotFound {
+                return Err(TableErrors::TableNotFound(table_name.clone()));
+            }
+            return Err(TableErrors::ReadError(format!("{:?}", error)));
+        }
+        return Ok(file_.unwrap());
+    }
+    /// Reads the table schema from disk, in the order the fields are stored.
+    pub fn read_schema(
+        table_name: &String,
+        format: FileFormat,
+    ) -> Result<Vec<(String, DataType)>, TableErrors> {
+        let reader = InMemoryTable::get_reader(&format);
+        let f = InMemoryTable::open_table_file(table_name, &format)?;
+        let result = reader.read_schema(f);
+        if result.is_err() {
+            let error = format!("{:?}", result.unwrap_err());
+            return Err(TableErrors::ReadError(error));
+        }
+        return Ok(result.unwrap());
+    }
+    /// Checks that the table has the given schema, with columns that all have the same size,
+    /// so that its rows may be appended to a stored table.
+    fn check_schema(&self, schema: &[(String, DataType)]) -> Result<(), TableErrors> {
+        let number_of_rows = self.number_of_rows();
+        let matches_schema = self.fields.len() == schema.len()
+            && schema.iter().all(|(name, data_type)| {
+                let same_type = self.fields.get(name).map(|f| f.name()) == Some(data_type.name());
+                let same_size = self.columns.get(name).map(|c| c.len()) == Some(number_of_rows);
+                same_type && same_size
+            });
+        if !matches_schema {
+            return Err(TableErrors::WriteError(
+                "Appended data does not match the table schema".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+    /// Reads every column of the table from disk.
+    /// The `select_columns` keep the order the fields are stored in,
+    /// so that saving the table back preserves it.
+    pub fn read_all(table_name: &String, format: FileFormat) -> Result<InMemoryTable, TableErrors> {
+        let schema = InMemoryTable::read_schema(table_name, format)?;
+        let select_columns = schema.into_iter().map(|(name, _)| name).collect();
+        return InMemoryTable::read(table_name.clone(), select_columns, format);
+    }
+    /// Reads the table from disk, returning the concrete type.
+    /// This is what the VirtualMachine uses, while [Table::load] exposes it as a trait object.
+    pub fn read(
+        table_name: String,
+        select_columns: Vec<String>,
+        format: FileFormat,
+    ) -> Result<InMemoryTable, TableErrors> {
+        let reader = InMemoryTable::get_reader(&format);
+        let f = InMemoryTable::open_table_file(&table_name, &format)?;
+        let result = reader.read(f, select_columns.clone());
+        if result.is_err() {
+            let error = format!("{:?}", result.unwrap_err());
+            return Err(TableErrors::ReadError(error));
+        }
+
+        let (fields, columns) = result.unwrap();
+        for select_col in select_columns.iter() {
+            if !fields.contains_key(select_col) {
+                return Err(TableErrors::ColumnNotFound(select_col.clone()));
+            }
+        }
+        return Ok(InMemoryTable {
+            name: table_name,
+            fields,
+            columns,
+            select_columns,
+        });
+    }
 }
 
 impl Table for InMemoryTable {
@@ -70,21 +318,26 @@ impl Table for InMemoryTable {
         );
 
         // Pick up correct writer
-        let writer: Box<dyn Writer>;
-        match format {
-            FileFormat::SimpleColumnar => {
-                writer = ColumnarWriter::new();
-            }
-        }
+        let writer: Box<dyn Writer> = match format {
+            FileFormat::SimpleColumnar => ColumnarWriter::new(),
+        };
         // Adapt to the given mode
         match mode {
             SaveMode::Overwrite => {
                 let f = OpenOptions::new().write(true).create_new(true).open(path);
                 if f.is_err() {
-                    println!("{:?}", f.unwrap_err());
-                    return Err(TableErrors::TableAlreadyExists);
+                    let error = f.unwrap_err();
+                    if error.kind() == ErrorKind::AlreadyExists {
+                        return Err(TableErrors::TableAlreadyExists);
+                    }
+                    return Err(TableErrors::WriteError(format!("{:?}", error)));
                 }
-                let write_result = writer.write(&self.fields, &self.columns, f.unwrap());
+                let write_result = writer.write(
+                    &self.fields,
+                    &self.columns,
+                    &self.select_columns,
+                    f.unwrap(),
+                );
                 if write_result.is_err() {
                     let s = format!("{:?}", write_result.unwrap_err());
                     return Err(TableErrors::WriteError(s));
@@ -92,17 +345,23 @@ impl Table for InMemoryTable {
             }
 
             SaveMode::Append => {
-                let f = OpenOptions::new()
-                    .append(true)
-                    .write(true)
-                    .create(false)
-                    .open(path);
+                // rows that do not match the stored schema are rejected before anything is written
+                let schema = InMemoryTable::read_schema(&self.name, format)?;
+                self.check_schema(&schema)?;
+                let f = OpenOptions::new().append(true).open(path);
                 if f.is_err() {
-                    println!("{:?}", f.unwrap_err());
-                    return Err(TableErrors::TableNotFound);
+                    return Err(TableErrors::WriteError(format!("{:?}", f.unwrap_err())));
                 }
-                let write_result = writer.append(&self.fields, &self.columns, f.unwrap());
+                let f = f.unwrap();
+                let length = f.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                let column_order: Vec<String> = schema.into_iter().map(|(name, _)| name).collect();
+                let write_result = writer.append(&self.fields, &self.columns, &column_order, f);
                 if write_result.is_err() {
+                    // the stored rows are kept by cutting off the partially written blocks
+                    if length > 0 {
+                        let f = OpenOptions::new().write(true).open(path);
+                        let _ = f.and_then(|f| f.set_len(length));
+                    }
                     let s = format!("{:?}", write_result.unwrap_err());
                     return Err(TableErrors::WriteError(s));
                 }
@@ -117,42 +376,7 @@ impl Table for InMemoryTable {
         select_columns: Vec<String>,
         format: FileFormat,
     ) -> Result<Box<dyn Table>, TableErrors> {
-        let s = InMemoryTable::get_table_path(&table_name, &format);
-        let path = Path::new(&s);
-        info!("Loading table in format {:?} from path: {:?}", format, path);
-
-        let reader: Box<dyn Reader>;
-        match format {
-            FileFormat::SimpleColumnar => reader = ColumnarReader::new(),
-        };
-
-        let file_ = OpenOptions::new().read(true).open(path);
-        if file_.is_err() {
-            let error = format!("{:?}", file_.unwrap_err());
-            println!("{:?}", error);
-            return Err(TableErrors::TableNotFound);
-        }
-
-        let f = file_.unwrap();
-        let result = reader.read(f, select_columns.clone());
-        if result.is_err() {
-            let error = format!("{:?}", result.unwrap_err());
-            println!("{:?}", error);
-            return Err(TableErrors::ReadError(error));
-        }
-
-        let (fields, columns) = result.unwrap();
-        for select_col in select_columns.iter() {
-            if !fields.contains_key(select_col) {
-                return Err(TableErrors::ColumnNotFound(select_col.clone()));
-            }
-        }
-        let table = InMemoryTable {
-            name: table_name,
-            fields,
-            columns,
-            select_columns,
-        };
+        let table = InMemoryTable::read(table_name, select_columns, format)?;
         return Ok(Box::new(table));
     }
 }
@@ -0,0 +1,188 @@
+//! Joins, which combine the rows of two [InMemoryTable]s.
+use crate::database::evaluator::{
+    evaluate, evaluate_predicate, infer_type, resolve, EvaluationError,
+};
+use crate::database::in_memory_table::InMemoryTable;
+use std::collections::HashMap;
+use steeldb_core::{DataType, RowKey};
+use steeldb_parser::{ComparisonOperator, Expression, JoinKind};
+
+/// Joins two tables, keeping the pairs of rows for which the condition holds.
+/// Outer joins also keep the rows of the outer tables that match no row of the other table,
+/// with NULL in the columns of the other table.
+/// The result has the columns of the left table followed by the ones of the right table,
+/// which must have distinct names, so the planner qualifies them with their table aliases.
+/// The result has no name, so its columns are only found through their qualified names.
+/// Rows are ordered by the left row first, followed by the unmatched rows of the right table.
+///
+/// When the condition compares columns of each table for equality, the rows of the right table
+/// are hashed by those columns, and only the pairs of rows with equal keys are evaluated.
+/// Otherwise, every pair of rows is evaluated.
+pub fn join(
+    left: &InMemoryTable,
+    right: &InMemoryTable,
+    kind: JoinKind,
+    condition: &Expression,
+) -> Result<InMemoryTable, EvaluationError> {
+    let mut schema = Vec::<(String, DataType)>::new();
+    for table in [left, right] {
+        for name in table.select_columns.iter() {
+            if schema.iter().any(|(other, _)| other == name) {
+                return Err(EvaluationError::AmbiguousColumn(name.clone()));
+            }
+            schema.push((name.clone(), table.fields.get(name).unwrap().clone()));
+        }
+    }
+    let mut candidates = InMemoryTable::from_schema(String::new(), schema.clone());
+    let condition = resolve(condition, &candidates)?;
+
+    let pairs = candidate_pairs(left, right, &condition)?;
+    for (left_row, right_row) in pairs.iter() {
+        push_row(
+            &mut candidates,
+            left,
+            Some(*left_row),
+            right,
+            Some(*right_row),
+        );
+    }
+    let mut matches = Vec::<(usize, usize)>::new();
+    for (row, pair) in pairs.into_iter().enumerate() {
+        if evaluate_predicate(&condition, &candidates, row)? {
+            matches.push(pair);
+        }
+    }
+
+    let keep_left = matches!(kind, JoinKind::Left | JoinKind::Full);
+    let keep_right = matches!(kind, JoinKind::Right | JoinKind::Full);
+    let mut joined = InMemoryTable::from_schema(String::new(), schema);
+    let mut right_matched = vec![false; right.number_of_rows()];
+    let mut matches = matches.into_iter().peekable();
+    for left_row in 0..left.number_of_rows() {
+        let mut matched = false;
+        while let Some((_, right_row)) = matches.next_if(|(row, _)| *row == left_row) {
+            push_row(&mut joined, left, Some(left_row), right, Some(right_row));
+            right_matched[right_row] = true;
+            matched = true;
+        }
+        if !matched && keep_left {
+            push_row(&mut joined, left, Some(left_row), right, None);
+        }
+    }
+    if keep_right {
+        for (right_row, matched) in right_matched.into_iter().enumerate() {
+            if !matched {
+                push_row(&mut joined, left, None, right, Some(right_row));
+            }
+        }
+    }
+    return Ok(joined);
+}
+
+/// Appends a row to the joined table, made of a row of each table.
+/// A missing row fills the columns of its table with NULL.
+fn push_row(
+    joined: &mut InMemoryTable,
+    left: &InMemoryTable,
+    left_row: Option<usize>,
+    right: &InMemoryTable,
+    right_row: Option<usize>,
+) {
+    for (table, row) in [(left, left_row), (right, right_row)] {
+        for (name, column) in table.columns.iter() {
+            let value = match row {
+                Some(row) => column[row].clone(),
+                None => DataType::Null,
+            };
+            joined.columns.get_mut(name).unwrap().push(value);
+        }
+    }
+}
+
+/// Returns the pairs of rows that may match the condition, as (left row, right row),
+/// ordered by the left row first.
+fn candidate_pairs(
+    left: &InMemoryTable,
+    right: &InMemoryTable,
+    condition: &Expression,
+) -> Result<Vec<(usize, usize)>, EvaluationError> {
+    let keys = equality_keys(condition, left, right)?;
+    let mut pairs = Vec::<(usize, usize)>::new();
+    if keys.is_empty() {
+        for left_row in 0..left.number_of_rows() {
+            for right_row in 0..right.number_of_rows() {
+                pairs.push((left_row, right_row));
+            }
+        }
+        return Ok(pairs);
+    }
+
+    let mut right_rows = HashMap::<RowKey, Vec<usize>>::new();
+    for row in 0..right.number_of_rows() {
+        let mut key = Vec::<DataType>::new();
+        for (_, right_key) in keys.iter() {
+            key.push(evaluate(right_key, right, row)?);
+        }
+        right_rows.entry(RowKey(key)).or_default().push(row);
+    }
+    for left_row in 0..left.number_of_rows() {
+        let mut key = Vec::<DataType>::new();
+        for (left_key, _) in keys.iter() {
+            key.push(evaluate(left_key, left, left_row)?);
+        }
+        if let Some(rows) = right_rows.get(&RowKey(key)) {
+            pairs.extend(rows.iter().map(|right_row| (left_row, *right_row)));
+        }
+    }
+    return Ok(pairs);
+}
+
+/// Finds the equalities between a column of each table that the condition requires,
+/// returned as (left column, right column).
+/// Hashing only narrows down the pairs of rows, the whole condition is still evaluated for each of them,
+/// so the keys must have comparable types for the type errors to be the same as without hashing.
+fn equality_keys(
+    condition: &Expression,
+    left: &InMemoryTable,
+    right: &InMemoryTable,
+) -> Result<Vec<(Expression, Expression)>, EvaluationError> {
+    let mut keys = Vec::<(Expression, Expression)>::new();
+    match condition {
+        Expression::And(first, second) => {
+            keys.extend(equality_keys(first, left, right)?);
+            keys.extend(equality_keys(second, left, right)?);
+        }
+        Expression::Comparison(first, ComparisonOperator::Equal, second) => {
+            if let (Expression::Column(first_name), Expression::Column(second_name)) =
+                (first.as_ref(), second.as_ref())
+            {
+                let key = if left.fields.contains_key(first_name)
+                    && right.fields.contains_key(second_name)
+                {
+                    Some((first.as_ref().clone(), second.as_ref().clone()))
+                } else if right.fields.contains_key(first_name)
+                    && left.fields.contains_key(second_name)
+                {
+                    Some((second.as_ref().clone(), first.as_ref().clone()))
+                } else {
+                    None
+                };
+                if let Some((left_key, right_key)) = key {
+                    let left_type = infer_type(&left_key, left)?;
+                    let right_type = infer_type(&right_key, right)?;
+                    let is_string = |data_type: &DataType| matches!(data_type, DataType::String(_));
+                    if is_string(&left_type) != is_string(&right_type) {
+                        return Err(EvaluationError::TypeMismatch(format!(
+                            "Cannot compare {} with {}",
+                            left_type.name(),
+                            right_type.name()
+                        )));
+                    }
+                    keys.push((left_key, right_key));
+                }
+            }
+        }
+        _ => {}
+    }
+    return Ok(keys);
+}
@@ -5,5 +5,6 @@ pub fn logger_init() {
         .filter_or("STEELDB_LOG_LEVEL", "info")
         .write_style_or("STEELDB_LOG_STYLE", "always");
 
-    env_logger::init_from_env(env);
+    // the logger is global, so it is kept when several databases are created
+    let _ = env_logger::try_init_from_env(env);
 }
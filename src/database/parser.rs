@@ -1,21 +1,349 @@
 //! Uses the [steeldb_parser] crate to map parsing into commands that will be executed by the VirtualMachine.
 
 use super::command::Command;
-use super::config::DEFAULT_TABLE;
-pub use steeldb_parser::{parse_select, ParseError};
+pub use steeldb_parser::{parse_statement, split_script, ParseError};
+use steeldb_parser::{
+    CreateTableStatement, Expression, InsertStatement, SelectItem, SelectStatement, Statement,
+    TableReference, With,
+};
 
 /// The entrypoint of the parser is the [parse] function.
+/// It parses the input into a [Statement] and maps it into the commands that execute it.
+/// The empty statements around it, as in `select a from t;;`, are skipped like in a script.
 pub fn parse(input: String) -> Result<Vec<Command>, ParseError> {
-    let result = parse_select(input);
+    if split_script(&input).len() == 1 {
+        match parse_script(input) {
+            Ok(mut scripts) => return Ok(scripts.remove(0)),
+            Err((_, error)) => return Err(error),
+        }
+    }
+    let result = parse_statement(input);
     match result {
-        Ok(columns) => {
-            return Ok(vec![Command::SelectFrom(
-                columns,
-                DEFAULT_TABLE.to_string(),
-            )]);
+        Ok(statement) => return Ok(statement_commands(statement)),
+        Err(error) => return Err(error),
+    }
+}
+
+/// Parses a statement whose values may be given by parameters, such as `?` or `$1`,
+/// mapping it into the commands that execute it, with the number of its parameters.
+/// The parameters are bound when the commands are executed, see [Statement](super::prepared_statement::Statement).
+pub fn parse_prepared(input: String) -> Result<(Vec<Command>, usize), ParseError> {
+    let statement = parse_statement(input)?;
+    let parameter_count = statement.parameter_count();
+    return Ok((statement_commands(statement), parameter_count));
+}
+
+/// Parses a script of several statements, see [steeldb_parser::parse_script],
+/// mapping each statement into the commands that execute it, in order.
+/// The error is returned with the index of the invalid statement, starting at 0.
+pub fn parse_script(input: String) -> Result<Vec<Vec<Command>>, (usize, ParseError)> {
+    let statements = steeldb_parser::parse_script(input)?;
+    return Ok(statements.into_iter().map(statement_commands).collect());
+}
+
+/// Maps a statement into the commands that execute it.
+fn statement_commands(statement: Statement) -> Vec<Command> {
+    match statement {
+        Statement::Select(statement) => {
+            return select_commands(*statement, false);
+        }
+        Statement::CreateTable(statement) => {
+            return create_table_commands(statement);
+        }
+        Statement::Insert(statement) => {
+            return insert_commands(statement);
+        }
+        Statement::DropTable(statement) => {
+            return vec![Command::DropTable(statement.table, statement.if_exists)];
+        }
+        Statement::TruncateTable(statement) => {
+            return vec![Command::TruncateTable(statement.table)];
+        }
+        Statement::AlterTable(statement) => {
+            return vec![Command::AlterTable(statement.table, statement.action)];
+        }
+        Statement::Update(statement) => {
+            return vec![Command::Update(
+                statement.table,
+                statement.assignments,
+                statement.filter,
+            )];
+        }
+        Statement::Delete(statement) => {
+            return vec![Command::Delete(statement.table, statement.filter)];
+        }
+        Statement::Explain(statement) => {
+            return vec![Command::Explain(statement_commands(*statement))];
+        }
+    }
+}
+
+/// Maps a subquery into the commands that execute it, see [select_commands].
+pub fn subquery_commands(statement: SelectStatement) -> Vec<Command> {
+    return select_commands(statement, true);
+}
+
+/// When the FROM clause is omitted, the select reads a single row without columns, see [Command::SingleRow].
+///
+/// A select is mapped into the following commands, where only the first one is always present:
+/// 1. [Command::SelectFrom] loads the columns referenced by any clause, or every column for `*`.
+///    Joined or aliased tables, derived tables and the tables of queries with subqueries are instead loaded
+///    by [Command::SelectFromAs], which ignores the columns of other queries, and combined by [Command::Join].
+/// 2. [Command::EvaluateSubqueries] and [Command::Filter] keep only the rows matching the WHERE clause.
+/// 3. [Command::Aggregate] groups the rows, when the select has a GROUP BY clause or aggregates.
+/// 4. [Command::EvaluateSubqueries] computes the subqueries of the SELECT, HAVING and ORDER BY clauses.
+/// 5. [Command::Filter] keeps only the groups matching the HAVING clause.
+/// 6. [Command::Window] computes the window functions of the SELECT and ORDER BY clauses.
+/// 7. [Command::Sort] orders the rows by the ORDER BY clause.
+/// 8. [Command::Limit] applies the OFFSET and LIMIT clauses.
+/// 9. [Command::Project] computes the columns given in the SELECT clause, named by their aliases.
+///
+/// With `SELECT DISTINCT`, [Command::Project] and then [Command::Distinct] instead run before [Command::Limit],
+/// so duplicates are removed from the selected columns before the rows are counted.
+///
+/// The aggregates are computed once for each group, for every clause that uses them.
+/// The columns of a subquery, which is `nested`, may refer to the enclosing queries.
+/// Selects combined by set operations are mapped by [set_operation_commands],
+/// and a WITH clause is mapped by [with_commands] before the commands of the select.
+/// A select that fails `SelectStatement::validate` is mapped into a single [Command::Invalid].
+fn select_commands(mut statement: SelectStatement, nested: bool) -> Vec<Command> {
+    if let Err(message) = statement.validate() {
+        return vec![Command::Invalid(message)];
+    }
+    if let Some(with) = statement.with.take() {
+        let mut commands = with_commands(with, nested);
+        commands.extend(select_commands(statement, nested));
+        return commands;
+    }
+    if !statement.set_operations.is_empty() {
+        return set_operation_commands(statement, nested);
+    }
+    let table = statement.table.clone();
+    let output_columns = statement.column_names();
+    let is_grouped = statement.is_grouped();
+    let is_plain = !nested && !statement.has_subqueries();
+
+    // the expressions that are evaluated after grouping, when the select is grouped
+    let mut output_expressions = Vec::<&Expression>::new();
+    let mut referenced_columns = Vec::<String>::new();
+    for item in statement.columns.iter() {
+        match item {
+            SelectItem::Wildcard => referenced_columns.push("*".to_string()),
+            SelectItem::Expression(expression, _) => output_expressions.push(expression),
+        }
+    }
+    output_expressions.extend(statement.having.iter());
+    output_expressions.extend(
+        statement
+            .order_by
+            .iter()
+            .map(|order_by| &order_by.expression),
+    );
+
+    let mut expressions = output_expressions.clone();
+    expressions.extend(statement.filter.iter());
+    expressions.extend(statement.group_by.iter());
+    if let Some(table) = &table {
+        expressions.extend(table.join_conditions());
+    }
+    for expression in expressions {
+        referenced_columns.extend(expression.referenced_columns());
+    }
+    let mut load_columns = Vec::<String>::new();
+    for column in referenced_columns {
+        if !load_columns.contains(&column) {
+            load_columns.push(column);
+        }
+    }
+
+    let mut aggregates = Vec::<Expression>::new();
+    let mut output_subqueries = Vec::<Expression>::new();
+    let mut windows = Vec::<Expression>::new();
+    for expression in output_expressions {
+        for window in expression.window_functions() {
+            if !windows.contains(window) {
+                windows.push(window.clone());
+            }
+        }
+        for aggregate in expression.aggregates() {
+            if !aggregates.contains(aggregate) {
+                aggregates.push(aggregate.clone());
+            }
         }
-        Err(error) => {
-            return Err(error);
+        for subquery in expression.subqueries() {
+            if !output_subqueries.contains(subquery) {
+                output_subqueries.push(subquery.clone());
+            }
         }
     }
+
+    let mut commands = Vec::<Command>::new();
+    let mut needs_projection = true;
+    match table {
+        Some(TableReference::Table(name, None)) if is_plain => {
+            // a column may be qualified by the name of the table
+            let qualifier = format!("{}.", name);
+            let mut columns = Vec::<String>::new();
+            for column in load_columns {
+                let column = match column.strip_prefix(&qualifier) {
+                    Some(unqualified) => unqualified.to_string(),
+                    None => column,
+                };
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+            // counting rows needs at least one column
+            if columns.is_empty() {
+                columns.push("*".to_string());
+            }
+            needs_projection = is_grouped || columns != output_columns;
+            commands.push(Command::SelectFrom(columns, name));
+        }
+        Some(table) => commands.extend(from_commands(table, &load_columns)),
+        None => commands.push(Command::SingleRow),
+    }
+    if let Some(filter) = statement.filter {
+        let subqueries: Vec<Expression> = filter.subqueries().into_iter().cloned().collect();
+        if !subqueries.is_empty() {
+            commands.push(Command::EvaluateSubqueries(subqueries));
+        }
+        commands.push(Command::Filter(filter));
+    }
+    if is_grouped {
+        let mut group_by = Vec::<Expression>::new();
+        for key in statement.group_by {
+            if !group_by.contains(&key) {
+                group_by.push(key);
+            }
+        }
+        commands.push(Command::Aggregate(group_by, aggregates));
+    }
+    if !output_subqueries.is_empty() {
+        commands.push(Command::EvaluateSubqueries(output_subqueries));
+    }
+    if let Some(having) = statement.having {
+        commands.push(Command::Filter(having));
+    }
+    if !windows.is_empty() {
+        commands.push(Command::Window(windows));
+    }
+    if !statement.order_by.is_empty() {
+        commands.push(Command::Sort(statement.order_by));
+    }
+    if statement.distinct {
+        if needs_projection {
+            commands.push(Command::Project(statement.columns.clone()));
+        }
+        commands.push(Command::Distinct);
+    }
+    if statement.limit.is_some() || statement.offset.is_some() {
+        commands.push(Command::Limit(
+            statement.limit,
+            statement.offset.unwrap_or(0),
+        ));
+    }
+    if needs_projection && !statement.distinct {
+        commands.push(Command::Project(statement.columns));
+    }
+    return commands;
+}
+
+/// Maps selects combined by set operations into the commands of each select, see [select_commands],
+/// each followed by a [Command::SetOperation] combining its rows with the rows before it.
+/// The ORDER BY, LIMIT and OFFSET clauses are then applied to the combined rows
+/// by [Command::Sort] and [Command::Limit].
+fn set_operation_commands(mut statement: SelectStatement, nested: bool) -> Vec<Command> {
+    let set_operations = std::mem::take(&mut statement.set_operations);
+    let order_by = std::mem::take(&mut statement.order_by);
+    let limit = statement.limit.take();
+    let offset = statement.offset.take();
+
+    let mut commands = select_commands(statement, nested);
+    for operation in set_operations {
+        commands.extend(select_commands(operation.select, nested));
+        commands.push(Command::SetOperation(operation.operator, operation.all));
+    }
+    if !order_by.is_empty() {
+        commands.push(Command::Sort(order_by));
+    }
+    if limit.is_some() || offset.is_some() {
+        commands.push(Command::Limit(limit, offset.unwrap_or(0)));
+    }
+    return commands;
+}
+
+/// Maps the tables of a WITH clause into a [Command::With] each, in the order given,
+/// so that each table may read the ones before it.
+/// A recursive table that reads itself is instead mapped into a [Command::WithRecursive],
+/// whose recursive commands are the ones of the select after its last UNION.
+fn with_commands(with: With, nested: bool) -> Vec<Command> {
+    let mut commands = Vec::<Command>::new();
+    for table in with.tables {
+        if !with.recursive || !table.select.references_table(&table.name) {
+            let select = select_commands(table.select, nested);
+            commands.push(Command::With(table.name, table.columns, select));
+            continue;
+        }
+        let mut initial = table.select;
+        // the form of recursive tables was checked by validate, so the last set operation is the UNION
+        let recursive = initial.set_operations.pop().unwrap();
+        commands.push(Command::WithRecursive(
+            table.name,
+            table.columns,
+            select_commands(initial, nested),
+            select_commands(recursive.select, nested),
+            recursive.all,
+        ));
+    }
+    return commands;
+}
+
+/// Maps the tables of a FROM clause into the commands that load and join them,
+/// in the order the [VirtualMachine](super::virtual_machine::VirtualMachine) consumes them.
+/// Each table is loaded under its alias, defaulting to its name, reading only the referenced columns.
+/// Derived tables are computed by their own commands, then qualified by their alias.
+fn from_commands(table: TableReference, columns: &[String]) -> Vec<Command> {
+    match table {
+        TableReference::Table(name, alias) => {
+            let alias = alias.unwrap_or_else(|| name.clone());
+            return vec![Command::SelectFromAs(columns.to_vec(), name, alias)];
+        }
+        TableReference::Subquery(statement, alias) => {
+            let mut commands = select_commands(*statement, true);
+            commands.push(Command::Qualify(alias));
+            return commands;
+        }
+        TableReference::Join(left, kind, right, condition) => {
+            let mut commands = from_commands(*left, columns);
+            commands.extend(from_commands(*right, columns));
+            commands.push(Command::Join(kind, condition));
+            return commands;
+        }
+    }
+}
+
+/// A CREATE TABLE is mapped into a single [Command::CreateTable].
+fn create_table_commands(statement: CreateTableStatement) -> Vec<Command> {
+    let schema = statement
+        .columns
+        .into_iter()
+        .map(|column| (column.name, column.data_type))
+        .collect();
+    return vec![Command::CreateTable(
+        statement.table,
+        schema,
+        statement.if_not_exists,
+    )];
+}
+
+/// An INSERT is mapped into a single [Command::Insert].
+/// The values are checked against the table schema when the command is executed.
+fn insert_commands(statement: InsertStatement) -> Vec<Command> {
+    return vec![Command::Insert(
+        statement.table,
+        statement.columns,
+        statement.rows,
+        statement.parameters,
+    )];
 }
@@ -0,0 +1,187 @@
+//! Prepared statements, which are parsed and planned once, then executed many times with the values of their parameters.
+use crate::database::command::Command;
+use steeldb_core::{DataType, TableErrors};
+use steeldb_parser::{Expression, OrderBy, SelectItem};
+
+/// A statement prepared by [SteelDB::prepare](crate::SteelDB::prepare), holding the commands that execute it.
+/// Its parameters, given by `?` or `$1`, receive their values each time it is executed by
+/// [SteelDB::execute_prepared](crate::SteelDB::execute_prepared).
+/// The values are never parsed as SQL, so they cannot change what the statement does.
+#[derive(Clone)]
+pub struct Statement {
+    /// The SQL the statement was prepared from.
+    sql: String,
+    /// The planned commands, with their parameters not yet bound.
+    commands: Vec<Command>,
+    /// The number of values the statement must be executed with.
+    parameter_count: usize,
+}
+
+impl Statement {
+    /// Creates a statement from its planned commands and the number of its parameters.
+    pub(crate) fn new(sql: String, commands: Vec<Command>, parameter_count: usize) -> Statement {
+        return Statement {
+            sql,
+            commands,
+            parameter_count,
+        };
+    }
+
+    /// The SQL the statement was prepared from.
+    pub fn sql(&self) -> &str {
+        return &self.sql;
+    }
+
+    /// The number of values the statement must be executed with, which is its largest parameter number.
+    pub fn parameter_count(&self) -> usize {
+        return self.parameter_count;
+    }
+
+    /// Returns the commands with each parameter replaced by its value, where the first value is the one of `$1`.
+    /// There must be exactly one value per parameter. Values are converted into the types their uses
+    /// require when the commands are executed, as literals are.
+    pub(crate) fn bind(&self, values: &[DataType]) -> Result<Vec<Command>, String> {
+        if values.len() != self.parameter_count {
+            let error = TableErrors::Error(format!(
+                "Expected {} parameter value(s), found {}",
+                self.parameter_count,
+                values.len()
+            ));
+            return Err(format!("{:?}", error));
+        }
+        return bind_commands(&self.commands, values);
+    }
+}
+
+/// Replaces the parameters of the commands with their values, including the ones of nested commands.
+fn bind_commands(commands: &[Command], values: &[DataType]) -> Result<Vec<Command>, String> {
+    let mut bound = Vec::<Command>::new();
+    for command in commands.iter() {
+        bound.push(bind_command(command, values)?);
+    }
+    return Ok(bound);
+}
+
+fn bind_command(command: &Command, values: &[DataType]) -> Result<Command, String> {
+    let bind_all = |expressions: &[Expression]| -> Result<Vec<Expression>, String> {
+        return expressions
+            .iter()
+            .map(|expression| bind_expression(expression, values))
+            .collect();
+    };
+    let bind_optional = |expression: &Option<Expression>| -> Result<Option<Expression>, String> {
+        match expression {
+            Some(expression) => return Ok(Some(bind_expression(expression, values)?)),
+            None => return Ok(None),
+        }
+    };
+    match command {
+        Command::Join(kind, condition) => {
+            return Ok(Command::Join(*kind, bind_expression(condition, values)?));
+        }
+        Command::Filter(predicate) => {
+            return Ok(Command::Filter(bind_expression(predicate, values)?));
+        }
+        Command::Project(items) => {
+            let mut bound = Vec::<SelectItem>::new();
+            for item in items.iter() {
+                match item {
+                    SelectItem::Wildcard => bound.push(SelectItem::Wildcard),
+                    // the column keeps the name it was written with
+                    SelectItem::Expression(expression, _) => bound.push(SelectItem::Expression(
+                        bind_expression(expression, values)?,
+                        Some(item.name()),
+                    )),
+                }
+            }
+            return Ok(Command::Project(bound));
+        }
+        Command::Insert(table, columns, rows, parameters) => {
+            let mut rows = rows.clone();
+            for (row, column, parameter) in parameters.iter() {
+                rows[*row][*column] = parameter_value(parameter.number(), values)?;
+            }
+            return Ok(Command::Insert(
+                table.clone(),
+                columns.clone(),
+                rows,
+                vec![],
+            ));
+        }
+        Command::Update(table, assignments, filter) => {
+            let mut bound = Vec::<(String, Expression)>::new();
+            for (column, expression) in assignments.iter() {
+                bound.push((column.clone(), bind_expression(expression, values)?));
+            }
+            return Ok(Command::Update(
+                table.clone(),
+                bound,
+                bind_optional(filter)?,
+            ));
+        }
+        Command::Delete(table, filter) => {
+            return Ok(Command::Delete(table.clone(), bind_optional(filter)?));
+        }
+        Command::Aggregate(group_by, aggregates) => {
+            return Ok(Command::Aggregate(
+                bind_all(group_by)?,
+                bind_all(aggregates)?,
+            ));
+        }
+        Command::Sort(order_by) => {
+            let mut bound = Vec::<OrderBy>::new();
+            for key in order_by.iter() {
+                bound.push(OrderBy {
+                    expression: bind_expression(&key.expression, values)?,
+                    ..key.clone()
+                });
+            }
+            return Ok(Command::Sort(bound));
+        }
+        Command::Window(windows) => return Ok(Command::Window(bind_all(windows)?)),
+        Command::EvaluateSubqueries(subqueries) => {
+            return Ok(Command::EvaluateSubqueries(bind_all(subqueries)?));
+        }
+        Command::With(name, columns, commands) => {
+            return Ok(Command::With(
+                name.clone(),
+                columns.clone(),
+                bind_commands(commands, values)?,
+            ));
+        }
+        Command::WithRecursive(name, columns, initial, recursive, all) => {
+            return Ok(Command::WithRecursive(
+                name.clone(),
+                columns.clone(),
+                bind_commands(initial, values)?,
+                bind_commands(recursive, values)?,
+                *all,
+            ));
+        }
+        Command::Explain(commands) => {
+            return Ok(Command::Explain(bind_commands(commands, values)?))
+        }
+        _ => return Ok(command.clone()),
+    }
+}
+
+/// Replaces the parameters of an expression, including the ones of its subqueries, with literals of their values.
+fn bind_expression(expression: &Expression, values: &[DataType]) -> Result<Expression, String> {
+    return expression.map_parameters(&mut |parameter| {
+        return Ok(Expression::Literal(parameter_value(
+            parameter.number(),
+            values,
+        )?));
+    });
+}
+
+/// Returns the value of the parameter with the given number, starting at 1.
+fn parameter_value(number: usize, values: &[DataType]) -> Result<DataType, String> {
+    match values.get(number - 1) {
+        Some(value) => return Ok(value.clone()),
+        None => {
+            let error = TableErrors::Error(format!("Parameter ${} has no value", number));
+            return Err(format!("{:?}", error));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Set operations, which combine the rows of two [InMemoryTable]s with the same number of columns.
+use crate::database::evaluator::{coerce, common_type, EvaluationError};
+use crate::database::in_memory_table::InMemoryTable;
+use std::collections::{HashMap, HashSet};
+use steeldb_core::{DataType, RowKey};
+use steeldb_parser::SetOperator;
+
+/// Combines the rows of two tables, matching their columns by position.
+/// The result takes the column names of the left table, with the common type of each pair of columns,
+/// see [common_type]. Rows are equal when all of their values are, so NULLs are equal to each other.
+///
+/// Unless `all` is given, the result has no duplicate rows.
+/// Otherwise, a row found `n` times in the left table and `m` times in the right table is kept
+/// `n + m` times by UNION, `min(n, m)` times by INTERSECT and `n - m` times by EXCEPT.
+/// Rows keep the order of the left table, followed by the order of the right table for UNION.
+pub fn combine(
+    left: &InMemoryTable,
+    right: &InMemoryTable,
+    operator: SetOperator,
+    all: bool,
+) -> Result<InMemoryTable, EvaluationError> {
+    if left.select_columns.len() != right.select_columns.len() {
+        return Err(EvaluationError::TypeMismatch(format!(
+            "Each side of {} must have the same number of columns, found {} and {}",
+            operator,
+            left.select_columns.len(),
+            right.select_columns.len()
+        )));
+    }
+    let mut schema = Vec::<(String, DataType)>::new();
+    for (name, right_name) in left.select_columns.iter().zip(&right.select_columns) {
+        let left_type = left.fields.get(name).unwrap();
+        let right_type = right.fields.get(right_name).unwrap();
+        let data_type = match common_type(&[left_type.clone(), right_type.clone()]) {
+            Ok(data_type) => data_type,
+            Err(_) => {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "Cannot mix {} with {} in column {} of {}",
+                    left_type.name(),
+                    right_type.name(),
+                    name,
+                    operator
+                )));
+            }
+        };
+        schema.push((name.clone(), data_type));
+    }
+    let left_rows = rows(left, &schema)?;
+    let right_rows = rows(right, &schema)?;
+
+    let mut right_counts = HashMap::<RowKey, usize>::new();
+    for row in right_rows.iter() {
+        *right_counts.entry(row.clone()).or_default() += 1;
+    }
+    let mut seen = HashSet::<RowKey>::new();
+    let mut combined = Vec::<RowKey>::new();
+    match operator {
+        SetOperator::Union => {
+            for row in left_rows.into_iter().chain(right_rows) {
+                if all || seen.insert(row.clone()) {
+                    combined.push(row);
+                }
+            }
+        }
+        SetOperator::Intersect => {
+            for row in left_rows {
+                match right_counts.get_mut(&row) {
+                    Some(count) if *count > 0 => {
+                        if all {
+                            *count -= 1;
+                            combined.push(row);
+                        } else if seen.insert(row.clone()) {
+                            combined.push(row);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        SetOperator::Except => {
+            for row in left_rows {
+                match right_counts.get_mut(&row) {
+                    Some(count) if *count > 0 => {
+                        // each row of the right table removes a single row of the left table
+                        if all {
+                            *count -= 1;
+                        }
+                    }
+                    _ => {
+                        if all || seen.insert(row.clone()) {
+                            combined.push(row);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut result = InMemoryTable::from_schema(String::new(), schema.clone());
+    for row in combined {
+        for ((name, _), value) in schema.iter().zip(row.0) {
+            result.columns.get_mut(name).unwrap().push(value);
+        }
+    }
+    return Ok(result);
+}
+
+/// Returns the rows of the selected columns of the table, converted into the types of the schema.
+fn rows(
+    table: &InMemoryTable,
+    schema: &[(String, DataType)],
+) -> Result<Vec<RowKey>, EvaluationError> {
+    let mut rows = Vec::<RowKey>::new();
+    for row in 0..table.number_of_rows() {
+        let mut values = Vec::<DataType>::new();
+        for (name, (_, data_type)) in table.select_columns.iter().zip(schema) {
+            let value = table.columns.get(name).unwrap()[row].clone();
+            if value == DataType::Null {
+                values.push(value);
+            } else {
+                values.push(coerce(value, data_type)?);
+            }
+        }
+        rows.push(RowKey(values));
+    }
+    return Ok(rows);
+}
@@ -1,11 +1,12 @@
 //! The entrypoint module of the database. Defines an embbeddeable Database.
 //! In the future, it might also define the database as Server/Client.
-use crate::database::command::CommandResult;
+use crate::database::command::{Command, CommandResult};
 use crate::database::logger::logger_init;
-use crate::database::parser::{parse, ParseError};
+use crate::database::parser::{parse, parse_prepared, parse_script, split_script};
+use crate::database::prepared_statement::Statement;
 use crate::database::virtual_machine::VirtualMachine;
 use log::{error, info};
-use steeldb_core::{ExecutionResult, SteelDBInterface};
+use steeldb_core::{DataType, ExecutionResult, ParseError, SteelDBInterface};
 
 /// The main struct exposed by the crate.
 /// See the crate root documentation on how to use it.
@@ -25,34 +26,104 @@ impl SteelDB {
         };
     }
 }
+
+impl Default for SteelDB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl SteelDB {
+    /// Parses and plans a statement once, so that it can be executed many times by [SteelDB::execute_prepared].
+    /// Values are given by the parameters `?`, numbered in the order they are written, or `$1`, `$2` and so on,
+    /// which may be used wherever a literal may. A statement cannot mix both kinds.
+    pub fn prepare(&self, sql: String) -> Result<Statement, ParseError> {
+        info!("Preparing statement: {}", sql);
+        let (commands, parameter_count) = parse_prepared(sql.clone())?;
+        return Ok(Statement::new(sql, commands, parameter_count));
+    }
+
+    /// Executes a prepared statement, with one value per parameter, where the first value is the one of `$1`.
+    /// The values are converted into the types their uses require, failing with a `CommandError` when they cannot be.
+    pub fn execute_prepared(
+        &mut self,
+        statement: &Statement,
+        parameters: &[DataType],
+    ) -> ExecutionResult {
+        info!("Executing prepared statement: {}", statement.sql());
+        match statement.bind(parameters) {
+            Ok(commands) => return self.execute_commands(commands),
+            Err(error) => {
+                error!("Binding failed: {:?}", error);
+                return ExecutionResult::CommandError(error);
+            }
+        }
+    }
+
+    /// Executes the commands of a single statement, translating the [CommandResult] into an [ExecutionResult].
+    fn execute_commands(&self, commands: Vec<Command>) -> ExecutionResult {
+        let command_result = self.virtual_machine.execute(commands);
+        // translate CommandResult into ExecutionResult
+        // we do not want to make the outer layer import any enum except ExecutionResult
+        match command_result {
+            CommandResult::RetrievedDataSuccess(table) => {
+                info!("Retrieved data successfully");
+                return ExecutionResult::TableResult(table);
+            }
+            CommandResult::VoidSuccess => {
+                info!("Command successful");
+                return ExecutionResult::VoidOK;
+            }
+            CommandResult::RowsAffected(number_rows) => {
+                info!("Command successful, {} row(s) affected", number_rows);
+                return ExecutionResult::RowsAffected(number_rows);
+            }
+            CommandResult::Error(error) => {
+                error!("Command failed: {:?}", error);
+                return ExecutionResult::CommandError(error);
+            }
+        }
+    }
+
+    /// Executes a script of several statements in order, stopping at the first one that fails.
+    /// The whole script is parsed before any statement is executed.
+    fn execute_script(&self, user_input: String) -> ExecutionResult {
+        let scripts = match parse_script(user_input) {
+            Ok(scripts) => scripts,
+            Err((index, error)) => {
+                error!("Parse error in statement {}: {}", index, error);
+                let error = Box::new(ExecutionResult::ParseError(error));
+                return ExecutionResult::ScriptError(index, error);
+            }
+        };
+        let mut results = Vec::<ExecutionResult>::new();
+        for (index, commands) in scripts.into_iter().enumerate() {
+            let result = self.execute_commands(commands);
+            if let ExecutionResult::CommandError(_) = result {
+                return ExecutionResult::ScriptError(index, Box::new(result));
+            }
+            results.push(result);
+        }
+        return ExecutionResult::ScriptResult(results);
+    }
+}
+
 impl SteelDBInterface for SteelDB {
     /// Entrypoint to execute a SQL query.
+    /// An input of several statements is executed as a script, returning an [ExecutionResult::ScriptResult]
+    /// with the result of each statement, or an [ExecutionResult::ScriptError] at the first failure.
     fn execute(&mut self, user_input: String) -> ExecutionResult {
         info!("Executing user input: {}", user_input);
+        if split_script(&user_input).len() > 1 {
+            return self.execute_script(user_input);
+        }
         let result = parse(user_input);
         match result {
             Ok(commands) => {
-                let command_result = self.virtual_machine.execute(commands);
-                // translate CommandResult into ExecutionResult
-                // we do not want to make the outer layer import any enum except ExecutionResult
-                match command_result {
-                    CommandResult::RetrievedDataSuccess(table) => {
-                        info!("Retrieved data successfully");
-                        return ExecutionResult::TableResult(table);
-                    }
-                    CommandResult::VoidSuccess => {
-                        info!("Command successful");
-                        return ExecutionResult::VoidOK;
-                    }
-                    CommandResult::Error(error) => {
-                        error!("Command failed: {:?}", error);
-                        return ExecutionResult::CommandError(error);
-                    }
-                }
+                return self.execute_commands(commands);
             }
             // translate ParseError into ExecutionResult
-            Err(ParseError::Error(error)) => {
-                error!("Parse error: {:?}", error);
+            Err(error) => {
+                error!("Parse error: {}", error);
                 return ExecutionResult::ParseError(error);
             }
         }
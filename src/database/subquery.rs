@@ -0,0 +1,209 @@
+//! Subqueries, which are executed by a nested [VirtualMachine] for the rows of the enclosing query.
+//!
+//! The columns of a subquery may refer to the enclosing queries, making it correlated.
+//! Before each command of a subquery runs, the columns it cannot find are bound to the values
+//! of the current rows of the enclosing queries, see [bind_outer].
+//! A correlated subquery is executed for every row, while any other subquery is executed once.
+use crate::database::command::Command;
+use crate::database::evaluator::{compare, evaluate, resolve, resolve_column, EvaluationError};
+use crate::database::in_memory_table::InMemoryTable;
+use crate::database::parser::subquery_commands;
+use crate::database::virtual_machine::VirtualMachine;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use steeldb_core::DataType;
+use steeldb_parser::Expression;
+
+/// Replaces the columns that are not found in any of the tables by the values they refer to
+/// in the scope, which holds the current row of each enclosing query, from the innermost outwards.
+/// Columns of the tables always take precedence over the columns of the enclosing queries.
+/// Returns the bound expression with how many levels of the scope it read from.
+pub fn bind_outer(
+    expression: &Expression,
+    tables: &[&InMemoryTable],
+    scope: &[InMemoryTable],
+) -> Result<(Expression, usize), EvaluationError> {
+    let mut depth = 0;
+    let bound = bind(expression, tables, scope, &mut depth)?;
+    return Ok((bound, depth));
+}
+
+fn bind(
+    expression: &Expression,
+    tables: &[&InMemoryTable],
+    scope: &[InMemoryTable],
+    depth: &mut usize,
+) -> Result<Expression, EvaluationError> {
+    let name = match expression {
+        Expression::Column(name) => name,
+        // aggregates and subqueries computed beforehand are found by their name, so they are kept as they are
+        _ if tables
+            .iter()
+            .any(|table| table.fields.contains_key(&expression.to_string())) =>
+        {
+            return Ok(expression.clone());
+        }
+        _ => return expression.map_children(|child| bind(child, tables, scope, depth)),
+    };
+    for table in tables.iter() {
+        match resolve_column(table, name) {
+            Err(EvaluationError::ColumnNotFound(_)) => {}
+            _ => return Ok(expression.clone()),
+        }
+    }
+    for (level, outer) in scope.iter().enumerate() {
+        match resolve_column(outer, name) {
+            Ok(column) => {
+                *depth = (*depth).max(level + 1);
+                let value = outer.columns.get(&column).unwrap()[0].clone();
+                return Ok(Expression::Literal(value));
+            }
+            Err(EvaluationError::ColumnNotFound(_)) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    return Ok(expression.clone());
+}
+
+/// Executes the subqueries for each row of the table, returning a copy of the table
+/// with a column per subquery, named after it, from which [evaluate] reads their values.
+/// A scalar subquery holds its value, while EXISTS and IN hold 1 when true, 0 when false and NULL when unknown.
+/// These columns are not selected, so they are never part of a result.
+/// The subqueries may read the given tables of the WITH clauses of the enclosing queries.
+/// Also returns how many levels of the scope the subqueries read from.
+pub fn evaluate_subqueries(
+    table: &InMemoryTable,
+    subqueries: &[Expression],
+    scope: &[InMemoryTable],
+    common_tables: &HashMap<String, InMemoryTable>,
+) -> Result<(InMemoryTable, usize), EvaluationError> {
+    let all_rows: Vec<usize> = (0..table.number_of_rows()).collect();
+    let mut result = table.select_rows(&all_rows);
+    let mut depth = 0;
+    for subquery in subqueries.iter() {
+        let name = subquery.to_string();
+        if result.fields.contains_key(&name) {
+            continue;
+        }
+        let (statement, value) = match subquery {
+            Expression::Subquery(statement) | Expression::Exists(statement) => (statement, None),
+            Expression::InSubquery(value, statement, _) => {
+                let (bound, value_depth) = bind_outer(value, &[table], scope)?;
+                depth = depth.max(value_depth);
+                (statement, Some(resolve(&bound, table)?))
+            }
+            _ => {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "Expected a subquery, found {}",
+                    subquery
+                )));
+            }
+        };
+        let mut commands = subquery_commands(*statement.clone());
+        if let Expression::Exists(_) = subquery {
+            commands.push(Command::Limit(Some(1), 0));
+        }
+
+        // the type of a scalar subquery is only known once it is executed
+        let mut data_type = match subquery {
+            Expression::Subquery(_) => DataType::Null,
+            _ => DataType::Integer32(0),
+        };
+        let mut values = Vec::<DataType>::new();
+        let mut uncorrelated: Option<InMemoryTable> = None;
+        for row in 0..table.number_of_rows() {
+            let mut correlated: Option<InMemoryTable> = None;
+            if uncorrelated.is_none() {
+                let (rows, subquery_depth) =
+                    execute_subquery(&commands, table, row, scope, common_tables)?;
+                if subquery_depth == 0 {
+                    uncorrelated = Some(rows);
+                } else {
+                    // the first level of the subquery scope is the current row
+                    depth = depth.max(subquery_depth - 1);
+                    correlated = Some(rows);
+                }
+            }
+            let rows = uncorrelated.as_ref().or(correlated.as_ref()).unwrap();
+
+            match (subquery, &value) {
+                (Expression::Exists(_), _) => {
+                    let found = rows.number_of_rows() > 0;
+                    values.push(DataType::Integer32(found as i32));
+                }
+                (_, Some(value)) => {
+                    let column = single_column(rows)?;
+                    let value = evaluate(value, table, row)?;
+                    let found = match is_in(&value, column)? {
+                        Some(found) => DataType::Integer32(found as i32),
+                        None => DataType::Null,
+                    };
+                    values.push(found);
+                }
+                _ => {
+                    let column = single_column(rows)?;
+                    if column.len() > 1 {
+                        return Err(EvaluationError::Subquery(format!(
+                            "Subquery returned more than one row: {}",
+                            subquery
+                        )));
+                    }
+                    data_type = rows.fields.get(&rows.select_columns[0]).unwrap().clone();
+                    values.push(column.first().cloned().unwrap_or(DataType::Null));
+                }
+            }
+        }
+        result.fields.insert(name.clone(), data_type);
+        result.columns.insert(name, values);
+    }
+    return Ok((result, depth));
+}
+
+/// Executes the commands of a subquery for a row of the table, which becomes the innermost level of its scope.
+/// Returns the result with how many levels of its scope the subquery read from.
+fn execute_subquery(
+    commands: &[Command],
+    table: &InMemoryTable,
+    row: usize,
+    scope: &[InMemoryTable],
+    common_tables: &HashMap<String, InMemoryTable>,
+) -> Result<(InMemoryTable, usize), EvaluationError> {
+    let mut subquery_scope = vec![table.select_rows(&[row])];
+    subquery_scope.extend(scope.iter().cloned());
+    let virtual_machine = VirtualMachine::with_scope(subquery_scope, common_tables.clone());
+    match virtual_machine.execute_query(commands.to_vec()) {
+        Ok(result) => return Ok((result, virtual_machine.scope_depth())),
+        Err(error) => return Err(EvaluationError::Subquery(error)),
+    }
+}
+
+/// Returns the values of a subquery result, which must have a single column.
+fn single_column(table: &InMemoryTable) -> Result<&Vec<DataType>, EvaluationError> {
+    if table.select_columns.len() != 1 {
+        return Err(EvaluationError::Subquery(format!(
+            "Subquery must return a single column, found {}",
+            table.select_columns.len()
+        )));
+    }
+    return Ok(table.columns.get(&table.select_columns[0]).unwrap());
+}
+
+/// Whether the value is one of the values, with the three-valued logic of SQL:
+/// unknown when the value is NULL or when it is not found but one of the values is NULL.
+fn is_in(value: &DataType, values: &[DataType]) -> Result<Option<bool>, EvaluationError> {
+    if values.is_empty() {
+        return Ok(Some(false));
+    }
+    if *value == DataType::Null {
+        return Ok(None);
+    }
+    let mut found = Some(false);
+    for candidate in values.iter() {
+        if *candidate == DataType::Null {
+            found = None;
+        } else if compare(value, candidate)? == Some(Ordering::Equal) {
+            return Ok(Some(true));
+        }
+    }
+    return Ok(found);
+}
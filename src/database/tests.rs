@@ -1,172 +1,3053 @@
 //! Database unit tests
-#[cfg(test)]
-mod tests {
-    use crate::database::config::DATA_DIR;
-    use crate::database::in_memory_table::InMemoryTable;
-    use std::collections::HashMap;
-    use std::path::Path;
-    use steeldb_core::DataType;
-    use steeldb_core::{FileFormat, SaveMode, Table, TableErrors};
-
-    pub fn load_test_table(
-        table_name: String,
-        select_columns: Vec<String>,
-    ) -> Result<InMemoryTable, TableErrors> {
-        let mut fields = HashMap::<String, DataType>::new();
-        fields.insert("name".to_string(), DataType::String("name".to_string()));
-        fields.insert("annual_salary".to_string(), DataType::Integer32(0));
-        fields.insert("final_grade".to_string(), DataType::Float32(0.0));
-
-        let mut columns = HashMap::<String, Vec<DataType>>::new();
-
-        let mut name_column = Vec::<DataType>::new();
-        let mut annual_salary_column = Vec::<DataType>::new();
-        let mut final_grade_column = Vec::<DataType>::new();
-
-        name_column.push(DataType::String("John".to_string()));
-        name_column.push(DataType::String("Lenon".to_string()));
-        name_column.push(DataType::String("Mary".to_string()));
-
-        annual_salary_column.push(DataType::Integer32(60000));
-        annual_salary_column.push(DataType::Integer32(200000));
-        annual_salary_column.push(DataType::Integer32(30000));
-
-        final_grade_column.push(DataType::Float32(4.0));
-        final_grade_column.push(DataType::Float32(3.0));
-        final_grade_column.push(DataType::Float32(5.0));
-
-        columns.insert("name".to_string(), name_column);
-        columns.insert("annual_salary".to_string(), annual_salary_column);
-        columns.insert("final_grade".to_string(), final_grade_column);
-
-        let mut test_table = InMemoryTable {
-            name: table_name,
-            fields: fields,
-            columns: columns,
-            select_columns: select_columns,
-        };
-
-        let mut returned_columns = HashMap::<String, Vec<DataType>>::new();
-        for column_name in test_table.select_columns.iter() {
-            if !test_table.fields.contains_key(column_name) {
-                return Err(TableErrors::ColumnNotFound(column_name.clone()));
+use crate::database::command::{Command, CommandResult};
+use crate::database::config::DATA_DIR;
+use crate::database::file_io::{ColumnarWriter, Writer};
+use crate::database::in_memory_table::InMemoryTable;
+use crate::database::parser::parse;
+use crate::database::steeldb::SteelDB;
+use crate::database::virtual_machine::VirtualMachine;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use steeldb_core::DataType;
+use steeldb_core::{
+    ExecutionResult, FileFormat, ParseErrorKind, SaveMode, SteelDBInterface, Table, TableErrors,
+};
+
+pub fn load_test_table(
+    table_name: String,
+    select_columns: Vec<String>,
+) -> Result<InMemoryTable, TableErrors> {
+    let mut fields = HashMap::<String, DataType>::new();
+    fields.insert("name".to_string(), DataType::String("name".to_string()));
+    fields.insert("annual_salary".to_string(), DataType::Integer32(0));
+    fields.insert("final_grade".to_string(), DataType::Float32(0.0));
+
+    let mut columns = HashMap::<String, Vec<DataType>>::new();
+
+    let mut name_column = Vec::<DataType>::new();
+    let mut annual_salary_column = Vec::<DataType>::new();
+    let mut final_grade_column = Vec::<DataType>::new();
+
+    name_column.push(DataType::String("John".to_string()));
+    name_column.push(DataType::String("Lenon".to_string()));
+    name_column.push(DataType::String("Mary".to_string()));
+
+    annual_salary_column.push(DataType::Integer32(60000));
+    annual_salary_column.push(DataType::Integer32(200000));
+    annual_salary_column.push(DataType::Integer32(30000));
+
+    final_grade_column.push(DataType::Float32(4.0));
+    final_grade_column.push(DataType::Float32(3.0));
+    final_grade_column.push(DataType::Float32(5.0));
+
+    columns.insert("name".to_string(), name_column);
+    columns.insert("annual_salary".to_string(), annual_salary_column);
+    columns.insert("final_grade".to_string(), final_grade_column);
+
+    let mut test_table = InMemoryTable {
+        name: table_name,
+        fields,
+        columns,
+        select_columns,
+    };
+
+    let mut returned_columns = HashMap::<String, Vec<DataType>>::new();
+    for column_name in test_table.select_columns.iter() {
+        if !test_table.fields.contains_key(column_name) {
+            return Err(TableErrors::ColumnNotFound(column_name.clone()));
+        }
+        let retrieved_column = test_table.columns.get(column_name);
+        match retrieved_column {
+            Some(col) => {
+                returned_columns.insert(column_name.clone(), col.to_owned());
             }
-            let retrieved_column = test_table.columns.get(column_name);
-            match retrieved_column {
-                Some(col) => {
-                    returned_columns.insert(column_name.clone(), col.to_owned());
-                }
-                None => return Err(TableErrors::ColumnNotFound(column_name.clone())),
+            None => return Err(TableErrors::ColumnNotFound(column_name.clone())),
+        }
+    }
+    test_table.columns = returned_columns;
+    return Ok(test_table);
+}
+
+fn remove_test_table(table_name: &str) {
+    InMemoryTable::init_data_dir();
+    let mut filename = table_name.to_string();
+    filename.push_str(".columnar");
+
+    let file_path = Path::new(DATA_DIR).join(Path::new(&filename));
+    print!("Checking if file exists at {:?}... ", file_path);
+    if file_path.exists() {
+        print!("exists, deleting...");
+        let result = std::fs::remove_file(file_path);
+        result.unwrap();
+        println!("OK");
+    } else {
+        println!("does not exist");
+    }
+}
+
+fn write_test_table(table_name: &str) {
+    remove_test_table(table_name);
+
+    let select_columns = vec![
+        "name".to_string(),
+        "annual_salary".to_string(),
+        "final_grade".to_string(),
+    ];
+    let table_result = load_test_table(table_name.to_string(), select_columns);
+    let table = table_result.unwrap();
+    let save_result = table.save(SaveMode::Overwrite, FileFormat::SimpleColumnar);
+    save_result.unwrap();
+}
+
+#[test]
+fn test_write_columnar_table() {
+    write_test_table("test_write_table");
+}
+
+#[test]
+fn test_read_columnar_table() {
+    let table_name = "test_read_table";
+    write_test_table(table_name);
+    let select_columns = vec![
+        "name".to_string(),
+        "annual_salary".to_string(),
+        "final_grade".to_string(),
+    ];
+    let load_result = InMemoryTable::new().load(
+        table_name.to_string(),
+        select_columns,
+        FileFormat::SimpleColumnar,
+    );
+    let table = load_result.unwrap();
+
+    let name_column = table.get_columns().get("name").unwrap();
+    let matches: Vec<bool> = ["John", "Lenon", "Mary"]
+        .iter()
+        .zip(name_column.iter())
+        .map(|(left, right)| -> bool {
+            match right {
+                DataType::String(s) => s == left,
+                _ => panic!("Found not string in name column!"),
+            }
+        })
+        .collect();
+    for m in matches.iter() {
+        assert!(m);
+    }
+
+    let name_column = table.get_columns().get("annual_salary").unwrap();
+    let matches: Vec<bool> = [60000, 200000, 30000]
+        .iter()
+        .zip(name_column.iter())
+        .map(|(left, right)| -> bool {
+            match right {
+                DataType::Integer32(i) => i == left,
+                _ => panic!("Found not integer in annual salary column!"),
             }
+        })
+        .collect();
+    for m in matches.iter() {
+        assert!(m);
+    }
+
+    let name_column = table.get_columns().get("final_grade").unwrap();
+    let matches: Vec<bool> = [4.0, 3.0, 5.0]
+        .iter()
+        .zip(name_column.iter())
+        .map(|(left, right)| -> bool {
+            match right {
+                DataType::Float32(f) => f == left,
+                _ => panic!("Found not float in final grade column!"),
+            }
+        })
+        .collect();
+    for m in matches.iter() {
+        assert!(m);
+    }
+}
+
+#[test]
+fn test_column_not_found() {
+    let table_name = "test_column_not_found";
+    write_test_table(table_name);
+    let select_columns = vec!["durp".to_string()];
+    let load_result = InMemoryTable::new().load(
+        table_name.to_string(),
+        select_columns,
+        FileFormat::SimpleColumnar,
+    );
+    assert!(load_result.is_err());
+}
+
+#[test]
+fn test_select_from_table() {
+    let table_name = "test_select_from_table";
+    write_test_table(table_name);
+    let commands = parse(format!("select name, final_grade from {};", table_name)).unwrap();
+    let result = VirtualMachine::new().execute(commands);
+    match result {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_table_name(), table_name);
+            assert_eq!(
+                table.get_select_columns(),
+                &vec!["name".to_string(), "final_grade".to_string()]
+            );
+            assert_eq!(table.get_columns().get("name").unwrap().len(), 3);
         }
-        test_table.columns = returned_columns;
-        return Ok(test_table);
+        _ => panic!("Expected table to be retrieved"),
     }
+}
 
-    fn write_test_table(table_name: &str) {
-        InMemoryTable::init_data_dir();
-        let mut filename = table_name.to_string();
-        filename.push_str(".columnar");
+#[test]
+fn test_select_from_missing_table() {
+    let commands = parse("select name from test_missing_table;".to_string()).unwrap();
+    let result = VirtualMachine::new().execute(commands);
+    match result {
+        CommandResult::Error(error) => {
+            assert_eq!(error, "TableNotFound(\"test_missing_table\")");
+        }
+        _ => panic!("Expected missing table error"),
+    }
+}
 
-        let file_path = Path::new(DATA_DIR).join(Path::new(&filename));
-        print!("Checking if file exists at {:?}... ", file_path);
-        if file_path.exists() {
-            print!("exists, deleting...");
-            let result = std::fs::remove_file(file_path);
-            result.unwrap();
-            println!("OK");
-        } else {
-            println!("does not exist");
+fn execute_query(query: &str) -> CommandResult {
+    let commands = parse(query.to_string()).unwrap();
+    return VirtualMachine::new().execute(commands);
+}
+
+#[test]
+fn test_select_where() {
+    let table_name = "test_select_where";
+    write_test_table(table_name);
+    let query = format!(
+        "select name from {} where annual_salary > 50000 and not final_grade = 3;",
+        table_name
+    );
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            // the predicate columns are not part of the result
+            assert_eq!(table.get_select_columns(), &vec!["name".to_string()]);
+            assert_eq!(table.get_columns().len(), 1);
+            let names = table.get_columns().get("name").unwrap();
+            assert_eq!(names, &vec![DataType::String("John".to_string())]);
         }
+        _ => panic!("Expected table to be retrieved"),
+    }
+}
 
-        let select_columns = vec![
-            "name".to_string(),
-            "annual_salary".to_string(),
-            "final_grade".to_string(),
-        ];
-        let table_result = load_test_table(table_name.to_string(), select_columns);
-        let table = table_result.unwrap();
-        let save_result = table.save(SaveMode::Overwrite, FileFormat::SimpleColumnar);
-        save_result.unwrap();
+#[test]
+fn test_select_where_string_and_or() {
+    let table_name = "test_select_where_string_and_or";
+    write_test_table(table_name);
+    let query = format!(
+        "select name, final_grade from {} where (name = 'Mary' or name = 'Lenon') and final_grade >= 4;",
+        table_name
+    );
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            let grades = table.get_columns().get("final_grade").unwrap();
+            assert_eq!(grades, &vec![DataType::Float32(5.0)]);
+        }
+        _ => panic!("Expected table to be retrieved"),
     }
+}
 
-    #[test]
-    fn test_write_columnar_table() {
-        write_test_table("test_write_table");
+#[test]
+fn test_select_where_type_mismatch() {
+    let table_name = "test_select_where_type_mismatch";
+    write_test_table(table_name);
+    let query = format!("select name from {} where name > 10;", table_name);
+    match execute_query(&query) {
+        CommandResult::Error(error) => {
+            assert_eq!(error, "TypeMismatch(\"Cannot compare String with i32\")");
+        }
+        _ => panic!("Expected type mismatch error"),
     }
+}
 
-    #[test]
-    fn test_read_columnar_table() {
-        let table_name = "test_read_table";
-        write_test_table(&table_name);
-        let select_columns = vec![
-            "name".to_string(),
-            "annual_salary".to_string(),
-            "final_grade".to_string(),
-        ];
-        let load_result = InMemoryTable::new().load(
-            table_name.to_string(),
-            select_columns,
-            FileFormat::SimpleColumnar,
+#[test]
+fn test_select_where_not_a_predicate() {
+    let table_name = "test_select_where_not_a_predicate";
+    write_test_table(table_name);
+    let query = format!("select name from {} where name;", table_name);
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert!(error.starts_with("TypeMismatch")),
+        _ => panic!("Expected type mismatch error"),
+    }
+}
+
+#[test]
+fn test_parse_error_location() {
+    let result = parse("select name\nfrom test_table\nwhere name = ';".to_string());
+    match result {
+        Err(error) => {
+            assert_eq!(error.kind, ParseErrorKind::InvalidToken);
+            assert_eq!((error.line, error.column), (3, 14));
+            assert_eq!(error.offset, 41);
+        }
+        Ok(_) => panic!("Expected parse error"),
+    }
+}
+
+#[test]
+fn test_create_table() {
+    let table_name = "test_create_table";
+    remove_test_table(table_name);
+    let query = format!(
+        "create table {} (id int, price float, item text);",
+        table_name
+    );
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+
+    let query = format!("select item, id, price from {};", table_name);
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_columns().get("id").unwrap().len(), 0);
+            assert_eq!(table.get_columns().len(), 3);
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+
+    // the columns are saved in the order they were declared
+    let path = InMemoryTable::get_table_path(&table_name.to_string(), &FileFormat::SimpleColumnar);
+    let content = std::fs::read_to_string(path).unwrap();
+    let expected = "TABLE COLUMNAR FORMAT HEADER\n\
+        Field name: id; Type: i32; Number of elements: 0\n\
+        Field name: price; Type: f32; Number of elements: 0\n\
+        Field name: item; Type: String; Number of elements: 0\n";
+    assert_eq!(content, expected);
+}
+
+#[test]
+fn test_create_table_already_exists() {
+    let table_name = "test_create_table_already_exists";
+    remove_test_table(table_name);
+    let query = format!("create table {} (id int);", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert_eq!(error, "TableAlreadyExists"),
+        _ => panic!("Expected table already exists error"),
+    }
+    let query = format!("create table if not exists {} (id int);", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+}
+
+#[test]
+fn test_write_empty_schema() {
+    InMemoryTable::init_data_dir();
+    let path = Path::new(DATA_DIR).join("test_write_empty_schema.columnar");
+    let file_ = File::create(path).unwrap();
+    let result = ColumnarWriter::new().write(&HashMap::new(), &HashMap::new(), &[], file_);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_insert_multiple_rows() {
+    let table_name = "test_insert_multiple_rows";
+    remove_test_table(table_name);
+    let query = format!("create table {} (a int, b text, c float);", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+
+    let query = format!(
+        "insert into {} (b, a, c) values ('x', 1, 1.5), ('y', 2, 3);",
+        table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(2)
+    ));
+    let query = format!("insert into {} values (3, 'z', -1.0);", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+
+    let query = format!("select a, b, c from {};", table_name);
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            let columns = table.get_columns();
+            assert_eq!(
+                columns.get("a").unwrap(),
+                &vec![
+                    DataType::Integer32(1),
+                    DataType::Integer32(2),
+                    DataType::Integer32(3)
+                ]
+            );
+            assert_eq!(
+                columns.get("b").unwrap(),
+                &vec![
+                    DataType::String("x".to_string()),
+                    DataType::String("y".to_string()),
+                    DataType::String("z".to_string())
+                ]
+            );
+            // integers are widened into floats
+            assert_eq!(
+                columns.get("c").unwrap(),
+                &vec![
+                    DataType::Float32(1.5),
+                    DataType::Float32(3.0),
+                    DataType::Float32(-1.0)
+                ]
+            );
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+}
+
+#[test]
+fn test_insert_invalid_rows() {
+    let table_name = "test_insert_invalid_rows";
+    remove_test_table(table_name);
+    let query = format!("create table {} (a int, b text);", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+
+    let cases = vec![
+        (
+            "(a, b) values (1.5, 'x')",
+            "TypeMismatch(\"Cannot convert f32 to i32\")",
+        ),
+        ("(a) values (1)", "Error(\"Missing value for column: b\")"),
+        (
+            "(a, b) values (1, 'x'), (2)",
+            "Error(\"Expected 2 values, found 1\")",
+        ),
+        ("(a, d) values (1, 'x')", "ColumnNotFound(\"d\")"),
+    ];
+    for (values, expected) in cases {
+        let query = format!("insert into {} {};", table_name, values);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected insert to fail: {}", values),
+        }
+    }
+
+    // nothing was written by the failed inserts
+    let query = format!("select a from {};", table_name);
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_columns().get("a").unwrap().len(), 0);
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+}
+
+#[test]
+fn test_append_columnar_table() {
+    let table_name = "test_append_columnar_table";
+    write_test_table(table_name);
+    let select_columns = vec![
+        "name".to_string(),
+        "annual_salary".to_string(),
+        "final_grade".to_string(),
+    ];
+    let content = read_table_file(table_name);
+    let table = load_test_table(table_name.to_string(), select_columns.clone()).unwrap();
+    table
+        .save(SaveMode::Append, FileFormat::SimpleColumnar)
+        .unwrap();
+    // the stored rows are not rewritten, the new ones follow them
+    assert!(read_table_file(table_name).starts_with(&content));
+
+    let table = InMemoryTable::read(
+        table_name.to_string(),
+        select_columns,
+        FileFormat::SimpleColumnar,
+    )
+    .unwrap();
+    assert_eq!(table.number_of_rows(), 6);
+    assert_eq!(
+        table.columns.get("name").unwrap()[3],
+        DataType::String("John".to_string())
+    );
+}
+
+#[test]
+fn test_append_missing_table() {
+    let table_name = "test_append_missing_table";
+    remove_test_table(table_name);
+    let table = load_test_table(table_name.to_string(), vec!["name".to_string()]).unwrap();
+    let result = table.save(SaveMode::Append, FileFormat::SimpleColumnar);
+    assert!(matches!(result, Err(TableErrors::TableNotFound(_))));
+}
+
+#[test]
+fn test_drop_table() {
+    let table_name = "test_drop_table";
+    write_test_table(table_name);
+    let query = format!("drop table {};", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    let path = InMemoryTable::get_table_path(&table_name.to_string(), &FileFormat::SimpleColumnar);
+    assert!(!Path::new(&path).exists());
+
+    match execute_query(&query) {
+        CommandResult::Error(error) => {
+            assert_eq!(error, "TableNotFound(\"test_drop_table\")")
+        }
+        _ => panic!("Expected table not found error"),
+    }
+    let query = format!("drop table if exists {};", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+}
+
+#[test]
+fn test_truncate_table() {
+    let table_name = "test_truncate_table";
+    write_test_table(table_name);
+    let schema_before =
+        InMemoryTable::read_schema(&table_name.to_string(), FileFormat::SimpleColumnar).unwrap();
+    let query = format!("truncate table {};", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+
+    let schema_after =
+        InMemoryTable::read_schema(&table_name.to_string(), FileFormat::SimpleColumnar).unwrap();
+    let names_before: Vec<&String> = schema_before.iter().map(|(name, _)| name).collect();
+    let names_after: Vec<&String> = schema_after.iter().map(|(name, _)| name).collect();
+    assert_eq!(names_before, names_after);
+
+    let query = format!("select name, annual_salary from {};", table_name);
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_columns().get("name").unwrap().len(), 0);
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+
+    match execute_query("truncate table test_truncate_missing_table;") {
+        CommandResult::Error(error) => {
+            assert_eq!(error, "TableNotFound(\"test_truncate_missing_table\")")
+        }
+        _ => panic!("Expected table not found error"),
+    }
+}
+
+fn read_table_file(table_name: &str) -> String {
+    let path = InMemoryTable::get_table_path(&table_name.to_string(), &FileFormat::SimpleColumnar);
+    return std::fs::read_to_string(path).unwrap();
+}
+
+#[test]
+fn test_alter_table_columns() {
+    let table_name = "test_alter_table_columns";
+    remove_test_table(table_name);
+    let query = format!("create table {} (a int, b text);", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    let query = format!("insert into {} values (1, 'x'), (2, 'y');", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(2)
+    ));
+    let queries = vec![
+        format!("alter table {} add column c float default 2;", table_name),
+        format!("alter table {} add column d int;", table_name),
+        format!("alter table {} drop column b;", table_name),
+        format!("alter table {} rename column a to e;", table_name),
+    ];
+    for query in queries {
+        assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    }
+
+    let expected = "TABLE COLUMNAR FORMAT HEADER\n\
+        Field name: e; Type: i32; Number of elements: 2\n1\n2\n\
+        Field name: c; Type: f32; Number of elements: 2\n2\n2\n\
+        Field name: d; Type: i32; Number of elements: 2\n\\N\n\\N\n";
+    assert_eq!(read_table_file(table_name), expected);
+}
+
+#[test]
+fn test_alter_table_errors_keep_file() {
+    let table_name = "test_alter_table_errors_keep_file";
+    write_test_table(table_name);
+    let content = read_table_file(table_name);
+    let cases = vec![
+        (
+            "add column name text",
+            "Error(\"Column already exists: name\")",
+        ),
+        (
+            "add column extra int default 'x'",
+            "TypeMismatch(\"Cannot convert String to i32\")",
+        ),
+        ("drop column durp", "ColumnNotFound(\"durp\")"),
+        (
+            "rename column name to final_grade",
+            "Error(\"Column already exists: final_grade\")",
+        ),
+    ];
+    for (action, expected) in cases {
+        let query = format!("alter table {} {};", table_name, action);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected alter table to fail: {}", action),
+        }
+    }
+    assert_eq!(read_table_file(table_name), content);
+}
+
+#[test]
+fn test_alter_table_rename() {
+    let table_name = "test_alter_table_rename";
+    let new_name = "test_alter_table_renamed";
+    write_test_table(table_name);
+    remove_test_table(new_name);
+    let query = format!("alter table {} rename to {};", table_name, new_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+
+    let query = format!("select name from {};", new_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RetrievedDataSuccess(_)
+    ));
+    let query = format!("select name from {};", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::Error(_)));
+
+    write_test_table(table_name);
+    let query = format!("alter table {} rename to {};", table_name, new_name);
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert_eq!(error, "TableAlreadyExists"),
+        _ => panic!("Expected table already exists error"),
+    }
+}
+
+#[test]
+fn test_update_where() {
+    let table_name = "test_update_where";
+    write_test_table(table_name);
+    let query = format!(
+        "update {} set annual_salary = 1000, final_grade = annual_salary where name <> 'Mary';",
+        table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(2)
+    ));
+
+    let query = format!(
+        "select name, annual_salary, final_grade from {};",
+        table_name
+    );
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            let columns = table.get_columns();
+            assert_eq!(
+                columns.get("annual_salary").unwrap(),
+                &vec![
+                    DataType::Integer32(1000),
+                    DataType::Integer32(1000),
+                    DataType::Integer32(30000)
+                ]
+            );
+            // assignments read the values from before the update, widened to f32
+            assert_eq!(
+                columns.get("final_grade").unwrap(),
+                &vec![
+                    DataType::Float32(60000.0),
+                    DataType::Float32(200000.0),
+                    DataType::Float32(5.0)
+                ]
+            );
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+
+    let query = format!("update {} set name = 'Anne';", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(3)
+    ));
+    let query = format!(
+        "update {} set name = 'Bob' where name = 'John';",
+        table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(0)
+    ));
+}
+
+#[test]
+fn test_update_errors_keep_file() {
+    let table_name = "test_update_errors_keep_file";
+    write_test_table(table_name);
+    let content = read_table_file(table_name);
+    let cases = vec![
+        (
+            "set annual_salary = 'x'",
+            "TypeMismatch(\"Cannot convert String to i32\")",
+        ),
+        ("set durp = 1", "ColumnNotFound(\"durp\")"),
+        (
+            "set name = 'x', name = 'y'",
+            "Error(\"Duplicate column: name\")",
+        ),
+        (
+            "set name = 'x' where annual_salary > 'y'",
+            "TypeMismatch(\"Cannot compare i32 with String\")",
+        ),
+    ];
+    for (assignments, expected) in cases {
+        let query = format!("update {} {};", table_name, assignments);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected update to fail: {}", assignments),
+        }
+    }
+    assert_eq!(read_table_file(table_name), content);
+}
+
+#[test]
+fn test_delete_where() {
+    let table_name = "test_delete_where";
+    write_test_table(table_name);
+    let query = format!("delete from {} where annual_salary < 100000;", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(2)
+    ));
+
+    let query = format!(
+        "select name, annual_salary, final_grade from {};",
+        table_name
+    );
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            let columns = table.get_columns();
+            assert_eq!(
+                columns.get("name").unwrap(),
+                &vec![DataType::String("Lenon".to_string())]
+            );
+            assert_eq!(
+                columns.get("annual_salary").unwrap(),
+                &vec![DataType::Integer32(200000)]
+            );
+            assert_eq!(
+                columns.get("final_grade").unwrap(),
+                &vec![DataType::Float32(3.0)]
+            );
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+
+    let query = format!("delete from {} where name = 'John';", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(0)
+    ));
+    let query = format!("delete from {};", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+    let query = format!("select name from {};", table_name);
+    match execute_query(&query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_columns().get("name").unwrap().len(), 0);
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+}
+
+#[test]
+fn test_delete_errors() {
+    let table_name = "test_delete_errors";
+    write_test_table(table_name);
+    let content = read_table_file(table_name);
+    let query = format!("delete from {} where durp = 1;", table_name);
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert_eq!(error, "ColumnNotFound(\"durp\")"),
+        _ => panic!("Expected delete to fail"),
+    }
+    assert_eq!(read_table_file(table_name), content);
+
+    match execute_query("delete from test_delete_missing_table;") {
+        CommandResult::Error(error) => {
+            assert_eq!(error, "TableNotFound(\"test_delete_missing_table\")")
+        }
+        _ => panic!("Expected table not found error"),
+    }
+}
+
+#[test]
+fn test_select_order_by() {
+    let table_name = "test_select_order_by";
+    remove_test_table(table_name);
+    let query = format!(
+        "create table {} (name text, grade float, age int);",
+        table_name
+    );
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    // NaN cannot be written as a literal, so the rows are saved directly
+    let mut table = InMemoryTable::from_schema(
+        table_name.to_string(),
+        vec![
+            ("name".to_string(), DataType::String("".to_string())),
+            ("grade".to_string(), DataType::Float32(0.0)),
+            ("age".to_string(), DataType::Integer32(0)),
+        ],
+    );
+    let rows = vec![
+        ("a", 3.0, 20),
+        ("b", f32::NAN, 30),
+        ("c", -1.5, 20),
+        ("d", 3.0, 10),
+        ("e", 7.25, 30),
+    ];
+    for (name, grade, age) in rows {
+        let columns = &mut table.columns;
+        columns
+            .get_mut("name")
+            .unwrap()
+            .push(DataType::String(name.to_string()));
+        columns
+            .get_mut("grade")
+            .unwrap()
+            .push(DataType::Float32(grade));
+        columns
+            .get_mut("age")
+            .unwrap()
+            .push(DataType::Integer32(age));
+    }
+    table
+        .save(SaveMode::Append, FileFormat::SimpleColumnar)
+        .unwrap();
+
+    let cases = vec![
+        ("order by grade", vec!["c", "a", "d", "e", "b"]),
+        ("order by grade desc", vec!["b", "e", "a", "d", "c"]),
+        (
+            "order by age desc, grade asc",
+            vec!["e", "b", "c", "a", "d"],
+        ),
+        ("order by age, name desc", vec!["d", "c", "a", "e", "b"]),
+        ("where age > 10 order by grade limit 2", vec!["c", "a"]),
+        ("order by name limit 2 offset 1", vec!["b", "c"]),
+        ("order by name offset 4", vec!["e"]),
+        ("order by name limit 0", vec![]),
+        ("order by name offset 10", vec![]),
+    ];
+    for (clauses, expected) in cases {
+        let query = format!("select name from {} {};", table_name, clauses);
+        match execute_query(&query) {
+            CommandResult::RetrievedDataSuccess(table) => {
+                let expected: Vec<DataType> = expected
+                    .iter()
+                    .map(|name| DataType::String(name.to_string()))
+                    .collect();
+                assert_eq!(table.get_columns().get("name").unwrap(), &expected);
+                // the sort keys are only loaded to sort the rows
+                assert_eq!(table.get_select_columns(), &vec!["name".to_string()]);
+            }
+            _ => panic!("Expected table to be retrieved: {}", clauses),
+        }
+    }
+}
+
+#[test]
+fn test_datatype_total_ordering() {
+    use std::cmp::Ordering;
+    let ordered = vec![
+        DataType::Float32(f32::NEG_INFINITY),
+        DataType::Integer32(-3),
+        DataType::Float32(-0.5),
+        DataType::Integer32(2),
+        DataType::Float32(2.5),
+        DataType::Float32(f32::INFINITY),
+        DataType::Float32(f32::NAN),
+        DataType::String("".to_string()),
+        DataType::String("A".to_string()),
+        DataType::String("a".to_string()),
+    ];
+    for (i, left) in ordered.iter().enumerate() {
+        for (j, right) in ordered.iter().enumerate() {
+            assert_eq!(left.total_cmp(right), i.cmp(&j), "{:?} {:?}", left, right);
+        }
+    }
+    assert_eq!(
+        DataType::Integer32(1).total_cmp(&DataType::Float32(1.0)),
+        Ordering::Equal
+    );
+}
+
+/// Creates an employees table, used by the aggregation tests.
+fn write_employees_table(table_name: &str) {
+    remove_test_table(table_name);
+    let query = format!(
+        "create table {} (name text, dept text, salary int, bonus float);",
+        table_name
+    );
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    let query = format!(
+        "insert into {} values ('Ann', 'eng', 100, 1.5), ('Bob', 'ops', 50, 0.5), \
+        ('Cid', 'eng', 300, 2.0), ('Dan', 'sales', 70, 1.0), ('Eve', 'ops', 30, 0.25);",
+        table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(5)
+    ));
+}
+
+/// Runs a query expected to retrieve a table, returning its rows in the selected column order.
+fn query_rows(query: &str) -> (Vec<String>, Vec<Vec<DataType>>) {
+    match execute_query(query) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            let names = table.get_select_columns().clone();
+            let columns = table.get_columns();
+            let number_rows = columns.values().next().map(|c| c.len()).unwrap_or(0);
+            let rows = (0..number_rows)
+                .map(|row| {
+                    names
+                        .iter()
+                        .map(|name| columns.get(name).unwrap()[row].clone())
+                        .collect()
+                })
+                .collect();
+            return (names, rows);
+        }
+        CommandResult::Error(error) => panic!("Query {} failed: {}", query, error),
+        _ => panic!("Expected table to be retrieved: {}", query),
+    }
+}
+
+#[test]
+fn test_select_group_by() {
+    let table_name = "test_select_group_by";
+    write_employees_table(table_name);
+
+    let query = format!(
+        "select dept, count(*), sum(salary), avg(salary), min(name), max(bonus), sum(bonus) \
+        from {} group by dept;",
+        table_name
+    );
+    let (names, rows) = query_rows(&query);
+    assert_eq!(
+        names,
+        vec![
+            "dept",
+            "COUNT(*)",
+            "SUM(salary)",
+            "AVG(salary)",
+            "MIN(name)",
+            "MAX(bonus)",
+            "SUM(bonus)"
+        ]
+    );
+    let string = |s: &str| DataType::String(s.to_string());
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("eng"),
+                DataType::Integer32(2),
+                DataType::Integer32(400),
+                DataType::Float32(200.0),
+                string("Ann"),
+                DataType::Float32(2.0),
+                DataType::Float32(3.5)
+            ],
+            vec![
+                string("ops"),
+                DataType::Integer32(2),
+                DataType::Integer32(80),
+                DataType::Float32(40.0),
+                string("Bob"),
+                DataType::Float32(0.5),
+                DataType::Float32(0.75)
+            ],
+            vec![
+                string("sales"),
+                DataType::Integer32(1),
+                DataType::Integer32(70),
+                DataType::Float32(70.0),
+                string("Dan"),
+                DataType::Float32(1.0),
+                DataType::Float32(1.0)
+            ],
+        ]
+    );
+
+    // aggregates can sort the groups without being selected
+    let query = format!(
+        "select dept from {} where salary > 40 group by dept order by sum(salary) desc limit 2;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows, vec![vec![string("eng")], vec![string("sales")]]);
+
+    let query = format!("select count(*), max(salary) from {};", table_name);
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![vec![DataType::Integer32(5), DataType::Integer32(300)]]
+    );
+
+    // without GROUP BY, an empty table is still a single group
+    let query = format!(
+        "select count(*), sum(salary), avg(bonus) from {} where salary > 1000;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows[0][0], DataType::Integer32(0));
+    assert_eq!(rows[0][1], DataType::Null);
+    assert_eq!(rows[0][2], DataType::Null);
+    let query = format!(
+        "select dept, count(*) from {} where salary > 1000 group by dept;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert!(rows.is_empty());
+}
+
+#[test]
+fn test_select_group_by_errors() {
+    let table_name = "test_select_group_by_errors";
+    write_employees_table(table_name);
+    let query = format!("update {} set salary = 2147483647;", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(5)
+    ));
+
+    let cases = vec![
+        ("sum(name)", "TypeMismatch(\"Cannot apply SUM to String\")"),
+        ("avg(dept)", "TypeMismatch(\"Cannot apply AVG to String\")"),
+        ("sum(durp)", "ColumnNotFound(\"durp\")"),
+        (
+            "sum(salary)",
+            "OutOfRange(\"SUM 10737418235 is out of range for i32\")",
+        ),
+    ];
+    for (aggregate, expected) in cases {
+        let query = format!("select {} from {};", aggregate, table_name);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected aggregate to fail: {}", aggregate),
+        }
+    }
+    // averages are computed without overflowing
+    let query = format!("select avg(salary) from {};", table_name);
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows, vec![vec![DataType::Float32(2147483647.0)]]);
+}
+
+#[test]
+fn test_select_wildcard() {
+    let table_name = "test_select_wildcard";
+    write_employees_table(table_name);
+    let query = format!(
+        "select * from {} where salary < 60 order by salary;",
+        table_name
+    );
+    let (names, rows) = query_rows(&query);
+    assert_eq!(names, vec!["name", "dept", "salary", "bonus"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                DataType::String("Eve".to_string()),
+                DataType::String("ops".to_string()),
+                DataType::Integer32(30),
+                DataType::Float32(0.25)
+            ],
+            vec![
+                DataType::String("Bob".to_string()),
+                DataType::String("ops".to_string()),
+                DataType::Integer32(50),
+                DataType::Float32(0.5)
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_select_having() {
+    let table_name = "test_select_having";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let cases = vec![
+        // aggregates that are not selected
+        (
+            "select dept from {} group by dept having count(*) > 1 and max(salary) > 60;",
+            vec![vec![string("eng")]],
+        ),
+        (
+            "select dept, avg(bonus) from {} group by dept having avg(bonus) < 1.5 order by dept;",
+            vec![
+                vec![string("ops"), DataType::Float32(0.375)],
+                vec![string("sales"), DataType::Float32(1.0)],
+            ],
+        ),
+        // grouping keys
+        (
+            "select dept, sum(salary) from {} group by dept having dept <> 'eng' or sum(salary) > 1000;",
+            vec![
+                vec![string("ops"), DataType::Integer32(80)],
+                vec![string("sales"), DataType::Integer32(70)],
+            ],
+        ),
+        // without GROUP BY, the whole table is a single group
+        (
+            "select count(*) from {} having sum(salary) > 1000;",
+            vec![],
+        ),
+    ];
+    for (query, expected) in cases {
+        let query = query.replace("{}", table_name);
+        let (_, rows) = query_rows(&query);
+        assert_eq!(rows, expected, "{}", query);
+    }
+
+    let query = format!(
+        "select dept from {} group by dept having count(*) > 'x';",
+        table_name
+    );
+    match execute_query(&query) {
+        CommandResult::Error(error) => {
+            assert_eq!(error, "TypeMismatch(\"Cannot compare i32 with String\")")
+        }
+        _ => panic!("Expected having to fail"),
+    }
+
+    let query = format!(
+        "select dept from {} group by dept having salary > 1;",
+        table_name
+    );
+    let expected = "InvalidQuery(\"Column salary must appear in the GROUP BY clause or be used in an aggregate function\")";
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert_eq!(error, expected),
+        _ => panic!("Expected an invalid query"),
+    }
+    // subqueries are validated before any command runs
+    let query = format!(
+        "select dept from {} where exists (select salary from {} group by dept);",
+        table_name, table_name
+    );
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert_eq!(error, expected),
+        _ => panic!("Expected an invalid subquery"),
+    }
+}
+
+/// Creates the departments and employees tables, used by the join tests.
+fn write_join_tables(departments: &str, employees: &str) {
+    remove_test_table(departments);
+    remove_test_table(employees);
+    let queries = vec![
+        format!("create table {} (id int, dept_name text);", departments),
+        format!(
+            "insert into {} values (1, 'eng'), (2, 'ops'), (3, 'empty');",
+            departments
+        ),
+        format!(
+            "create table {} (id int, name text, dept_id int, salary int, manager_id int);",
+            employees
+        ),
+        format!(
+            "insert into {} values (10, 'Ann', 1, 100, 10), (11, 'Bob', 2, 50, 10), \
+            (12, 'Cid', 1, 300, 10), (13, 'Dan', 4, 70, 11);",
+            employees
+        ),
+    ];
+    for query in queries {
+        assert!(!matches!(execute_query(&query), CommandResult::Error(_)));
+    }
+}
+
+#[test]
+fn test_select_inner_join() {
+    let departments = "test_select_inner_join_departments";
+    let employees = "test_select_inner_join_employees";
+    write_join_tables(departments, employees);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let query = format!(
+        "select e.name, d.dept_name from {} e join {} as d on e.dept_id = d.id order by e.name;",
+        employees, departments
+    );
+    let (names, rows) = query_rows(&query);
+    assert_eq!(names, vec!["e.name", "d.dept_name"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Ann"), string("eng")],
+            vec![string("Bob"), string("ops")],
+            vec![string("Cid"), string("eng")],
+        ]
+    );
+
+    // unqualified names and table names work when they are not ambiguous
+    let query = format!(
+        "select name, dept_name from {0} inner join {1} on dept_id = {1}.id where salary < 200 order by name;",
+        employees, departments
+    );
+    let (names, rows) = query_rows(&query);
+    assert_eq!(names, vec!["name", "dept_name"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Ann"), string("eng")],
+            vec![string("Bob"), string("ops")],
+        ]
+    );
+
+    // a self join, through aliases
+    let query = format!(
+        "select e.name, m.name from {0} e join {0} m on e.manager_id = m.id order by e.name;",
+        employees
+    );
+    let (names, rows) = query_rows(&query);
+    assert_eq!(names, vec!["e.name", "m.name"]);
+    let managers: Vec<&DataType> = rows.iter().map(|row| &row[1]).collect();
+    assert_eq!(
+        managers,
+        vec![
+            &string("Ann"),
+            &string("Ann"),
+            &string("Ann"),
+            &string("Bob")
+        ]
+    );
+
+    // conditions without equalities between the tables compare every pair of rows
+    let query = format!(
+        "select d.dept_name, e.name from {} d join {} e on e.salary > 90 and d.id = 3;",
+        departments, employees
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("empty"), string("Ann")],
+            vec![string("empty"), string("Cid")],
+        ]
+    );
+
+    let query = format!(
+        "select d.dept_name, count(*), sum(e.salary) from {} e join {} d on e.dept_id = d.id \
+        group by d.dept_name order by d.dept_name;",
+        employees, departments
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("eng"),
+                DataType::Integer32(2),
+                DataType::Integer32(400)
+            ],
+            vec![
+                string("ops"),
+                DataType::Integer32(1),
+                DataType::Integer32(50)
+            ],
+        ]
+    );
+
+    // the wildcard only qualifies the names that would be ambiguous
+    let query = format!(
+        "select * from {} d join {} e on d.id = e.dept_id where e.name = 'Bob';",
+        departments, employees
+    );
+    let (names, rows) = query_rows(&query);
+    assert_eq!(
+        names,
+        vec![
+            "d.id",
+            "dept_name",
+            "e.id",
+            "name",
+            "dept_id",
+            "salary",
+            "manager_id"
+        ]
+    );
+    assert_eq!(rows[0][3], string("Bob"));
+}
+
+#[test]
+fn test_select_inner_join_errors() {
+    let departments = "test_select_inner_join_errors_departments";
+    let employees = "test_select_inner_join_errors_employees";
+    write_join_tables(departments, employees);
+    let cases = vec![
+        (
+            format!(
+                "select id from {} join {} on dept_id = dept_name;",
+                employees, departments
+            ),
+            "TypeMismatch(\"Cannot compare i32 with String\")",
+        ),
+        (
+            format!(
+                "select id from {0} join {1} on dept_id = {1}.id;",
+                employees, departments
+            ),
+            "AmbiguousColumn(\"id\")",
+        ),
+        (
+            format!(
+                "select e.durp from {} e join {} d on e.dept_id = d.id;",
+                employees, departments
+            ),
+            "ColumnNotFound(\"e.durp\")",
+        ),
+    ];
+    for (query, expected) in cases {
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected join to fail: {}", query),
+        }
+    }
+}
+
+#[test]
+fn test_join_projection_pushdown() {
+    let departments = "test_join_projection_pushdown_departments";
+    let employees = "test_join_projection_pushdown_employees";
+    write_join_tables(departments, employees);
+    let query = format!(
+        "select e.name from {} e join {} d on e.dept_id = d.id;",
+        employees, departments
+    );
+    let commands = parse(query).unwrap();
+    let columns = match &commands[0] {
+        Command::SelectFromAs(columns, _, _) => columns.clone(),
+        _ => panic!("Expected the employees table to be loaded first"),
+    };
+
+    // each side only reads the columns referenced through its alias
+    let command = Command::SelectFromAs(columns.clone(), employees.to_string(), "e".to_string());
+    match VirtualMachine::new().execute(vec![command]) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_select_columns(), &vec!["e.name", "e.dept_id"]);
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+    let command = Command::SelectFromAs(columns, departments.to_string(), "d".to_string());
+    match VirtualMachine::new().execute(vec![command]) {
+        CommandResult::RetrievedDataSuccess(table) => {
+            assert_eq!(table.get_select_columns(), &vec!["d.id"]);
+        }
+        _ => panic!("Expected table to be retrieved"),
+    }
+}
+
+#[test]
+fn test_select_outer_join() {
+    let departments = "test_select_outer_join_departments";
+    let employees = "test_select_outer_join_employees";
+    write_join_tables(departments, employees);
+    let string = |s: &str| DataType::String(s.to_string());
+    let null = DataType::Null;
+
+    let cases = vec![
+        (
+            "left join",
+            vec![
+                vec![string("Ann"), string("eng")],
+                vec![string("Bob"), string("ops")],
+                vec![string("Cid"), string("eng")],
+                vec![string("Dan"), null.clone()],
+            ],
+        ),
+        (
+            "right outer join",
+            vec![
+                vec![string("Ann"), string("eng")],
+                vec![string("Bob"), string("ops")],
+                vec![string("Cid"), string("eng")],
+                vec![null.clone(), string("empty")],
+            ],
+        ),
+        (
+            "full outer join",
+            vec![
+                vec![string("Ann"), string("eng")],
+                vec![string("Bob"), string("ops")],
+                vec![string("Cid"), string("eng")],
+                vec![string("Dan"), null.clone()],
+                vec![null.clone(), string("empty")],
+            ],
+        ),
+    ];
+    for (join, expected) in cases {
+        let query = format!(
+            "select e.name, d.dept_name from {} e {} {} d on e.dept_id = d.id;",
+            employees, join, departments
         );
-        let table = load_result.unwrap();
+        let (_, rows) = query_rows(&query);
+        assert_eq!(rows, expected, "{}", join);
+    }
+
+    // comparisons with NULL are unknown, so they match neither the predicate nor its negation
+    for filter in ["d.dept_name <> 'eng'", "not d.dept_name = 'eng'"] {
+        let query = format!(
+            "select e.name from {} e left join {} d on e.dept_id = d.id where {};",
+            employees, departments, filter
+        );
+        let (_, rows) = query_rows(&query);
+        assert_eq!(rows, vec![vec![string("Bob")]], "{}", filter);
+    }
+    let query = format!(
+        "select d.dept_name from {} d left join {} e on e.dept_id = d.id where e.id is null;",
+        departments, employees
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows, vec![vec![string("empty")]]);
+
+    // aggregates skip NULLs, and are NULL when there is nothing to aggregate
+    let query = format!(
+        "select d.dept_name, count(e.id), count(*), sum(e.salary) from {} d left join {} e \
+        on e.dept_id = d.id group by d.dept_name order by d.dept_name;",
+        departments, employees
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("empty"),
+                DataType::Integer32(0),
+                DataType::Integer32(1),
+                null.clone()
+            ],
+            vec![
+                string("eng"),
+                DataType::Integer32(2),
+                DataType::Integer32(2),
+                DataType::Integer32(400)
+            ],
+            vec![
+                string("ops"),
+                DataType::Integer32(1),
+                DataType::Integer32(1),
+                DataType::Integer32(50)
+            ],
+        ]
+    );
 
-        let name_column = table.get_columns().get("name").unwrap();
-        let matches: Vec<bool> = vec!["John", "Lenon", "Mary"]
+    // NULLs sort last in ascending order and first in descending order, unless requested otherwise
+    let orders = vec![
+        (
+            "d.dept_name, e.name",
+            vec!["empty", "eng", "eng", "ops", "NULL"],
+        ),
+        (
+            "d.dept_name desc, e.name",
+            vec!["NULL", "ops", "eng", "eng", "empty"],
+        ),
+        (
+            "d.dept_name nulls first, e.name",
+            vec!["NULL", "empty", "eng", "eng", "ops"],
+        ),
+        (
+            "d.dept_name desc nulls last",
+            vec!["ops", "eng", "eng", "empty", "NULL"],
+        ),
+    ];
+    for (order_by, expected) in orders {
+        let query = format!(
+            "select d.dept_name from {} e full join {} d on e.dept_id = d.id order by {};",
+            employees, departments, order_by
+        );
+        let (_, rows) = query_rows(&query);
+        let names: Vec<String> = rows
             .iter()
-            .zip(name_column.iter())
-            .map(|(left, right)| -> bool {
-                match right {
-                    DataType::String(s) => s == left,
-                    _ => panic!("Found not string in name column!"),
-                }
+            .map(|row| match &row[0] {
+                DataType::String(s) => s.clone(),
+                other => other.name(),
             })
             .collect();
-        for m in matches.iter() {
-            assert!(m);
+        assert_eq!(names, expected, "{}", order_by);
+    }
+}
+
+#[test]
+fn test_select_subqueries() {
+    let departments = "test_select_subqueries_departments";
+    let employees = "test_select_subqueries_employees";
+    write_join_tables(departments, employees);
+    let string = |s: &str| DataType::String(s.to_string());
+    let names = |query: String| -> Vec<DataType> {
+        let (_, rows) = query_rows(&query);
+        return rows.into_iter().map(|row| row[0].clone()).collect();
+    };
+
+    let query = format!(
+        "select name from {} where dept_id in (select id from {}) order by name;",
+        employees, departments
+    );
+    assert_eq!(
+        names(query),
+        vec![string("Ann"), string("Bob"), string("Cid")]
+    );
+    let query = format!(
+        "select name from {} where dept_id not in (select id from {});",
+        employees, departments
+    );
+    assert_eq!(names(query), vec![string("Dan")]);
+
+    // NOT IN is unknown when the subquery returns a NULL
+    let query = format!(
+        "select name from {0} where dept_id not in \
+        (select d.id from {0} e left join {1} d on e.dept_id = d.id);",
+        employees, departments
+    );
+    assert_eq!(names(query), vec![]);
+
+    let query = format!(
+        "select name from {} where salary > (select avg(salary) from {});",
+        employees, employees
+    );
+    assert_eq!(names(query), vec![string("Cid")]);
+
+    // correlated subqueries, where unqualified columns refer to the subquery tables first
+    let query = format!(
+        "select dept_name from {} d where exists (select id from {} e where e.dept_id = d.id) \
+        order by dept_name;",
+        departments, employees
+    );
+    assert_eq!(names(query), vec![string("eng"), string("ops")]);
+    let query = format!(
+        "select dept_name from {} d where not exists (select id from {} e where e.dept_id = d.id);",
+        departments, employees
+    );
+    assert_eq!(names(query), vec![string("empty")]);
+    let query = format!(
+        "select name from {0} e where salary > (select avg(salary) from {0} where dept_id = e.dept_id);",
+        employees
+    );
+    assert_eq!(names(query), vec![string("Cid")]);
+    let query = format!(
+        "select name from {0} m where exists (select id from {0} e where e.manager_id = m.id and e.id <> m.id) \
+        order by name;",
+        employees
+    );
+    assert_eq!(names(query), vec![string("Ann"), string("Bob")]);
+
+    // a subquery may refer to any of its enclosing queries
+    let query = format!(
+        "select dept_name from {0} d where exists (select id from {1} e where e.dept_id = d.id \
+        and exists (select id from {1} m where m.id = e.manager_id and m.dept_id = d.id));",
+        departments, employees
+    );
+    assert_eq!(names(query), vec![string("eng")]);
+
+    let query = format!(
+        "select name, (select dept_name from {} d where d.id = e.dept_id) from {} e order by name;",
+        departments, employees
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "name".to_string(),
+            format!(
+                "(SELECT dept_name FROM {} AS d WHERE d.id = e.dept_id)",
+                departments
+            )
+        ]
+    );
+    let values: Vec<DataType> = rows.into_iter().map(|row| row[1].clone()).collect();
+    assert_eq!(
+        values,
+        vec![string("eng"), string("ops"), string("eng"), DataType::Null]
+    );
+
+    // derived tables
+    let query = format!(
+        "select d.dept_name, s.name from (select name, dept_id from {} where salary > 60) as s \
+        join {} d on s.dept_id = d.id order by s.name;",
+        employees, departments
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("eng"), string("Ann")],
+            vec![string("eng"), string("Cid")],
+        ]
+    );
+    let query = format!(
+        "select name from (select name, salary from {} order by salary desc limit 2) top order by name;",
+        employees
+    );
+    assert_eq!(names(query), vec![string("Ann"), string("Cid")]);
+}
+
+#[test]
+fn test_subquery_errors_and_writes() {
+    let departments = "test_subquery_errors_and_writes_departments";
+    let employees = "test_subquery_errors_and_writes_employees";
+    write_join_tables(departments, employees);
+    let cases = vec![
+        (
+            format!(
+                "select name from {0} where salary > (select salary from {0});",
+                employees
+            ),
+            format!(
+                "Subquery(\"Subquery returned more than one row: (SELECT salary FROM {})\")",
+                employees
+            ),
+        ),
+        (
+            format!(
+                "select name from {} where dept_id in (select id, dept_name from {});",
+                employees, departments
+            ),
+            "Subquery(\"Subquery must return a single column, found 2\")".to_string(),
+        ),
+        (
+            format!(
+                "select name from {} where dept_id in (select durp from {});",
+                employees, departments
+            ),
+            "Subquery(\"ColumnNotFound(\\\"durp\\\")\")".to_string(),
+        ),
+    ];
+    for (query, expected) in cases {
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected subquery to fail: {}", query),
         }
+    }
 
-        let name_column = table.get_columns().get("annual_salary").unwrap();
-        let matches: Vec<bool> = vec![60000, 200000, 30000]
-            .iter()
-            .zip(name_column.iter())
-            .map(|(left, right)| -> bool {
-                match right {
-                    DataType::Integer32(i) => i == left,
-                    _ => panic!("Found not integer in annual salary column!"),
-                }
-            })
-            .collect();
-        for m in matches.iter() {
-            assert!(m);
+    let query = format!(
+        "update {0} set salary = (select max(salary) from {0}) where name = 'Bob';",
+        employees
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+    let query = format!(
+        "delete from {} where dept_id not in (select id from {});",
+        employees, departments
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+    // the columns of the subqueries are not saved
+    let (columns, rows) = query_rows(&format!("select * from {} order by name;", employees));
+    assert_eq!(
+        columns,
+        vec!["id", "name", "dept_id", "salary", "manager_id"]
+    );
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1][3], DataType::Integer32(300));
+}
+
+#[test]
+fn test_select_computed_columns() {
+    let table_name = "test_select_computed_columns";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let query = format!(
+        "select name, salary * 2 + 1 as doubled, salary / 3, salary % 7, -bonus, salary * bonus total \
+        from {} where salary - 60 > 0 order by doubled desc;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "name",
+            "doubled",
+            "salary / 3",
+            "salary % 7",
+            "-bonus",
+            "total"
+        ]
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("Cid"),
+                DataType::Integer32(601),
+                DataType::Integer32(100),
+                DataType::Integer32(6),
+                DataType::Float32(-2.0),
+                DataType::Float32(600.0)
+            ],
+            vec![
+                string("Ann"),
+                DataType::Integer32(201),
+                DataType::Integer32(33),
+                DataType::Integer32(2),
+                DataType::Float32(-1.5),
+                DataType::Float32(150.0)
+            ],
+            vec![
+                string("Dan"),
+                DataType::Integer32(141),
+                DataType::Integer32(23),
+                DataType::Integer32(0),
+                DataType::Float32(-1.0),
+                DataType::Float32(70.0)
+            ],
+        ]
+    );
+
+    let query = format!(
+        "select dept, sum(salary) * 2 as twice, sum(salary * bonus) from {} \
+        group by dept order by twice;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["dept", "twice", "SUM(salary * bonus)"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("sales"),
+                DataType::Integer32(140),
+                DataType::Float32(70.0)
+            ],
+            vec![
+                string("ops"),
+                DataType::Integer32(160),
+                DataType::Float32(32.5)
+            ],
+            vec![
+                string("eng"),
+                DataType::Integer32(800),
+                DataType::Float32(750.0)
+            ],
+        ]
+    );
+
+    let query = format!(
+        "update {} set salary = (salary - 1) / 3 * -1 where name = 'Dan';",
+        table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+    // integer division truncates towards zero
+    let query = format!(
+        "select salary as s, salary / 2 from {} where name = 'Dan';",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["s", "salary / 2"]);
+    assert_eq!(
+        rows,
+        vec![vec![DataType::Integer32(-23), DataType::Integer32(-11)]]
+    );
+}
+
+#[test]
+fn test_arithmetic_errors() {
+    let table_name = "test_arithmetic_errors";
+    write_employees_table(table_name);
+    let cases = vec![
+        ("salary / 0", "DivisionByZero(\"salary / 0\")"),
+        (
+            "bonus % (salary - salary)",
+            "DivisionByZero(\"bonus % (salary - salary)\")",
+        ),
+        (
+            "salary * 100000000",
+            "OutOfRange(\"salary * 100000000 is out of range\")",
+        ),
+        (
+            "bonus * 300000000000000000000000000000000000000.0",
+            "OutOfRange(\"bonus * 3e38 is out of range\")",
+        ),
+        ("name + 1", "TypeMismatch(\"Cannot apply + to String\")"),
+        ("-name", "TypeMismatch(\"Cannot negate String\")"),
+        ("name as salary, salary", "AmbiguousColumn(\"salary\")"),
+    ];
+    for (items, expected) in cases {
+        let query = format!("select {} from {};", items, table_name);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected arithmetic to fail: {}", items),
         }
+    }
 
-        let name_column = table.get_columns().get("final_grade").unwrap();
-        let matches: Vec<bool> = vec![4.0, 3.0, 5.0]
-            .iter()
-            .zip(name_column.iter())
-            .map(|(left, right)| -> bool {
-                match right {
-                    DataType::Float32(f) => f == left,
-                    _ => panic!("Found not float in final grade column!"),
+    // NULL operands give NULL
+    let departments = "test_arithmetic_errors_departments";
+    let employees = "test_arithmetic_errors_employees";
+    write_join_tables(departments, employees);
+    let query = format!(
+        "select d.dept_name, e.salary * 2 + 1 from {} d left join {} e on d.id = e.dept_id \
+        where e.id is null;",
+        departments, employees
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["d.dept_name", "e.salary * 2 + 1"]);
+    assert_eq!(
+        rows,
+        vec![vec![DataType::String("empty".to_string()), DataType::Null]]
+    );
+}
+
+#[test]
+fn test_scalar_functions() {
+    let table_name = "test_scalar_functions";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let query = format!(
+        "select upper(name), lower(dept) as d, length(dept), substr(dept, 2), substr(name, 0, 3), \
+        replace(dept, 'e', 'E') from {} where length(dept) > 3 or Upper(name) = 'ANN' order by name;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "UPPER(name)",
+            "d",
+            "LENGTH(dept)",
+            "SUBSTR(dept, 2)",
+            "SUBSTR(name, 0, 3)",
+            "REPLACE(dept, 'e', 'E')"
+        ]
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("ANN"),
+                string("eng"),
+                DataType::Integer32(3),
+                string("ng"),
+                string("An"),
+                string("Eng")
+            ],
+            vec![
+                string("DAN"),
+                string("sales"),
+                DataType::Integer32(5),
+                string("ales"),
+                string("Da"),
+                string("salEs")
+            ],
+        ]
+    );
+
+    let query = format!(
+        "select abs(0 - salary), round(bonus), round(salary * -1, -2), round(bonus / 3, 2), \
+        floor(-bonus), ceil(bonus), coalesce(salary, bonus) from {} where name = 'Bob';",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![vec![
+            DataType::Integer32(50),
+            DataType::Float32(1.0),
+            DataType::Integer32(-100),
+            DataType::Float32(0.17),
+            DataType::Float32(-1.0),
+            DataType::Float32(1.0),
+            DataType::Float32(50.0)
+        ]]
+    );
+
+    // functions return NULL for NULL arguments, except COALESCE
+    let departments = "test_scalar_functions_departments";
+    let employees = "test_scalar_functions_employees";
+    write_join_tables(departments, employees);
+    let query = format!(
+        "select upper(e.name), coalesce(e.name, '(none)'), coalesce(e.salary, d.id * 1000) \
+        from {} d left join {} e on d.id = e.dept_id order by d.id desc, e.id;",
+        departments, employees
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![DataType::Null, string("(none)"), DataType::Integer32(3000)],
+            vec![string("BOB"), string("Bob"), DataType::Integer32(50)],
+            vec![string("ANN"), string("Ann"), DataType::Integer32(100)],
+            vec![string("CID"), string("Cid"), DataType::Integer32(300)],
+        ]
+    );
+    let query = format!(
+        "update {} set name = lower(name) where trim(upper(name)) = 'ANN';",
+        employees
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+    let query = format!("select name from {} where id = 10;", employees);
+    assert_eq!(query_rows(&query).1, vec![vec![string("ann")]]);
+
+    // NULL may also be given as a literal argument
+    let query = format!(
+        "select coalesce(null, 3), coalesce(null, null), coalesce(null, 1.5, 2), upper(null) \
+        from {} where id = 10;",
+        employees
+    );
+    assert_eq!(
+        query_rows(&query).1,
+        vec![vec![
+            DataType::Integer32(3),
+            DataType::Null,
+            DataType::Float32(1.5),
+            DataType::Null
+        ]]
+    );
+}
+
+#[test]
+fn test_scalar_function_errors() {
+    let table_name = "test_scalar_function_errors";
+    write_employees_table(table_name);
+    let cases = vec![
+        ("durp(name)", "UnknownFunction(\"DURP\")"),
+        (
+            "upper(name, dept)",
+            "WrongNumberOfArguments(\"UPPER expects 1 argument(s), found 2\")",
+        ),
+        (
+            "substr(name)",
+            "WrongNumberOfArguments(\"SUBSTR expects 2 to 3 argument(s), found 1\")",
+        ),
+        (
+            "coalesce()",
+            "WrongNumberOfArguments(\"COALESCE expects at least 1 argument(s), found 0\")",
+        ),
+        (
+            "upper(salary)",
+            "TypeMismatch(\"UPPER expects String as argument 1, found i32\")",
+        ),
+        (
+            "round(bonus, 1.5)",
+            "TypeMismatch(\"ROUND expects i32 as argument 2, found f32\")",
+        ),
+        (
+            "abs(name)",
+            "TypeMismatch(\"ABS expects a number as argument 1, found String\")",
+        ),
+        (
+            "coalesce(salary, name)",
+            "TypeMismatch(\"Cannot mix i32 with String\")",
+        ),
+        (
+            "substr(name, 1, -1)",
+            "OutOfRange(\"SUBSTR length cannot be negative, found -1\")",
+        ),
+        (
+            "abs(salary - salary - 2147483647 - 1)",
+            "OutOfRange(\"ABS(-2147483648) is out of range\")",
+        ),
+    ];
+    for (items, expected) in cases {
+        let query = format!("select {} from {};", items, table_name);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected function to fail: {}", items),
+        }
+    }
+    // calls are checked even when no rows are evaluated, including in subqueries
+    let queries = vec![
+        format!(
+            "select name from {} where 1 = 0 and durp(name) = 1;",
+            table_name
+        ),
+        format!(
+            "select name from {0} where 1 = 0 and exists (select durp(x) from {0});",
+            table_name
+        ),
+        format!("delete from {} where 1 = 0 and durp(name) = 1;", table_name),
+    ];
+    for query in queries {
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, "UnknownFunction(\"DURP\")"),
+            _ => panic!("Expected function to fail: {}", query),
+        }
+    }
+}
+
+#[test]
+fn test_case_expressions() {
+    let table_name = "test_case_expressions";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let query = format!(
+        "select name, case when salary >= 100 then 'high' when salary >= 50 then 'mid' else 'low' end as band, \
+        case dept when 'eng' then 1 when 'ops' then 2.5 end from {} \
+        where case dept when 'sales' then 0 else 1 end = 1 \
+        order by case when dept = 'ops' then 0 else 1 end, name;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "name",
+            "band",
+            "CASE dept WHEN 'eng' THEN 1 WHEN 'ops' THEN 2.5 END"
+        ]
+    );
+    // integers are widened into floats when another branch is a float
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Bob"), string("mid"), DataType::Float32(2.5)],
+            vec![string("Eve"), string("low"), DataType::Float32(2.5)],
+            vec![string("Ann"), string("high"), DataType::Float32(1.0)],
+            vec![string("Cid"), string("high"), DataType::Float32(1.0)],
+        ]
+    );
+
+    let query = format!(
+        "select dept, sum(case when bonus >= 1 then 1 else 0 end) as generous, \
+        count(case when salary > 60 then name end), \
+        case when count(*) > 1 then 'many' else 'one' end from {} group by dept order by dept;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "dept",
+            "generous",
+            "COUNT(CASE WHEN salary > 60 THEN name END)",
+            "CASE WHEN COUNT(*) > 1 THEN 'many' ELSE 'one' END"
+        ]
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("eng"),
+                DataType::Integer32(2),
+                DataType::Integer32(2),
+                string("many")
+            ],
+            vec![
+                string("ops"),
+                DataType::Integer32(0),
+                DataType::Integer32(0),
+                string("many")
+            ],
+            vec![
+                string("sales"),
+                DataType::Integer32(1),
+                DataType::Integer32(1),
+                string("one")
+            ],
+        ]
+    );
+
+    // the types of the branches are checked even when no row reaches them
+    let cases = vec![
+        (
+            format!(
+                "select case when salary > 1 then 'a' else 0 end from {};",
+                table_name
+            ),
+            "TypeMismatch(\"Cannot mix String with i32\")",
+        ),
+        (
+            format!("select case dept when 1 then 'x' end from {};", table_name),
+            "TypeMismatch(\"Cannot compare String with i32\")",
+        ),
+        (
+            format!(
+                "select name from {} where 1 = 0 and case when salary > 1 then 'a' else 0 end = 'a';",
+                table_name
+            ),
+            "TypeMismatch(\"Cannot mix String with i32\")",
+        ),
+    ];
+    for (query, expected) in cases {
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected CASE to fail: {}", query),
+        }
+    }
+}
+
+#[test]
+fn test_select_distinct() {
+    let table_name = "test_select_distinct";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let query = format!(
+        "select distinct dept from {} order by dept desc;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["dept"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("sales")],
+            vec![string("ops")],
+            vec![string("eng")]
+        ]
+    );
+    // which duplicate is kept would decide the order by a column that is not selected
+    let query = format!(
+        "select distinct dept from {} order by salary desc;",
+        table_name
+    );
+    match execute_query(&query) {
+        CommandResult::Error(error) => assert_eq!(
+            error,
+            "InvalidQuery(\"For SELECT DISTINCT, ORDER BY expressions must appear in the select list\")"
+        ),
+        _ => panic!("Expected an invalid query"),
+    }
+
+    // duplicates are removed before the rows are limited, and NULLs are equal to each other
+    let query = format!(
+        "select distinct case when salary > 60 then dept end as rich, 1 as one from {} \
+        order by rich nulls first limit 2 offset 1;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["rich", "one"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("eng"), DataType::Integer32(1)],
+            vec![string("sales"), DataType::Integer32(1)]
+        ]
+    );
+
+    let query = format!(
+        "select distinct dept, salary / 100 from {} order by dept;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("eng"), DataType::Integer32(1)],
+            vec![string("eng"), DataType::Integer32(3)],
+            vec![string("ops"), DataType::Integer32(0)],
+            vec![string("sales"), DataType::Integer32(0)],
+        ]
+    );
+}
+
+#[test]
+fn test_distinct_aggregates() {
+    let table_name = "test_distinct_aggregates";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+
+    let query = format!(
+        "select count(distinct dept), count(dept), sum(distinct salary / 100), \
+        count(distinct case when salary > 60 then dept end) from {};",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "COUNT(DISTINCT dept)",
+            "COUNT(dept)",
+            "SUM(DISTINCT salary / 100)",
+            "COUNT(DISTINCT CASE WHEN salary > 60 THEN dept END)"
+        ]
+    );
+    // NULL values are not counted
+    assert_eq!(
+        rows,
+        vec![vec![
+            DataType::Integer32(3),
+            DataType::Integer32(5),
+            DataType::Integer32(4),
+            DataType::Integer32(2)
+        ]]
+    );
+
+    // the values are distinct within each group
+    let query = format!(
+        "select dept, count(distinct salary / 100), avg(distinct salary / 100) from {} \
+        group by dept having count(distinct salary / 100) > 1 or dept = 'ops' order by dept;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("eng"),
+                DataType::Integer32(2),
+                DataType::Float32(2.0)
+            ],
+            vec![
+                string("ops"),
+                DataType::Integer32(1),
+                DataType::Float32(0.0)
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_set_operations() {
+    let table_name = "test_set_operations";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+    let strings = |values: &[&str]| -> Vec<Vec<DataType>> {
+        values.iter().map(|value| vec![string(value)]).collect()
+    };
+
+    let cases = vec![
+        (
+            "select dept from {t} union select dept from {t} order by dept;",
+            strings(&["eng", "ops", "sales"]),
+        ),
+        (
+            "select dept from {t} where salary > 60 union all select dept from {t} where salary < 60 order by dept;",
+            strings(&["eng", "eng", "ops", "ops", "sales"]),
+        ),
+        (
+            "select dept from {t} where bonus >= 1 intersect select dept from {t} where salary < 200;",
+            strings(&["eng", "sales"]),
+        ),
+        (
+            "select dept from {t} intersect all select dept from {t} where salary < 200;",
+            strings(&["eng", "ops", "sales", "ops"]),
+        ),
+        (
+            "select dept from {t} except select dept from {t} where salary > 60;",
+            strings(&["ops"]),
+        ),
+        (
+            "select dept from {t} except all select dept from {t} where salary > 90;",
+            strings(&["ops", "sales", "ops"]),
+        ),
+        // INTERSECT binds tighter than UNION
+        (
+            "select dept from {t} where salary > 200 union select dept from {t} where salary < 40 \
+            intersect select dept from {t} where salary < 60;",
+            strings(&["eng", "ops"]),
+        ),
+        (
+            "select name from {t} where dept in (select 'eng' from {t} union select 'sales' from {t}) order by name;",
+            strings(&["Ann", "Cid", "Dan"]),
+        ),
+        (
+            "select n from (select name as n from {t} where salary > 60 except select name from {t} where dept = 'eng') as s;",
+            strings(&["Dan"]),
+        ),
+    ];
+    for (query, expected) in cases {
+        let query = query.replace("{t}", table_name);
+        let (columns, rows) = query_rows(&query);
+        assert_eq!(columns.len(), 1);
+        assert_eq!(rows, expected, "{}", query);
+    }
+
+    // the names follow the left side, and integers are widened into floats
+    let query = format!(
+        "select salary as amount from {t} union select bonus from {t} order by amount limit 3;",
+        t = table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["amount"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![DataType::Float32(0.25)],
+            vec![DataType::Float32(0.5)],
+            vec![DataType::Float32(1.0)]
+        ]
+    );
+
+    // NULLs are equal to each other
+    let query = format!(
+        "select case when salary > 60 then dept end as d from {t} \
+        union select case when salary > 200 then dept end from {t} order by d nulls first;",
+        t = table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![DataType::Null],
+            vec![string("eng")],
+            vec![string("sales")]
+        ]
+    );
+
+    let cases = vec![
+        (
+            format!(
+                "select name, dept from {t} union select name from {t};",
+                t = table_name
+            ),
+            "TypeMismatch(\"Each side of UNION must have the same number of columns, found 2 and 1\")",
+        ),
+        (
+            format!(
+                "select name from {t} where 1 = 0 except select salary from {t};",
+                t = table_name
+            ),
+            "TypeMismatch(\"Cannot mix String with i32 in column name of EXCEPT\")",
+        ),
+    ];
+    for (query, expected) in cases {
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected set operation to fail: {}", query),
+        }
+    }
+}
+
+#[test]
+fn test_window_functions() {
+    let table_name = "test_window_functions";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+    let int = |i: i32| DataType::Integer32(i);
+
+    let query = format!(
+        "select name, row_number() over (partition by dept order by salary desc) as n, \
+        rank() over (order by dept) as r, dense_rank() over (order by dept) as d from {} order by name;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["name", "n", "r", "d"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Ann"), int(2), int(1), int(1)],
+            vec![string("Bob"), int(1), int(3), int(2)],
+            vec![string("Cid"), int(1), int(1), int(1)],
+            vec![string("Dan"), int(1), int(5), int(3)],
+            vec![string("Eve"), int(2), int(3), int(2)],
+        ]
+    );
+
+    let query = format!(
+        "select name, sum(salary) over (order by salary) as running, \
+        avg(salary) over (order by salary rows between 1 preceding and 1 following) as moving, \
+        lag(name) over (order by salary), lead(salary, 2, 0) over (order by salary) as ahead, \
+        first_value(name) over (partition by dept order by salary) as lowest, count(*) over () as total \
+        from {} order by salary;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(
+        columns,
+        vec![
+            "name",
+            "running",
+            "moving",
+            "LAG(name) OVER (ORDER BY salary)",
+            "ahead",
+            "lowest",
+            "total"
+        ]
+    );
+    let avg = |sum: f64, count: f64| DataType::Float32((sum / count) as f32);
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                string("Eve"),
+                int(30),
+                avg(80.0, 2.0),
+                DataType::Null,
+                int(70),
+                string("Eve"),
+                int(5)
+            ],
+            vec![
+                string("Bob"),
+                int(80),
+                avg(150.0, 3.0),
+                string("Eve"),
+                int(100),
+                string("Eve"),
+                int(5)
+            ],
+            vec![
+                string("Dan"),
+                int(150),
+                avg(220.0, 3.0),
+                string("Bob"),
+                int(300),
+                string("Dan"),
+                int(5)
+            ],
+            vec![
+                string("Ann"),
+                int(250),
+                avg(470.0, 3.0),
+                string("Dan"),
+                int(0),
+                string("Ann"),
+                int(5)
+            ],
+            vec![
+                string("Cid"),
+                int(550),
+                avg(400.0, 2.0),
+                string("Ann"),
+                int(0),
+                string("Ann"),
+                int(5)
+            ],
+        ]
+    );
+
+    // the default frame of an ordered window ends at the last row with the same sort keys
+    let query = format!(
+        "select name, sum(salary) over (order by dept) from {} order by name;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    let sums: Vec<DataType> = rows.into_iter().map(|row| row[1].clone()).collect();
+    assert_eq!(sums, vec![int(400), int(480), int(400), int(550), int(480)]);
+
+    // a frame starting at the first row may end before the current row, leaving it empty
+    let query = format!(
+        "select name, sum(salary) over (partition by dept order by salary \
+        rows between unbounded preceding and 1 preceding) from {} order by name;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    let sums: Vec<DataType> = rows.into_iter().map(|row| row[1].clone()).collect();
+    assert_eq!(
+        sums,
+        vec![
+            DataType::Null,
+            int(30),
+            int(100),
+            DataType::Null,
+            DataType::Null
+        ]
+    );
+
+    // windows are computed over the groups, and may be used to sort without being selected
+    let query = format!(
+        "select dept, sum(salary), rank() over (order by sum(salary) desc) from {} group by dept order by dept;",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("eng"), int(400), int(1)],
+            vec![string("ops"), int(80), int(2)],
+            vec![string("sales"), int(70), int(3)],
+        ]
+    );
+    let query = format!(
+        "select name from {} order by row_number() over (order by salary desc);",
+        table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Cid")],
+            vec![string("Ann")],
+            vec![string("Dan")],
+            vec![string("Bob")],
+            vec![string("Eve")]
+        ]
+    );
+
+    let cases = vec![
+        (
+            "select lag(name, -1) over (order by salary) from {};",
+            "OutOfRange(\"LAG offset cannot be negative, found -1\")",
+        ),
+        (
+            "select lead(name, 'x') over (order by salary) from {};",
+            "TypeMismatch(\"LEAD offset must be an i32, found String\")",
+        ),
+        (
+            "select lag(name, 1, 0) over (order by salary) from {};",
+            "TypeMismatch(\"Cannot mix String with i32\")",
+        ),
+        (
+            "select sum(name) over () from {} where 1 = 0;",
+            "TypeMismatch(\"Cannot apply SUM to String\")",
+        ),
+    ];
+    for (query, expected) in cases {
+        let query = query.replace("{}", table_name);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected window function to fail: {}", query),
+        }
+    }
+}
+
+#[test]
+fn test_common_table_expressions() {
+    let table_name = "test_common_table_expressions";
+    write_employees_table(table_name);
+    let string = |s: &str| DataType::String(s.to_string());
+    let int = DataType::Integer32;
+
+    // a table may read the ones before it, and is read instead of a stored table
+    let query = format!(
+        "with rich as (select name, dept, salary from {t} where salary > 60), \
+        eng (who, pay) as (select name, salary from rich where dept = 'eng') \
+        select who, pay from eng order by pay desc;",
+        t = table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["who", "pay"]);
+    assert_eq!(
+        rows,
+        vec![vec![string("Cid"), int(300)], vec![string("Ann"), int(100)]]
+    );
+
+    // tables may be joined, aliased and read by subqueries
+    let query = format!(
+        "with totals as (select dept, sum(salary) as total from {t} group by dept) \
+        select e.name, t.total from {t} as e join totals as t on e.dept = t.dept \
+        where e.salary < (select max(total) from totals) order by e.name;",
+        t = table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Ann"), int(400)],
+            vec![string("Bob"), int(80)],
+            vec![string("Cid"), int(400)],
+            vec![string("Dan"), int(70)],
+            vec![string("Eve"), int(80)]
+        ]
+    );
+
+    let query = format!(
+        "with recursive counter (n) as (select 1 from {t} where name = 'Ann' \
+        union all select n + 1 from counter where n < 5) select sum(n), count(*) from counter;",
+        t = table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows, vec![vec![int(15), int(5)]]);
+
+    // without ALL, the recursion ends once no new rows are found
+    let query = format!(
+        "with recursive cycle (n) as (select 0 from {t} where name = 'Ann' \
+        union select (n + 1) % 3 from cycle) select n from cycle order by n;",
+        t = table_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows, vec![vec![int(0)], vec![int(1)], vec![int(2)]]);
+
+    let chart_name = "test_common_table_expressions_chart";
+    remove_test_table(chart_name);
+    let query = format!(
+        "create table {} (id int, name text, manager int);",
+        chart_name
+    );
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    let query = format!(
+        "insert into {} values (1, 'Ann', 0), (2, 'Bob', 1), (3, 'Cid', 1), (4, 'Dan', 2), (5, 'Eve', 4), (6, 'Fay', 0);",
+        chart_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(6)
+    ));
+    let query = format!(
+        "with recursive reports (id, name, level) as (\
+        select id, name, 0 from {t} where name = 'Bob' \
+        union all select e.id, e.name, r.level + 1 from {t} as e join reports as r on e.manager = r.id) \
+        select name, level from reports order by level, name;",
+        t = chart_name
+    );
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![string("Bob"), int(0)],
+            vec![string("Dan"), int(1)],
+            vec![string("Eve"), int(2)]
+        ]
+    );
+
+    let cases = vec![
+        (
+            "with recursive forever (n) as (select 1 from {} where name = 'Ann' \
+            union all select n + 1 from forever) select n from forever;",
+            "Error(\"Recursive query forever exceeded 1000 iterations\")",
+        ),
+        (
+            "with pair (a, b) as (select name from {}) select a from pair;",
+            "Error(\"WITH query pair has 2 columns, but its select returns 1\")",
+        ),
+        (
+            "with twice (a, a) as (select name, dept from {}) select a from twice;",
+            "Error(\"Duplicate column: a\")",
+        ),
+        (
+            "with recursive r (n) as (select 1 from {} where name = 'Ann' union select 'x' from r) select n from r;",
+            "TypeMismatch(\"Cannot mix i32 with String in column n of UNION\")",
+        ),
+    ];
+    for (query, expected) in cases {
+        let query = query.replace("{}", table_name);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected WITH query to fail: {}", query),
+        }
+    }
+}
+
+#[test]
+fn test_execute_script() {
+    let table_name = "test_execute_script";
+    remove_test_table(table_name);
+    let mut database = SteelDB::new();
+
+    let script = format!(
+        "create table {t} (name text, note text);\n\
+        insert into {t} values ('Ann', 'a;b'), ('Bob', 'c');\n\
+        select name, note from {t} order by name;",
+        t = table_name
+    );
+    let results = match database.execute(script) {
+        ExecutionResult::ScriptResult(results) => results,
+        _ => panic!("Expected the script to succeed"),
+    };
+    assert_eq!(results.len(), 3);
+    assert!(matches!(results[0], ExecutionResult::VoidOK));
+    assert!(matches!(results[1], ExecutionResult::RowsAffected(2)));
+    match &results[2] {
+        ExecutionResult::TableResult(table) => {
+            let notes = table.get_columns().get("note").unwrap();
+            assert_eq!(
+                notes,
+                &vec![
+                    DataType::String("a;b".to_string()),
+                    DataType::String("c".to_string())
+                ]
+            );
+        }
+        _ => panic!("Expected the select to retrieve a table"),
+    }
+
+    // the script stops at the failing statement, after running the ones before it
+    let script = format!(
+        "insert into {t} values ('Cid', 'd'); select missing from {t}; delete from {t};",
+        t = table_name
+    );
+    match database.execute(script) {
+        ExecutionResult::ScriptError(index, error) => {
+            assert_eq!(index, 1);
+            assert!(matches!(*error, ExecutionResult::CommandError(_)));
+        }
+        _ => panic!("Expected the script to fail"),
+    }
+    let (_, rows) = query_rows(&format!("select name from {};", table_name));
+    assert_eq!(rows.len(), 3);
+
+    // the whole script is parsed first, so nothing runs when a statement is invalid
+    let script = format!(
+        "delete from {t};\nselect name from {t} where;",
+        t = table_name
+    );
+    match database.execute(script) {
+        ExecutionResult::ScriptError(index, error) => {
+            assert_eq!(index, 1);
+            match *error {
+                ExecutionResult::ParseError(error) => {
+                    assert_eq!(error.kind, ParseErrorKind::UnrecognizedToken);
+                    assert_eq!(error.line, 2);
                 }
-            })
-            .collect();
-        for m in matches.iter() {
-            assert!(m);
+                _ => panic!("Expected a parse error"),
+            }
         }
+        _ => panic!("Expected the script to fail"),
     }
+    let (_, rows) = query_rows(&format!("select name from {};", table_name));
+    assert_eq!(rows.len(), 3);
 
-    #[test]
-    fn test_column_not_found() {
-        let table_name = "test_column_not_found";
-        write_test_table(&table_name);
-        let select_columns = vec!["durp".to_string()];
-        let load_result = InMemoryTable::new().load(
-            table_name.to_string(),
-            select_columns,
-            FileFormat::SimpleColumnar,
-        );
-        assert!(load_result.is_err());
+    // a single statement keeps its own result
+    let query = format!("select name from {}; ", table_name);
+    assert!(matches!(
+        database.execute(query),
+        ExecutionResult::TableResult(_)
+    ));
+
+    // empty statements are skipped
+    let query = format!("select name from {};;", table_name);
+    assert!(matches!(
+        database.execute(query),
+        ExecutionResult::TableResult(_)
+    ));
+    let script = format!("delete from {t};; select name from {t};", t = table_name);
+    match database.execute(script) {
+        ExecutionResult::ScriptResult(results) => {
+            assert_eq!(results.len(), 2);
+            assert!(matches!(results[0], ExecutionResult::RowsAffected(3)));
+        }
+        _ => panic!("Expected the script to succeed"),
+    }
+}
+
+fn prepared_names(
+    database: &mut SteelDB,
+    statement: &crate::Statement,
+    values: &[DataType],
+) -> Vec<DataType> {
+    match database.execute_prepared(statement, values) {
+        ExecutionResult::TableResult(table) => {
+            return table.get_columns().get("name").unwrap().clone();
+        }
+        _ => panic!("Expected the prepared select to retrieve a table"),
+    }
+}
+
+#[test]
+fn test_prepared_statements() {
+    let table_name = "test_prepared_statements";
+    remove_test_table(table_name);
+    let mut database = SteelDB::new();
+    let query = format!(
+        "create table {} (name text, grade float, age int);",
+        table_name
+    );
+    assert!(matches!(database.execute(query), ExecutionResult::VoidOK));
+
+    // an insert is prepared once and executed with each row
+    let insert = database
+        .prepare(format!("insert into {} values (?, ?, ?);", table_name))
+        .unwrap();
+    assert_eq!(insert.parameter_count(), 3);
+    let rows = [
+        ("Ann", 4.0, 20),
+        ("Bob", 3.0, 25),
+        ("x'; delete from t; --", 2.0, 30),
+    ];
+    for (name, grade, age) in rows.iter() {
+        let values = [
+            DataType::String(name.to_string()),
+            DataType::Float32(*grade),
+            DataType::Integer32(*age),
+        ];
+        assert!(matches!(
+            database.execute_prepared(&insert, &values),
+            ExecutionResult::RowsAffected(1)
+        ));
+    }
+
+    // numbered parameters may be used more than once, in any order
+    let select = database
+        .prepare(format!(
+            "select name from {} where age > $2 and grade >= $1 or name = $3 order by name;",
+            table_name
+        ))
+        .unwrap();
+    assert_eq!(select.parameter_count(), 3);
+    let names = prepared_names(
+        &mut database,
+        &select,
+        &[
+            DataType::Float32(3.0),
+            DataType::Integer32(0),
+            DataType::String("none".to_string()),
+        ],
+    );
+    assert_eq!(
+        names,
+        vec![
+            DataType::String("Ann".to_string()),
+            DataType::String("Bob".to_string())
+        ]
+    );
+    // the values are never parsed as SQL
+    let names = prepared_names(
+        &mut database,
+        &select,
+        &[
+            DataType::Float32(5.0),
+            DataType::Integer32(0),
+            DataType::String("x'; delete from t; --".to_string()),
+        ],
+    );
+    assert_eq!(
+        names,
+        vec![DataType::String("x'; delete from t; --".to_string())]
+    );
+
+    // parameters are bound inside subqueries
+    let subquery = database
+        .prepare(format!(
+            "select name from {t} where age = (select max(age) from {t} where grade > ?);",
+            t = table_name
+        ))
+        .unwrap();
+    let names = prepared_names(&mut database, &subquery, &[DataType::Float32(2.5)]);
+    assert_eq!(names, vec![DataType::String("Bob".to_string())]);
+    let names = prepared_names(&mut database, &subquery, &[DataType::Float32(3.5)]);
+    assert_eq!(names, vec![DataType::String("Ann".to_string())]);
+
+    // a statement must be executed with one value per parameter
+    match database.execute_prepared(&subquery, &[]) {
+        ExecutionResult::CommandError(error) => {
+            assert!(error.contains("Expected 1 parameter value(s), found 0"));
+        }
+        _ => panic!("Expected the execution to fail"),
+    }
+    // and values that cannot be converted fail the statement
+    assert!(matches!(
+        database.execute_prepared(&subquery, &[DataType::String("high".to_string())]),
+        ExecutionResult::CommandError(_)
+    ));
+
+    // parameters only have values in prepared statements
+    let query = format!("select name from {} where age > ?;", table_name);
+    match database.execute(query) {
+        ExecutionResult::CommandError(error) => {
+            assert!(error.contains("Parameter $1 has no value"));
+        }
+        _ => panic!("Expected the execution to fail"),
+    }
+    let query = format!("insert into {} values ('Cid', ?, 1);", table_name);
+    assert!(matches!(
+        database.execute(query),
+        ExecutionResult::CommandError(_)
+    ));
+    // and a statement cannot mix both kinds of parameters
+    let query = format!(
+        "select name from {} where age > ? and grade > $1;",
+        table_name
+    );
+    assert!(database.prepare(query).is_err());
+}
+
+fn to_steps(steps: &[(&str, &str)]) -> Vec<(String, String)> {
+    return steps
+        .iter()
+        .map(|(operation, detail)| (operation.to_string(), detail.to_string()))
+        .collect();
+}
+
+fn explained_steps(query: &str) -> Vec<(String, String)> {
+    let (_, rows) = query_rows(query);
+    return rows
+        .into_iter()
+        .map(|row| match (&row[1], &row[2]) {
+            (DataType::String(operation), DataType::String(detail)) => {
+                (operation.clone(), detail.clone())
+            }
+            _ => panic!("Expected the operation and detail to be text"),
+        })
+        .collect();
+}
+
+#[test]
+fn test_explain() {
+    let table_name = "test_explain";
+    remove_test_table(table_name);
+    let query = format!(
+        "create table {} (name text, grade float, age int);",
+        table_name
+    );
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    let query = format!("insert into {} values ('Ann', 4.0, 20);", table_name);
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+
+    let (names, rows) = query_rows(&format!(
+        "explain select name from {} where age > 18 order by grade desc;",
+        table_name
+    ));
+    assert_eq!(names, vec!["step", "operation", "detail"]);
+    assert_eq!(rows[0][0], DataType::Integer32(1));
+    let steps = explained_steps(&format!(
+        "explain select name from {} where age > 18 order by grade desc;",
+        table_name
+    ));
+    let expected = vec![
+        ("Scan", "test_explain, columns name, grade, age"),
+        ("Filter", "age > 18"),
+        ("Sort", "grade DESC"),
+        ("Project", "name"),
+    ];
+    assert_eq!(steps, to_steps(&expected));
+
+    // each joined table is scanned for its own columns
+    let steps = explained_steps(&format!(
+        "explain select a.name, count(*) from {t} a join {t} b on a.age = b.age \
+        group by a.name limit 5;",
+        t = table_name
+    ));
+    let expected = vec![
+        ("Scan", "test_explain AS a, columns a.name, a.age"),
+        ("Scan", "test_explain AS b, columns b.age"),
+        ("Join", "JOIN ON a.age = b.age"),
+        ("Aggregate", "COUNT(*) GROUP BY a.name"),
+        ("Limit", "LIMIT 5"),
+        ("Project", "a.name, COUNT(*)"),
+    ];
+    assert_eq!(steps, to_steps(&expected));
+
+    // the commands of a WITH clause are indented under it
+    let steps = explained_steps(&format!(
+        "explain with recursive r (n) as (select 1 from {} union all \
+        select n + 1 from r where n < 3) select n from r;",
+        table_name
+    ));
+    let expected = vec![
+        ("With recursive", "r (n)"),
+        ("  Scan", "test_explain, columns *"),
+        ("  Project", "1"),
+        (
+            "  Recursive UNION ALL",
+            "with the rows added by the previous step",
+        ),
+        ("    Scan", "r, columns n"),
+        ("    Filter", "n < 3"),
+        ("    Project", "n + 1"),
+        ("Scan", "r, columns n"),
+    ];
+    assert_eq!(steps, to_steps(&expected));
+
+    // explained statements are not executed
+    let steps = explained_steps(&format!(
+        "explain update {} set age = age + 1 where name = 'Ann';",
+        table_name
+    ));
+    let expected = vec![(
+        "Update",
+        "test_explain SET age = age + 1 WHERE name = 'Ann'",
+    )];
+    assert_eq!(steps, to_steps(&expected));
+    let steps = explained_steps(&format!("explain delete from {};", table_name));
+    assert_eq!(steps, to_steps(&[("Delete", "test_explain")]));
+    let (_, rows) = query_rows(&format!("select age from {};", table_name));
+    assert_eq!(rows, vec![vec![DataType::Integer32(20)]]);
+
+    // the explained statement must still be valid
+    let query = format!("explain select missing(age) from {};", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::Error(_)));
+}
+
+#[test]
+fn test_insert_errors_keep_file() {
+    let table_name = "test_insert_errors_keep_file";
+    write_test_table(table_name);
+    let content = read_table_file(table_name);
+    let cases = vec![
+        (
+            "values ('Ann', 'x', 1.0)",
+            "TypeMismatch(\"Cannot convert String to i32\")",
+        ),
+        ("(name, durp) values ('Ann', 1)", "ColumnNotFound(\"durp\")"),
+        (
+            "(name, annual_salary) values ('Ann', 1)",
+            "Error(\"Missing value for column: final_grade\")",
+        ),
+        (
+            "values ('Ann', 1, 1.0), ('Bob', 2)",
+            "Error(\"Expected 3 values, found 2\")",
+        ),
+    ];
+    for (values, expected) in cases {
+        let query = format!("insert into {} {};", table_name, values);
+        match execute_query(&query) {
+            CommandResult::Error(error) => assert_eq!(error, expected),
+            _ => panic!("Expected insert to fail: {}", values),
+        }
     }
+    assert_eq!(read_table_file(table_name), content);
+
+    // rows that do not match the stored schema are rejected before anything is written
+    let table = load_test_table(table_name.to_string(), vec!["name".to_string()]).unwrap();
+    let result = table.save(SaveMode::Append, FileFormat::SimpleColumnar);
+    assert!(matches!(result, Err(TableErrors::WriteError(_))));
+    assert_eq!(read_table_file(table_name), content);
+}
+
+#[test]
+fn test_null_values() {
+    let table_name = "test_null_values";
+    remove_test_table(table_name);
+    let query = format!("create table {} (a int, b text, c float);", table_name);
+    assert!(matches!(execute_query(&query), CommandResult::VoidSuccess));
+    // a string starting with a backslash is not confused with a stored NULL
+    let query = format!(
+        "insert into {} values (1, null, null), (null, '\\N', 2.5), (3, 'x', 1);",
+        table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(3)
+    ));
+    let string = |s: &str| DataType::String(s.to_string());
+    let query = format!("select a, b, c from {};", table_name);
+    let (_, rows) = query_rows(&query);
+    assert_eq!(
+        rows,
+        vec![
+            vec![DataType::Integer32(1), DataType::Null, DataType::Null],
+            vec![DataType::Null, string("\\N"), DataType::Float32(2.5)],
+            vec![DataType::Integer32(3), string("x"), DataType::Float32(1.0)],
+        ]
+    );
+
+    let query = format!(
+        "select null, a from {} where b is null or c is null;",
+        table_name
+    );
+    let (columns, rows) = query_rows(&query);
+    assert_eq!(columns, vec!["NULL", "a"]);
+    assert_eq!(rows, vec![vec![DataType::Null, DataType::Integer32(1)]]);
+
+    // a scalar subquery without rows gives NULL
+    let query = format!(
+        "update {} set c = (select c from {} where a = 99), b = null where a = 3;",
+        table_name, table_name
+    );
+    assert!(matches!(
+        execute_query(&query),
+        CommandResult::RowsAffected(1)
+    ));
+    let query = format!("select b, c from {} where a = 3;", table_name);
+    let (_, rows) = query_rows(&query);
+    assert_eq!(rows, vec![vec![DataType::Null, DataType::Null]]);
+}
+
+#[test]
+fn test_select_without_from() {
+    let int = |i: i32| DataType::Integer32(i);
+    // a select without FROM reads a single row, and not the rows of the test table
+    write_test_table("test_table");
+    let query = "with recursive c(n) as (select 1 union all select n + 1 from c where n < 5) select n from c;";
+    let (columns, rows) = query_rows(query);
+    assert_eq!(columns, vec!["n"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![int(1)],
+            vec![int(2)],
+            vec![int(3)],
+            vec![int(4)],
+            vec![int(5)]
+        ]
+    );
+
+    let (columns, rows) = query_rows("select abs(-5), 1 + 2 as three, count(*);");
+    assert_eq!(columns, vec!["ABS(-5)", "three", "COUNT(*)"]);
+    assert_eq!(rows, vec![vec![int(5), int(3), int(1)]]);
+    let (_, rows) = query_rows("select 1 where 1 = 0;");
+    assert!(rows.is_empty());
 }
@@ -1,51 +1,850 @@
 //! VirtualMachine that takes parsed interpreted as commands and executes them.
 //! This effectively maps the Parser output into an actual code.
+use crate::database::aggregate::aggregate;
 use crate::database::command::{Command, CommandResult};
+use crate::database::config::MAX_RECURSIVE_ITERATIONS;
+use crate::database::evaluator::{
+    coerce, distinct, evaluate, evaluate_predicate, filter, project, sort, EvaluationError,
+};
+use crate::database::explain::explain;
+use crate::database::functions::validate;
 use crate::database::in_memory_table::InMemoryTable;
-use steeldb_core::FileFormat;
-use steeldb_core::Table;
+use crate::database::join::join;
+use crate::database::set_operation::combine;
+use crate::database::subquery::{bind_outer, evaluate_subqueries};
+use crate::database::window::compute_windows;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use steeldb_core::{DataType, FileFormat, SaveMode, Table, TableErrors};
+use steeldb_parser::{AlterTableAction, Expression, OrderBy, SelectItem, SetOperator};
 
-/// For now, an empty struct, but could be extended.
-pub struct VirtualMachine {}
+/// Executes commands. When executing a subquery, it also holds the current rows of the enclosing queries,
+/// so that the columns of the subquery may refer to them.
+/// It also holds the tables of the WITH clauses executed so far, which are read instead of stored tables.
+pub struct VirtualMachine {
+    /// The current row of each enclosing query, from the innermost outwards, see [bind_outer].
+    scope: Vec<InMemoryTable>,
+    /// How many levels of the scope the executed commands read from.
+    scope_depth: Cell<usize>,
+    /// The tables of the WITH clauses, by name, see [Command::With].
+    common_tables: RefCell<HashMap<String, InMemoryTable>>,
+}
 
 impl VirtualMachine {
     /// Class constructor
     pub fn new() -> VirtualMachine {
-        return VirtualMachine {};
+        return VirtualMachine::with_scope(vec![], HashMap::new());
+    }
+
+    /// Creates a VirtualMachine for a subquery, given the current rows of its enclosing queries,
+    /// from the innermost outwards, and the tables of the WITH clauses it may read.
+    /// Each table of the scope holds a single row.
+    pub fn with_scope(
+        scope: Vec<InMemoryTable>,
+        common_tables: HashMap<String, InMemoryTable>,
+    ) -> VirtualMachine {
+        return VirtualMachine {
+            scope,
+            scope_depth: Cell::new(0),
+            common_tables: RefCell::new(common_tables),
+        };
+    }
+
+    /// How many levels of the scope the executed commands read from.
+    /// Zero means the results do not depend on the enclosing queries.
+    pub fn scope_depth(&self) -> usize {
+        return self.scope_depth.get();
     }
 
     /// Main entry point, executes a vector of [Command] type, in the order given.
+    /// The selects and function calls of the commands are checked first, see [validate].
+    /// The tables of the WITH clauses of a previous execution are forgotten.
     pub fn execute(&self, commands: Vec<Command>) -> CommandResult {
-        // keep track of last command execution
-        // might be useful when implementing nested commands
-        let mut maybe_command_result: Option<CommandResult> = None;
+        if let Err(error) = validate(&commands) {
+            return CommandResult::Error(format!("{:?}", error));
+        }
+        self.common_tables.borrow_mut().clear();
+        let mut stack = Vec::<InMemoryTable>::new();
+        if let Some(result) = self.run(commands, &mut stack) {
+            return result;
+        }
+        // once we finish going through the list, the last table is our final result, let's return it
+        match stack.pop() {
+            Some(table) => CommandResult::RetrievedDataSuccess(Box::new(table)),
+            None => CommandResult::Error("Empty command FIFO".to_string()),
+        }
+    }
+
+    /// Executes the commands of a query, returning the resulting table or the error message.
+    /// This is how subqueries are executed.
+    pub fn execute_query(&self, commands: Vec<Command>) -> Result<InMemoryTable, String> {
+        let mut stack = Vec::<InMemoryTable>::new();
+        match self.run(commands, &mut stack) {
+            Some(CommandResult::Error(error)) => return Err(error),
+            Some(_) => return Err("Expected a query".to_string()),
+            None => match stack.pop() {
+                Some(table) => return Ok(table),
+                None => return Err("Empty command FIFO".to_string()),
+            },
+        }
+    }
+
+    /// Binds the columns of the expression that refer to the enclosing queries, see [bind_outer].
+    fn bind(
+        &self,
+        expression: &Expression,
+        tables: &[&InMemoryTable],
+    ) -> Result<Expression, EvaluationError> {
+        if self.scope.is_empty() {
+            return Ok(expression.clone());
+        }
+        let (bound, depth) = bind_outer(expression, tables, &self.scope)?;
+        self.scope_depth.set(self.scope_depth.get().max(depth));
+        return Ok(bound);
+    }
+
+    /// Executes the commands in the order given, pushing the tables they produce into the stack.
+    /// Returns the result of the command that ended the execution, such as an error,
+    /// or `None` once every command was executed.
+    fn run(&self, commands: Vec<Command>, stack: &mut Vec<InMemoryTable>) -> Option<CommandResult> {
+        // keep track of the tables produced
+        // commands such as Filter consume the last one and produce a new one
+        // while Join consumes the last two
 
         // the reason we implement this as a list of commands is to supported
-        // the execution of nested commands in the future
+        // the execution of nested commands, such as subqueries
         // this assumes the parser built a list of commands in the right order of execution
         for command in commands {
-            if let Command::SelectFrom(columns, table_name) = command {
-                let table_result =
-                    InMemoryTable::new().load(table_name, columns, FileFormat::SimpleColumnar);
-
-                // if we found an error, we want to immediately abort the nested execution
-                if table_result.is_err() {
-                    let error = format!("{:?}", table_result.unwrap_err());
-                    return CommandResult::Error(error);
-                } else {
+            match command {
+                Command::SelectFrom(columns, table_name) => {
+                    let format = FileFormat::SimpleColumnar;
+                    let common_table = self.common_tables.borrow().get(&table_name).cloned();
+                    let table_result = if let Some(table) = common_table {
+                        if columns.iter().any(|column| column == "*") {
+                            Ok(table)
+                        } else {
+                            table.project(columns)
+                        }
+                    } else if columns.iter().any(|column| column == "*") {
+                        InMemoryTable::read_all(&table_name, format)
+                    } else {
+                        InMemoryTable::read(table_name, columns, format)
+                    };
+
+                    // if we found an error, we want to immediately abort the nested execution
+                    if table_result.is_err() {
+                        let error = format!("{:?}", table_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
                     // if our command succeeds, we want to save the result in case the next command needs it
+                    stack.push(table_result.unwrap());
+                }
+                Command::SingleRow => stack.push(InMemoryTable::single_row()),
+                Command::SelectFromAs(columns, table_name, alias) => {
+                    let common_table = self.common_tables.borrow().get(&table_name).cloned();
+                    let table_result = match common_table {
+                        Some(table) => Ok(table.qualify(&alias)),
+                        None => read_aliased_table(table_name, &alias, &columns),
+                    };
+                    if table_result.is_err() {
+                        let error = format!("{:?}", table_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(table_result.unwrap());
+                }
+                Command::Join(kind, predicate) => {
+                    let (left, right) = match (stack.pop(), stack.pop()) {
+                        (Some(right), Some(left)) => (left, right),
+                        _ => {
+                            return Some(CommandResult::Error(
+                                "Join without input tables".to_string(),
+                            ));
+                        }
+                    };
+                    let join_result = self
+                        .bind(&predicate, &[&left, &right])
+                        .and_then(|predicate| join(&left, &right, kind, &predicate));
+                    if join_result.is_err() {
+                        let error = format!("{:?}", join_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(join_result.unwrap());
+                }
+                Command::SetOperation(operator, all) => {
+                    let (left, right) = match (stack.pop(), stack.pop()) {
+                        (Some(right), Some(left)) => (left, right),
+                        _ => {
+                            return Some(CommandResult::Error(
+                                "SetOperation without input tables".to_string(),
+                            ));
+                        }
+                    };
+                    let combined = combine(&left, &right, operator, all);
+                    if combined.is_err() {
+                        let error = format!("{:?}", combined.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(combined.unwrap());
+                }
+                Command::Filter(predicate) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Filter without input table".to_string(),
+                            ));
+                        }
+                    };
+                    let filter_result = self
+                        .bind(&predicate, &[&table])
+                        .and_then(|predicate| filter(&table, &predicate));
+                    if filter_result.is_err() {
+                        let error = format!("{:?}", filter_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(filter_result.unwrap());
+                }
+                Command::Project(items) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Project without input table".to_string(),
+                            ));
+                        }
+                    };
+                    // items bound to the enclosing queries keep the names they were written with
+                    let project_result = items
+                        .into_iter()
+                        .map(|item| match item {
+                            SelectItem::Wildcard => return Ok(item),
+                            SelectItem::Expression(ref expression, _) => {
+                                let bound = self.bind(expression, &[&table])?;
+                                return Ok(SelectItem::Expression(bound, Some(item.name())));
+                            }
+                        })
+                        .collect::<Result<Vec<SelectItem>, EvaluationError>>()
+                        .and_then(|items| project(&table, &items));
+                    if project_result.is_err() {
+                        let error = format!("{:?}", project_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(project_result.unwrap());
+                }
+                Command::Aggregate(group_by, aggregates) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Aggregate without input table".to_string(),
+                            ));
+                        }
+                    };
+                    let bind_all = |expressions: &[Expression]| {
+                        return expressions
+                            .iter()
+                            .map(|expression| self.bind(expression, &[&table]))
+                            .collect::<Result<Vec<Expression>, EvaluationError>>();
+                    };
+                    let aggregate_result = bind_all(&group_by).and_then(|group_by| {
+                        return aggregate(&table, &group_by, &bind_all(&aggregates)?);
+                    });
+                    if aggregate_result.is_err() {
+                        let error = format!("{:?}", aggregate_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(aggregate_result.unwrap());
+                }
+                Command::Window(windows) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Window without input table".to_string(),
+                            ));
+                        }
+                    };
+                    let window_result = windows
+                        .iter()
+                        .map(|window| self.bind(window, &[&table]))
+                        .collect::<Result<Vec<Expression>, EvaluationError>>()
+                        .and_then(|windows| compute_windows(&table, &windows));
+                    if window_result.is_err() {
+                        let error = format!("{:?}", window_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(window_result.unwrap());
+                }
+                Command::Sort(order_by) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Sort without input table".to_string(),
+                            ));
+                        }
+                    };
+                    let sort_result = order_by
+                        .into_iter()
+                        .map(|key| {
+                            let expression = self.bind(&key.expression, &[&table])?;
+                            return Ok(OrderBy { expression, ..key });
+                        })
+                        .collect::<Result<Vec<OrderBy>, EvaluationError>>()
+                        .and_then(|order_by| sort(&table, &order_by));
+                    if sort_result.is_err() {
+                        let error = format!("{:?}", sort_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    stack.push(sort_result.unwrap());
+                }
+                Command::Distinct => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Distinct without input table".to_string(),
+                            ));
+                        }
+                    };
+                    stack.push(distinct(&table));
+                }
+                Command::Limit(limit, offset) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Limit without input table".to_string(),
+                            ));
+                        }
+                    };
+                    let rows: Vec<usize> = (0..table.number_of_rows())
+                        .skip(offset)
+                        .take(limit.unwrap_or(usize::MAX))
+                        .collect();
+                    stack.push(table.select_rows(&rows));
+                }
+                Command::EvaluateSubqueries(subqueries) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "EvaluateSubqueries without input table".to_string(),
+                            ));
+                        }
+                    };
+                    let subqueries_result = evaluate_subqueries(
+                        &table,
+                        &subqueries,
+                        &self.scope,
+                        &self.common_tables.borrow(),
+                    );
+                    if subqueries_result.is_err() {
+                        let error = format!("{:?}", subqueries_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    let (table, depth) = subqueries_result.unwrap();
+                    self.scope_depth.set(self.scope_depth.get().max(depth));
+                    stack.push(table);
+                }
+                Command::Qualify(alias) => {
+                    let table = match stack.pop() {
+                        Some(table) => table,
+                        None => {
+                            return Some(CommandResult::Error(
+                                "Qualify without input table".to_string(),
+                            ));
+                        }
+                    };
+                    stack.push(table.qualify(&alias));
+                }
+                Command::With(name, columns, commands) => {
+                    let table_result = self
+                        .execute_query(commands)
+                        .and_then(|table| common_table(table, &name, &columns));
+                    if table_result.is_err() {
+                        return Some(CommandResult::Error(table_result.unwrap_err()));
+                    }
+                    let table = table_result.unwrap();
+                    self.common_tables.borrow_mut().insert(name, table);
+                }
+                Command::WithRecursive(name, columns, initial, recursive, all) => {
+                    let table_result =
+                        self.execute_recursive(&name, &columns, initial, recursive, all);
+                    if table_result.is_err() {
+                        return Some(CommandResult::Error(table_result.unwrap_err()));
+                    }
+                    let table = table_result.unwrap();
+                    self.common_tables.borrow_mut().insert(name, table);
+                }
+                Command::Invalid(message) => {
+                    let error = EvaluationError::InvalidQuery(message);
+                    return Some(CommandResult::Error(format!("{:?}", error)));
+                }
+                Command::Explain(commands) => {
+                    return Some(CommandResult::RetrievedDataSuccess(Box::new(explain(
+                        &commands,
+                    ))));
+                }
+                Command::CreateTable(table_name, schema, if_not_exists) => {
+                    InMemoryTable::init_data_dir();
+                    let table = InMemoryTable::from_schema(table_name, schema);
+                    let save_result = table.save(SaveMode::Overwrite, FileFormat::SimpleColumnar);
+                    match save_result {
+                        Ok(()) => return Some(CommandResult::VoidSuccess),
+                        Err(TableErrors::TableAlreadyExists) if if_not_exists => {
+                            return Some(CommandResult::VoidSuccess);
+                        }
+                        Err(error) => return Some(CommandResult::Error(format!("{:?}", error))),
+                    }
+                }
+                Command::Insert(table_name, columns, rows, parameters) => {
+                    if let Some((_, _, parameter)) = parameters.first() {
+                        let error = EvaluationError::TypeMismatch(format!(
+                            "Parameter ${} has no value, the statement must be prepared",
+                            parameter.number()
+                        ));
+                        return Some(CommandResult::Error(format!("{:?}", error)));
+                    }
+                    let table_result = rows_to_table(table_name, columns, rows);
+                    if table_result.is_err() {
+                        return Some(CommandResult::Error(table_result.unwrap_err()));
+                    }
                     let table = table_result.unwrap();
-                    maybe_command_result = Some(CommandResult::RetrievedDataSuccess(table));
+                    let save_result = table.save(SaveMode::Append, FileFormat::SimpleColumnar);
+                    if save_result.is_err() {
+                        let error = format!("{:?}", save_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    return Some(CommandResult::RowsAffected(table.number_of_rows()));
+                }
+                Command::DropTable(table_name, if_exists) => {
+                    let drop_result =
+                        InMemoryTable::drop_table(&table_name, FileFormat::SimpleColumnar);
+                    match drop_result {
+                        Ok(()) => return Some(CommandResult::VoidSuccess),
+                        Err(TableErrors::TableNotFound(_)) if if_exists => {
+                            return Some(CommandResult::VoidSuccess);
+                        }
+                        Err(error) => return Some(CommandResult::Error(format!("{:?}", error))),
+                    }
+                }
+                Command::TruncateTable(table_name) => {
+                    let truncate_result =
+                        InMemoryTable::truncate_table(&table_name, FileFormat::SimpleColumnar);
+                    if truncate_result.is_err() {
+                        let error = format!("{:?}", truncate_result.unwrap_err());
+                        return Some(CommandResult::Error(error));
+                    }
+                    return Some(CommandResult::VoidSuccess);
+                }
+                Command::AlterTable(table_name, action) => {
+                    let alter_result = alter_table(table_name, action);
+                    if alter_result.is_err() {
+                        return Some(CommandResult::Error(alter_result.unwrap_err()));
+                    }
+                    return Some(CommandResult::VoidSuccess);
+                }
+                Command::Update(table_name, assignments, predicate) => {
+                    let update_result = update_table(table_name, assignments, predicate);
+                    if update_result.is_err() {
+                        return Some(CommandResult::Error(update_result.unwrap_err()));
+                    }
+                    return Some(CommandResult::RowsAffected(update_result.unwrap()));
+                }
+                Command::Delete(table_name, predicate) => {
+                    let delete_result = delete_rows(table_name, predicate);
+                    if delete_result.is_err() {
+                        return Some(CommandResult::Error(delete_result.unwrap_err()));
+                    }
+                    return Some(CommandResult::RowsAffected(delete_result.unwrap()));
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// Computes the rows of a recursive table of a WITH clause, see [Command::WithRecursive].
+    /// While the recursive commands run, the table holds the rows added by the previous step.
+    /// Without `all`, the rows already found are dropped from each step, so that the recursion ends
+    /// once no new rows are found, see [combine]. Fails after [MAX_RECURSIVE_ITERATIONS] steps that added rows.
+    fn execute_recursive(
+        &self,
+        name: &str,
+        columns: &[String],
+        initial: Vec<Command>,
+        recursive: Vec<Command>,
+        all: bool,
+    ) -> Result<InMemoryTable, String> {
+        let mut result = common_table(self.execute_query(initial)?, name, columns)?;
+        if !all {
+            result = distinct(&result);
+        }
+        // the recursive select names its columns as the initial one does
+        let names = result.select_columns.clone();
+        let mut step = result.clone();
+        for _ in 0..MAX_RECURSIVE_ITERATIONS {
+            self.common_tables
+                .borrow_mut()
+                .insert(name.to_string(), step);
+            let rows = common_table(self.execute_query(recursive.clone())?, name, &names)?;
+            // the rows found so far come first, so the ones after them are the new rows
+            let combined = combine(&result, &rows, SetOperator::Union, all);
+            if combined.is_err() {
+                return Err(format!("{:?}", combined.unwrap_err()));
+            }
+            let combined = combined.unwrap();
+            let new_rows: Vec<usize> =
+                (result.number_of_rows()..combined.number_of_rows()).collect();
+            if new_rows.is_empty() {
+                return Ok(result);
+            }
+            step = combined.select_rows(&new_rows);
+            step.name = name.to_string();
+            result = combined;
+            result.name = name.to_string();
+        }
+        let error = TableErrors::Error(format!(
+            "Recursive query {} exceeded {} iterations",
+            name, MAX_RECURSIVE_ITERATIONS
+        ));
+        return Err(format!("{:?}", error));
+    }
+}
+
+/// Builds a table of a WITH clause from the result of its select, holding only the selected columns.
+/// The columns are renamed by position to the given names, when there are any.
+fn common_table(
+    table: InMemoryTable,
+    name: &str,
+    columns: &[String],
+) -> Result<InMemoryTable, String> {
+    let names = if columns.is_empty() {
+        table.select_columns.clone()
+    } else {
+        columns.to_vec()
+    };
+    if names.len() != table.select_columns.len() {
+        let error = TableErrors::Error(format!(
+            "WITH query {} has {} columns, but its select returns {}",
+            name,
+            names.len(),
+            table.select_columns.len()
+        ));
+        return Err(format!("{:?}", error));
+    }
+    let mut result = InMemoryTable::new();
+    result.name = name.to_string();
+    for (index, column) in names.iter().enumerate() {
+        if names[..index].contains(column) {
+            let error = TableErrors::Error(format!("Duplicate column: {}", column));
+            return Err(format!("{:?}", error));
+        }
+        let selected = &table.select_columns[index];
+        let data_type = table.fields.get(selected).unwrap().clone();
+        let values = table.columns.get(selected).unwrap().clone();
+        result.fields.insert(column.clone(), data_type);
+        result.columns.insert(column.clone(), values);
+        result.select_columns.push(column.clone());
+    }
+    return Ok(result);
+}
+
+/// Reads the columns of a table that the column references may refer to, qualified by the alias.
+/// A reference is either `*`, `alias.column` or an unqualified column name.
+/// When none of the columns is referenced, the first one is still read to keep the number of rows.
+fn read_aliased_table(
+    table_name: String,
+    alias: &str,
+    references: &[String],
+) -> Result<InMemoryTable, TableErrors> {
+    let format = FileFormat::SimpleColumnar;
+    let schema = InMemoryTable::read_schema(&table_name, format)?;
+    let mut columns = Vec::<String>::new();
+    for (name, _) in schema.iter() {
+        let is_referenced = references
+            .iter()
+            .any(|reference| match reference.split_once('.') {
+                Some((qualifier, column)) => qualifier == alias && column == name,
+                None => reference == "*" || reference == name,
+            });
+        if is_referenced {
+            columns.push(name.clone());
+        }
+    }
+    if columns.is_empty() {
+        if let Some((name, _)) = schema.first() {
+            columns.push(name.clone());
+        }
+    }
+    let table = InMemoryTable::read(table_name, columns, format)?;
+    return Ok(table.qualify(alias));
+}
+
+/// Builds the table holding the rows of an INSERT, checking them against the stored schema.
+/// Every column of the schema must receive a value, and values are converted to the column types.
+fn rows_to_table(
+    table_name: String,
+    columns: Option<Vec<String>>,
+    rows: Vec<Vec<DataType>>,
+) -> Result<InMemoryTable, String> {
+    let schema_result = InMemoryTable::read_schema(&table_name, FileFormat::SimpleColumnar);
+    if schema_result.is_err() {
+        return Err(format!("{:?}", schema_result.unwrap_err()));
+    }
+    let schema = schema_result.unwrap();
+    let fields: HashMap<String, DataType> = schema.iter().cloned().collect();
+    let columns = columns.unwrap_or_else(|| schema.iter().map(|(name, _)| name.clone()).collect());
+
+    for (index, column) in columns.iter().enumerate() {
+        if !fields.contains_key(column) {
+            return Err(format!("{:?}", TableErrors::ColumnNotFound(column.clone())));
+        }
+        if columns[..index].contains(column) {
+            let error = TableErrors::Error(format!("Duplicate column: {}", column));
+            return Err(format!("{:?}", error));
+        }
+    }
+    for (name, _) in schema.iter() {
+        if !columns.contains(name) {
+            let error = TableErrors::Error(format!("Missing value for column: {}", name));
+            return Err(format!("{:?}", error));
+        }
+    }
+
+    let mut table = InMemoryTable::from_schema(table_name, schema);
+    for row in rows {
+        if row.len() != columns.len() {
+            let error = TableErrors::Error(format!(
+                "Expected {} values, found {}",
+                columns.len(),
+                row.len()
+            ));
+            return Err(format!("{:?}", error));
+        }
+        for (column, value) in columns.iter().zip(row) {
+            if let DataType::String(s) = &value {
+                if s.contains('\n') {
+                    let error =
+                        TableErrors::Error("Strings cannot contain line breaks".to_string());
+                    return Err(format!("{:?}", error));
+                }
+            }
+            let coerce_result = coerce(value, fields.get(column).unwrap());
+            if coerce_result.is_err() {
+                return Err(format!("{:?}", coerce_result.unwrap_err()));
+            }
+            table
+                .columns
+                .get_mut(column)
+                .unwrap()
+                .push(coerce_result.unwrap());
+        }
+    }
+    return Ok(table);
+}
+
+/// Applies an ALTER TABLE action.
+/// Schema changes load the whole table, change it in memory and replace the stored file,
+/// so a failure halfway never leaves a partially written table behind.
+fn alter_table(table_name: String, action: AlterTableAction) -> Result<(), String> {
+    let format = FileFormat::SimpleColumnar;
+    if let AlterTableAction::RenameTable(new_name) = &action {
+        let rename_result = InMemoryTable::rename_table(&table_name, new_name, format);
+        if rename_result.is_err() {
+            return Err(format!("{:?}", rename_result.unwrap_err()));
+        }
+        return Ok(());
+    }
+
+    let table_result = InMemoryTable::read_all(&table_name, format);
+    if table_result.is_err() {
+        return Err(format!("{:?}", table_result.unwrap_err()));
+    }
+    let mut table = table_result.unwrap();
+
+    match action {
+        AlterTableAction::AddColumn(column, default) => {
+            if table.fields.contains_key(&column.name) {
+                let error = TableErrors::Error(format!("Column already exists: {}", column.name));
+                return Err(format!("{:?}", error));
+            }
+            // without a default, existing rows get NULL
+            let value = match default {
+                Some(default) => {
+                    let coerce_result = coerce(default, &column.data_type);
+                    if coerce_result.is_err() {
+                        return Err(format!("{:?}", coerce_result.unwrap_err()));
+                    }
+                    coerce_result.unwrap()
                 }
-            } else if let Command::Stub = command {
-                return CommandResult::VoidSuccess;
+                None => DataType::Null,
             };
+            let values = vec![value; table.number_of_rows()];
+            table.columns.insert(column.name.clone(), values);
+            table.fields.insert(column.name.clone(), column.data_type);
+            table.select_columns.push(column.name);
+        }
+        AlterTableAction::DropColumn(name) => {
+            if !table.fields.contains_key(&name) {
+                return Err(format!("{:?}", TableErrors::ColumnNotFound(name)));
+            }
+            if table.fields.len() == 1 {
+                let error = TableErrors::Error(format!("Cannot drop the only column: {}", name));
+                return Err(format!("{:?}", error));
+            }
+            table.fields.remove(&name);
+            table.columns.remove(&name);
+            table.select_columns.retain(|column| *column != name);
+        }
+        AlterTableAction::RenameColumn(name, new_name) => {
+            if !table.fields.contains_key(&name) {
+                return Err(format!("{:?}", TableErrors::ColumnNotFound(name)));
+            }
+            if table.fields.contains_key(&new_name) {
+                let error = TableErrors::Error(format!("Column already exists: {}", new_name));
+                return Err(format!("{:?}", error));
+            }
+            let data_type = table.fields.remove(&name).unwrap();
+            table.fields.insert(new_name.clone(), data_type);
+            let values = table.columns.remove(&name).unwrap();
+            table.columns.insert(new_name.clone(), values);
+            for column in table.select_columns.iter_mut() {
+                if *column == name {
+                    *column = new_name.clone();
+                }
+            }
         }
+        AlterTableAction::RenameTable(_) => {}
+    }
 
-        // once we finish going through the list, the last command result is our final one, let's return it
-        match maybe_command_result {
-            Some(command_result) => command_result,
-            None => CommandResult::Error("Empty command FIFO".to_string()),
+    let replace_result = table.replace(format);
+    if replace_result.is_err() {
+        return Err(format!("{:?}", replace_result.unwrap_err()));
+    }
+    return Ok(());
+}
+
+/// Applies an UPDATE, returning the number of updated rows.
+/// All new values of a row are computed from its previous values before any is assigned,
+/// then the stored file is replaced by the updated table.
+fn update_table(
+    table_name: String,
+    assignments: Vec<(String, Expression)>,
+    predicate: Option<Expression>,
+) -> Result<usize, String> {
+    let format = FileFormat::SimpleColumnar;
+    let table_result = InMemoryTable::read_all(&table_name, format);
+    if table_result.is_err() {
+        return Err(format!("{:?}", table_result.unwrap_err()));
+    }
+    let mut table = table_result.unwrap();
+
+    for (index, (column, _)) in assignments.iter().enumerate() {
+        if !table.fields.contains_key(column) {
+            return Err(format!("{:?}", TableErrors::ColumnNotFound(column.clone())));
+        }
+        if assignments[..index]
+            .iter()
+            .any(|(other, _)| other == column)
+        {
+            let error = TableErrors::Error(format!("Duplicate column: {}", column));
+            return Err(format!("{:?}", error));
+        }
+    }
+
+    // subqueries are evaluated into columns of a copy, which is not saved
+    let mut subqueries = Vec::<Expression>::new();
+    for expression in predicate
+        .iter()
+        .chain(assignments.iter().map(|(_, expression)| expression))
+    {
+        subqueries.extend(expression.subqueries().into_iter().cloned());
+    }
+    let evaluated = match evaluate_subqueries(&table, &subqueries, &[], &HashMap::new()) {
+        Ok((evaluated, _)) => evaluated,
+        Err(error) => return Err(format!("{:?}", error)),
+    };
+
+    let mut updates = Vec::<(usize, Vec<DataType>)>::new();
+    for row in 0..table.number_of_rows() {
+        if let Some(predicate) = &predicate {
+            let predicate_result = evaluate_predicate(predicate, &evaluated, row);
+            if predicate_result.is_err() {
+                return Err(format!("{:?}", predicate_result.unwrap_err()));
+            }
+            if !predicate_result.unwrap() {
+                continue;
+            }
+        }
+        let mut values = Vec::<DataType>::new();
+        for (column, expression) in assignments.iter() {
+            let value_result = evaluate(expression, &evaluated, row)
+                .and_then(|value| coerce(value, table.fields.get(column).unwrap()));
+            if value_result.is_err() {
+                return Err(format!("{:?}", value_result.unwrap_err()));
+            }
+            let value = value_result.unwrap();
+            if let DataType::String(s) = &value {
+                if s.contains('\n') {
+                    let error =
+                        TableErrors::Error("Strings cannot contain line breaks".to_string());
+                    return Err(format!("{:?}", error));
+                }
+            }
+            values.push(value);
+        }
+        updates.push((row, values));
+    }
+
+    for (row, values) in updates.iter() {
+        for ((column, _), value) in assignments.iter().zip(values) {
+            table.columns.get_mut(column).unwrap()[*row] = value.clone();
+        }
+    }
+
+    let replace_result = table.replace(format);
+    if replace_result.is_err() {
+        return Err(format!("{:?}", replace_result.unwrap_err()));
+    }
+    return Ok(updates.len());
+}
+
+/// Applies a DELETE, returning the number of deleted rows.
+/// The kept rows are selected from every column at once, so the columns stay aligned,
+/// then the stored file is replaced by the remaining table.
+fn delete_rows(table_name: String, predicate: Option<Expression>) -> Result<usize, String> {
+    let format = FileFormat::SimpleColumnar;
+    let table_result = InMemoryTable::read_all(&table_name, format);
+    if table_result.is_err() {
+        return Err(format!("{:?}", table_result.unwrap_err()));
+    }
+    let table = table_result.unwrap();
+
+    let mut kept_rows = Vec::<usize>::new();
+    if let Some(predicate) = &predicate {
+        // subqueries are evaluated into columns of a copy, which is not saved
+        let subqueries: Vec<Expression> = predicate.subqueries().into_iter().cloned().collect();
+        let evaluated = match evaluate_subqueries(&table, &subqueries, &[], &HashMap::new()) {
+            Ok((evaluated, _)) => evaluated,
+            Err(error) => return Err(format!("{:?}", error)),
+        };
+        for row in 0..table.number_of_rows() {
+            let predicate_result = evaluate_predicate(predicate, &evaluated, row);
+            if predicate_result.is_err() {
+                return Err(format!("{:?}", predicate_result.unwrap_err()));
+            }
+            if !predicate_result.unwrap() {
+                kept_rows.push(row);
+            }
         }
     }
+    let deleted = table.number_of_rows() - kept_rows.len();
+
+    let replace_result = table.select_rows(&kept_rows).replace(format);
+    if replace_result.is_err() {
+        return Err(format!("{:?}", replace_result.unwrap_err()));
+    }
+    return Ok(deleted);
 }
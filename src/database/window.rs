@@ -0,0 +1,282 @@
+//! Window functions, which compute a value for each row of an [InMemoryTable] from the rows of its window.
+use crate::database::aggregate::Accumulator;
+use crate::database::evaluator::{
+    aggregate_type, coerce, common_type, compare_keys, evaluate, infer_type, resolve,
+    EvaluationError,
+};
+use crate::database::in_memory_table::InMemoryTable;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use steeldb_core::{DataType, RowKey};
+use steeldb_parser::{Expression, FrameBound, Window, WindowFunction};
+
+/// Computes the window functions for every row of the table, returning a copy of the table
+/// with a column per window function, named after it, from which [evaluate] reads their values.
+/// These columns are not selected, so they are only part of a result once projected.
+///
+/// The rows are split into partitions by the PARTITION BY keys, then each partition is sorted
+/// by the ORDER BY keys, keeping the original order of rows with equal keys.
+/// An aggregate whose frame starts at UNBOUNDED PRECEDING, as the default frame does, keeps a single
+/// accumulator per partition and adds the rows as the end of the frame advances.
+/// Other frames are aggregated row by row, for each row of the partition.
+pub fn compute_windows(
+    table: &InMemoryTable,
+    windows: &[Expression],
+) -> Result<InMemoryTable, EvaluationError> {
+    let all_rows: Vec<usize> = (0..table.number_of_rows()).collect();
+    let mut result = table.select_rows(&all_rows);
+    for expression in windows.iter() {
+        let name = expression.to_string();
+        if result.fields.contains_key(&name) {
+            continue;
+        }
+        let resolved = resolve(expression, table)?;
+        let (function, arguments, window) = match &resolved {
+            Expression::WindowFunction(function, arguments, window) => {
+                (function, arguments, window)
+            }
+            _ => {
+                return Err(EvaluationError::TypeMismatch(format!(
+                    "Expected a window function, found {}",
+                    expression
+                )));
+            }
+        };
+        let data_type = window_type(function, arguments, table)?;
+
+        // the value of the first argument is read for many rows, so it is evaluated once
+        let mut argument_values = Vec::<DataType>::new();
+        if let Some(argument) = arguments.first() {
+            for row in all_rows.iter() {
+                argument_values.push(evaluate(argument, table, *row)?);
+            }
+        }
+        let mut values = vec![DataType::Null; table.number_of_rows()];
+        for Partition { rows, keys } in partitions(table, window)? {
+            let peers = peer_groups(&keys, window);
+            // the running aggregate of the partition, and the number of its rows added so far
+            let mut running = Accumulator::default();
+            let mut added = 0;
+            for (index, row) in rows.iter().enumerate() {
+                let value = match function {
+                    WindowFunction::RowNumber => DataType::Integer32(index as i32 + 1),
+                    WindowFunction::Rank => DataType::Integer32(peers[index].0 as i32 + 1),
+                    WindowFunction::DenseRank => DataType::Integer32(peers[index].2 as i32),
+                    WindowFunction::Lag | WindowFunction::Lead => {
+                        let offset = match arguments.get(1) {
+                            Some(offset) => evaluate(offset, table, *row)?,
+                            None => DataType::Integer32(1),
+                        };
+                        let offset = match offset {
+                            DataType::Integer32(offset) if offset >= 0 => offset as usize,
+                            // a NULL offset gives NULL
+                            DataType::Null => continue,
+                            DataType::Integer32(offset) => {
+                                return Err(EvaluationError::OutOfRange(format!(
+                                    "{} offset cannot be negative, found {}",
+                                    function, offset
+                                )));
+                            }
+                            offset => {
+                                return Err(EvaluationError::TypeMismatch(format!(
+                                    "{} offset must be an i32, found {}",
+                                    function,
+                                    offset.name()
+                                )));
+                            }
+                        };
+                        let target = match function {
+                            WindowFunction::Lag => index.checked_sub(offset),
+                            _ => index.checked_add(offset),
+                        };
+                        match target.and_then(|target| rows.get(target)) {
+                            Some(target) => argument_values[*target].clone(),
+                            None => match arguments.get(2) {
+                                Some(default) => evaluate(default, table, *row)?,
+                                None => DataType::Null,
+                            },
+                        }
+                    }
+                    WindowFunction::FirstValue => {
+                        match frame(window, index, rows.len(), &peers[index]) {
+                            Some((start, _)) => argument_values[rows[start]].clone(),
+                            None => DataType::Null,
+                        }
+                    }
+                    WindowFunction::Aggregate(aggregate) if starts_unbounded(window) => {
+                        // the frame only grows, since its end never moves back
+                        if let Some((_, end)) = frame(window, index, rows.len(), &peers[index]) {
+                            while added <= end {
+                                let value = argument_values.get(rows[added]).cloned();
+                                running.add(aggregate, false, value)?;
+                                added += 1;
+                            }
+                        }
+                        running.finish(aggregate, &data_type)?
+                    }
+                    WindowFunction::Aggregate(aggregate) => {
+                        let mut accumulator = Accumulator::default();
+                        if let Some((start, end)) = frame(window, index, rows.len(), &peers[index])
+                        {
+                            for frame_row in rows[start..=end].iter() {
+                                let value = argument_values.get(*frame_row).cloned();
+                                accumulator.add(aggregate, false, value)?;
+                            }
+                        }
+                        accumulator.finish(aggregate, &data_type)?
+                    }
+                };
+                values[*row] = match value {
+                    DataType::Null => DataType::Null,
+                    value => coerce(value, &data_type)?,
+                };
+            }
+        }
+        result.fields.insert(name.clone(), data_type);
+        result.columns.insert(name, values);
+    }
+    return Ok(result);
+}
+
+/// Returns the type of the values of a window function, given its resolved arguments.
+/// Ranks are i32, LAG and LEAD take the common type of their value and default,
+/// FIRST_VALUE the type of its value, and aggregates the type given by [aggregate_type].
+fn window_type(
+    function: &WindowFunction,
+    arguments: &[Expression],
+    table: &InMemoryTable,
+) -> Result<DataType, EvaluationError> {
+    let mut types = Vec::<DataType>::new();
+    for argument in arguments.iter() {
+        types.push(infer_type(argument, table)?);
+    }
+    match function {
+        WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => {
+            return Ok(DataType::Integer32(0));
+        }
+        WindowFunction::Lag | WindowFunction::Lead => {
+            if let Some(offset_type) = types.get(1) {
+                if !matches!(offset_type, DataType::Integer32(_) | DataType::Null) {
+                    return Err(EvaluationError::TypeMismatch(format!(
+                        "{} offset must be an i32, found {}",
+                        function,
+                        offset_type.name()
+                    )));
+                }
+            }
+            let mut value_types = vec![types[0].clone()];
+            value_types.extend(types.get(2).cloned());
+            return common_type(&value_types);
+        }
+        WindowFunction::FirstValue => return Ok(types[0].clone()),
+        WindowFunction::Aggregate(aggregate) => {
+            return aggregate_type(aggregate, types.first().cloned());
+        }
+    }
+}
+
+/// The rows of a partition, sorted by the ORDER BY keys of the window.
+struct Partition {
+    /// The positions of the rows in the table.
+    rows: Vec<usize>,
+    /// The values of the sort keys of each row, in the same order.
+    keys: Vec<Vec<DataType>>,
+}
+
+/// Splits the rows of the table by the values of the PARTITION BY keys, in the order partitions first appear,
+/// then sorts the rows of each partition by the ORDER BY keys.
+fn partitions(table: &InMemoryTable, window: &Window) -> Result<Vec<Partition>, EvaluationError> {
+    let mut partition_indexes = HashMap::<RowKey, usize>::new();
+    let mut partitions = Vec::<Vec<usize>>::new();
+    let mut keys = Vec::<Vec<DataType>>::new();
+    for row in 0..table.number_of_rows() {
+        let mut partition_key = Vec::<DataType>::new();
+        for key in window.partition_by.iter() {
+            partition_key.push(evaluate(key, table, row)?);
+        }
+        let index = *partition_indexes
+            .entry(RowKey(partition_key))
+            .or_insert(partitions.len());
+        if index == partitions.len() {
+            partitions.push(vec![]);
+        }
+        partitions[index].push(row);
+
+        let mut sort_key = Vec::<DataType>::new();
+        for key in window.order_by.iter() {
+            sort_key.push(evaluate(&key.expression, table, row)?);
+        }
+        keys.push(sort_key);
+    }
+
+    let mut sorted = Vec::<Partition>::new();
+    for mut rows in partitions {
+        rows.sort_by(|left, right| compare_keys(&keys[*left], &keys[*right], &window.order_by));
+        let keys = rows.iter().map(|row| keys[*row].clone()).collect();
+        sorted.push(Partition { rows, keys });
+    }
+    return Ok(sorted);
+}
+
+/// Finds the peers of each row of a sorted partition, which are the rows with equal sort keys.
+/// Returns, for each row, the positions of its first and last peers and the number of distinct keys up to it.
+/// Without ORDER BY, every row of the partition is a peer of the others.
+fn peer_groups(keys: &[Vec<DataType>], window: &Window) -> Vec<(usize, usize, usize)> {
+    let mut peers = Vec::<(usize, usize, usize)>::new();
+    for index in 0..keys.len() {
+        let is_peer = index > 0
+            && compare_keys(&keys[index - 1], &keys[index], &window.order_by) == Ordering::Equal;
+        if is_peer {
+            let (first, _, group) = peers[index - 1];
+            peers.push((first, index, group));
+        } else {
+            let group = peers.last().map(|(_, _, group)| group + 1).unwrap_or(1);
+            peers.push((index, index, group));
+        }
+    }
+    // the last peer is only known once the whole group was seen
+    for index in (0..keys.len()).rev() {
+        if index + 1 < keys.len() && peers[index + 1].0 == peers[index].0 {
+            peers[index].1 = peers[index + 1].1;
+        }
+    }
+    return peers;
+}
+
+/// Whether the frame of every row starts at the first row of its partition.
+fn starts_unbounded(window: &Window) -> bool {
+    match window.frame {
+        Some(frame) => return matches!(frame.start, FrameBound::UnboundedPreceding),
+        None => return true,
+    }
+}
+
+/// Returns the positions of the first and last rows of the frame of a row, in its sorted partition,
+/// or `None` when the frame is empty. See [Window::frame] for the default frame.
+fn frame(
+    window: &Window,
+    index: usize,
+    length: usize,
+    peers: &(usize, usize, usize),
+) -> Option<(usize, usize)> {
+    let frame = match window.frame {
+        Some(frame) => frame,
+        None if window.order_by.is_empty() => return Some((0, length - 1)),
+        None => return Some((0, peers.1)),
+    };
+    let position = |bound: FrameBound| -> i64 {
+        match bound {
+            FrameBound::UnboundedPreceding => return 0,
+            FrameBound::Preceding(rows) => return index as i64 - rows as i64,
+            FrameBound::CurrentRow => return index as i64,
+            FrameBound::Following(rows) => return index as i64 + rows as i64,
+            FrameBound::UnboundedFollowing => return length as i64 - 1,
+        }
+    };
+    let start = position(frame.start).max(0);
+    let end = position(frame.end).min(length as i64 - 1);
+    if start > end {
+        return None;
+    }
+    return Some((start as usize, end as usize));
+}
@@ -18,7 +18,14 @@
 //! 1. Using the [SteelDB] struct for a programmatic experience.
 //! 2. Using the [Repl] struct for an interactive experience.
 //!
-//! Note that the current version is extremely limited, as it only supports the SELECT clause.
+//! The supported SQL is still a small subset of the standard:
+//! - SELECT, with joins, subqueries, WITH clauses, aggregates, window functions, set operations and
+//!   the GROUP BY, HAVING, ORDER BY, LIMIT and OFFSET clauses. The FROM clause is optional.
+//! - CREATE TABLE, DROP TABLE, TRUNCATE TABLE and ALTER TABLE.
+//! - INSERT, UPDATE and DELETE.
+//! - EXPLAIN, which describes how a statement would be executed.
+//!
+//! Several statements may be executed at once as a script, and statements may be prepared with parameters.
 //! # Examples
 //! ### Database API
 //!
@@ -26,14 +33,46 @@
 //! use steeldb::{SteelDB, ExecutionResult, SteelDBInterface};
 //!
 //! let mut database = SteelDB::new();
-//! let result = database.execute("select name".to_string());
+//! let result = database.execute("select name from people;".to_string());
 //! match result {
 //!     ExecutionResult::TableResult(table) => {
 //!         println!("{:?}", table);
 //!     }
 //!     ExecutionResult::VoidOK => println!("Command OK"),
-//!     ExecutionResult::ParseError(error) => println!("Parse error: {:?}", error),
+//!     ExecutionResult::RowsAffected(number_rows) => println!("{} rows affected", number_rows),
+//!     ExecutionResult::ParseError(error) => println!("Parse error: {}", error),
 //!     ExecutionResult::CommandError(error) => println!("Command error: {:?}", error),
+//!     ExecutionResult::ScriptResult(results) => println!("{} statements executed", results.len()),
+//!     ExecutionResult::ScriptError(index, _) => println!("Statement {} failed", index),
+//! }
+//! ```
+//!
+//! Several statements may be given at once, as a script. They are executed in order,
+//! stopping at the first one that fails:
+//!
+//! ```no_run
+//! use steeldb::{SteelDB, ExecutionResult, SteelDBInterface};
+//!
+//! let mut database = SteelDB::new();
+//! let script = "create table t (a int); insert into t values (1); select a from t;";
+//! match database.execute(script.to_string()) {
+//!     ExecutionResult::ScriptResult(results) => assert_eq!(results.len(), 3),
+//!     ExecutionResult::ScriptError(index, error) => println!("Statement {} failed", index),
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! Statements that are executed many times with different values can be prepared once,
+//! giving the values as parameters, so that they are never parsed as SQL:
+//!
+//! ```no_run
+//! use steeldb::{DataType, SteelDB};
+//!
+//! let mut database = SteelDB::new();
+//! let statement = database.prepare("insert into t values (?, ?);".to_string()).unwrap();
+//! for (name, grade) in [("Ann", 4.0), ("Bob", 3.5)] {
+//!     let values = [DataType::String(name.to_string()), DataType::Float32(grade)];
+//!     database.execute_prepared(&statement, &values);
 //! }
 //! ```
 //!
@@ -59,5 +98,8 @@
 mod database;
 
 pub use database::config;
+pub use database::prepared_statement::Statement;
 pub use database::steeldb::SteelDB;
-pub use steeldb_core::{DataType, ExecutionResult, SteelDBInterface, Table, TableErrors};
+pub use steeldb_core::{
+    DataType, ExecutionResult, ParseError, ParseErrorKind, SteelDBInterface, Table, TableErrors,
+};
@@ -1,4 +1,5 @@
 use crate::DataType;
+use crate::ParseError;
 use crate::Table;
 use crate::VERSION;
 use std::collections::HashMap;
@@ -29,7 +30,10 @@ impl ConsolePrinter {
     /// Prints the available help.
     pub fn print_help(&self) {
         println!("Type 'exit;' to leave this shell");
-        println!("Current supported commands: [select]");
+        println!(
+            "Current supported commands: [select, with, create table, insert, drop table, truncate table, alter table, update, delete, explain]"
+        );
+        println!("Several statements may be given at once, each ending with ';'");
         println!("");
     }
 
@@ -93,6 +97,10 @@ impl ConsolePrinter {
                             val = integer_value.to_string();
                             size_of_value = val.len() as i32;
                         }
+                        DataType::Null => {
+                            val = "NULL".to_string();
+                            size_of_value = val.len() as i32;
+                        }
                     },
                     // Missing value case
                     None => size_of_value = 0,
@@ -177,4 +185,15 @@ impl ConsolePrinter {
 
         io::stdout().flush().unwrap();
     }
+
+    /// Prints the line of the input where the parse error happened, with a caret under the problem.
+    pub fn print_parse_error(&self, input: &str, error: &ParseError) {
+        let line = input.split('\n').nth(error.line - 1).unwrap_or("");
+        println!("{}", line.trim_end());
+        for _ in 1..error.column {
+            print!(" ");
+        }
+        println!("^");
+        println!("{}", error);
+    }
 }
@@ -1,9 +1,11 @@
 //! The module for defining data types supported by the Database.
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 
 /// The supported data type stored by the Database.
 /// By using the Enum, we can resolve the column type dynamically in run time.
 #[cfg(not(feature = "json"))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum DataType {
     /// Default String type. For now does not impose a length limit.
     /// This might change in the future.
@@ -12,13 +14,16 @@ pub enum DataType {
     Integer32(i32),
     /// Wrapper for primitive f32.
     Float32(f32),
+    /// The SQL NULL, a missing value, such as the columns of an unmatched row in an outer join.
+    /// It is not a column type, so tables never declare it in their schema.
+    Null,
 }
 
 #[cfg(feature = "json")]
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "json")]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum DataType {
     /// Default String type. For now does not impose a length limit.
     /// This might change in the future.
@@ -27,6 +32,9 @@ pub enum DataType {
     Integer32(i32),
     /// Wrapper for primitive f32.
     Float32(f32),
+    /// The SQL NULL, a missing value, such as the columns of an unmatched row in an outer join.
+    /// It is not a column type, so tables never declare it in their schema.
+    Null,
 }
 
 
@@ -45,6 +53,90 @@ impl DataType {
             Self::Float32(_) => {
                 return "f32".to_string();
             }
+            Self::Null => {
+                return "NULL".to_string();
+            }
+        }
+    }
+
+    /// A total ordering over every value, used to sort rows.
+    ///
+    /// Integers and floats are compared by their numeric value, so `1` equals `1.0`.
+    /// NaN is greater than every other number and equal to itself.
+    /// Numbers sort before strings, and strings are compared by their bytes.
+    /// NULL is greater than every other value and equal to itself.
+    pub fn total_cmp(&self, other: &DataType) -> Ordering {
+        match (self, other) {
+            (Self::Null, Self::Null) => return Ordering::Equal,
+            (Self::Null, _) => return Ordering::Greater,
+            (_, Self::Null) => return Ordering::Less,
+            (Self::String(l), Self::String(r)) => return l.cmp(r),
+            (Self::String(_), _) => return Ordering::Greater,
+            (_, Self::String(_)) => return Ordering::Less,
+            (Self::Integer32(l), Self::Integer32(r)) => return l.cmp(r),
+            (l, r) => {
+                let l = l.as_f64().unwrap();
+                let r = r.as_f64().unwrap();
+                match (l.is_nan(), r.is_nan()) {
+                    (true, true) => return Ordering::Equal,
+                    (true, false) => return Ordering::Greater,
+                    (false, true) => return Ordering::Less,
+                    (false, false) => return l.partial_cmp(&r).unwrap(),
+                }
+            }
+        }
+    }
+
+    /// Returns the numeric value as a f64, which represents both i32 and f32 exactly.
+    /// Returns `None` for strings and NULL.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::String(_) | Self::Null => return None,
+            Self::Integer32(i) => return Some(*i as f64),
+            Self::Float32(f) => return Some(*f as f64),
+        }
+    }
+}
+
+/// A row of values that can be hashed and compared for equality, such as grouping keys or the rows of
+/// a `SELECT DISTINCT`. [DataType] cannot be `Eq` itself, as a Float32 NaN is not equal to itself.
+/// Instead, values are equal when they are equal by [DataType::total_cmp],
+/// so `1` equals `1.0`, NaN equals NaN and NULL equals NULL.
+#[derive(Debug, Clone)]
+pub struct RowKey(pub Vec<DataType>);
+
+impl PartialEq for RowKey {
+    fn eq(&self, other: &RowKey) -> bool {
+        return self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|(left, right)| left.total_cmp(right).is_eq());
+    }
+}
+
+impl Eq for RowKey {}
+
+impl Hash for RowKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for value in self.0.iter() {
+            match value {
+                DataType::String(s) => s.hash(state),
+                DataType::Null => 0u8.hash(state),
+                // numbers are hashed by their f64 value, which is how they are compared
+                number => {
+                    let number = number.as_f64().unwrap();
+                    let number = if number.is_nan() {
+                        f64::NAN
+                    } else if number == 0.0 {
+                        0.0
+                    } else {
+                        number
+                    };
+                    number.to_bits().hash(state);
+                }
+            }
         }
     }
 }
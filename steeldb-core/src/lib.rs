@@ -3,14 +3,16 @@ pub mod table;
 pub mod repl;
 pub mod console_printer;
 pub mod steeldb_interface;
+pub mod parse_error;
 
 #[cfg(feature = "json")]
 pub mod json_result;
 
 pub use crate::table::{Table, TableErrors, SaveMode, ExecutionResult, FileFormat};
-pub use crate::datatypes::DataType;
+pub use crate::datatypes::{DataType, RowKey};
 pub use crate::repl::Repl;
 pub use crate::steeldb_interface::SteelDBInterface;
+pub use crate::parse_error::{ParseError, ParseErrorKind};
 
 /// Crate version defined in `Cargo.toml` file, retrieved at runtime.
 /// This is displayed in the REPL.
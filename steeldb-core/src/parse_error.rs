@@ -0,0 +1,79 @@
+//! Structured errors returned when parsing user input fails.
+use std::fmt;
+
+/// The possible reasons for a parse failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// The input contains characters that do not form any known token.
+    InvalidToken,
+    /// A token was found where the grammar does not allow it.
+    UnrecognizedToken,
+    /// The input ended before the statement was complete.
+    UnexpectedEndOfInput,
+    /// A complete statement was parsed, but more tokens followed.
+    ExtraToken,
+    /// A token was recognized, but its value is invalid, e.g. an integer out of range.
+    InvalidValue(String),
+}
+
+/// A parse error, locating the problem in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Why the parse failed.
+    pub kind: ParseErrorKind,
+    /// Byte offset of the problem in the input.
+    pub offset: usize,
+    /// Line of the problem in the input, starting at 1.
+    pub line: usize,
+    /// Column of the problem in its line, in characters, starting at 1.
+    pub column: usize,
+    /// The offending token, if any. This is `None` when the input ended unexpectedly.
+    pub token: Option<String>,
+    /// The tokens the parser would have accepted at this position.
+    pub expected: Vec<String>,
+}
+
+impl ParseError {
+    /// Creates a new error, computing the line and column from the byte offset in the input.
+    pub fn new(
+        kind: ParseErrorKind,
+        input: &str,
+        offset: usize,
+        token: Option<String>,
+        expected: Vec<String>,
+    ) -> ParseError {
+        let offset = offset.min(input.len());
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+        return ParseError {
+            kind,
+            offset,
+            line,
+            column,
+            token,
+            expected,
+        };
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::InvalidToken => write!(f, "Invalid token")?,
+            ParseErrorKind::UnrecognizedToken => write!(f, "Unexpected token")?,
+            ParseErrorKind::UnexpectedEndOfInput => write!(f, "Unexpected end of input")?,
+            ParseErrorKind::ExtraToken => write!(f, "Extra token")?,
+            ParseErrorKind::InvalidValue(message) => write!(f, "{}", message)?,
+        }
+        if let Some(token) = &self.token {
+            write!(f, " '{}'", token)?;
+        }
+        write!(f, " at line {}, column {}", self.line, self.column)?;
+        if !self.expected.is_empty() {
+            write!(f, ", expected one of: {}", self.expected.join(", "))?;
+        }
+        return Ok(());
+    }
+}
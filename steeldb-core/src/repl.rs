@@ -29,6 +29,50 @@ impl Repl {
         };
     }
 
+    /// Prints the result of executing the input.
+    /// The results of a script are printed in order, and a failing statement is identified by its number.
+    fn print_result(&self, input: &str, execution_result: ExecutionResult) {
+        match execution_result {
+            ExecutionResult::VoidOK => {
+                println!("OK!");
+            }
+            ExecutionResult::RowsAffected(number_rows) => {
+                println!("OK! {} row(s) affected", number_rows);
+            }
+            ExecutionResult::TableResult(table) => {
+                self.console.print_table(table);
+            }
+            ExecutionResult::ParseError(error) => {
+                println!("");
+                println!("");
+                println!("<------------------- PARSE ERROR ------------------->");
+                self.console.print_parse_error(input, &error);
+                println!("");
+                println!("Please check your input");
+                println!("<--------------------------------------------------->");
+                println!("");
+            }
+            ExecutionResult::CommandError(error) => {
+                println!("");
+                println!("");
+                println!("<------------------ COMMAND FAILED ------------------>");
+                println!("{:?}", error);
+                println!("");
+                println!("<---------------------------------------------------->");
+                println!("");
+            }
+            ExecutionResult::ScriptResult(results) => {
+                for result in results {
+                    self.print_result(input, result);
+                }
+            }
+            ExecutionResult::ScriptError(index, error) => {
+                println!("Statement {} failed, the script was stopped", index + 1);
+                self.print_result(input, *error);
+            }
+        }
+    }
+
     /// The main loop (literally, the REPL).
     pub fn main_loop(&mut self) {
         self.console.print_banner();
@@ -49,37 +93,12 @@ impl Repl {
                     break;
                 }
                 self.is_in_multiline = false;
-                let execution_result = self
-                    .database
-                    .execute(self.previous_lines.join(" ").to_lowercase());
+                // keywords are case insensitive, so the input is forwarded as is
+                // lowercasing it would also change string literals
+                let input = self.previous_lines.join(" ");
+                let execution_result = self.database.execute(input.clone());
 
-                match execution_result {
-                    ExecutionResult::VoidOK => {
-                        println!("OK!");
-                    }
-                    ExecutionResult::TableResult(table) => {
-                        self.console.print_table(table);
-                    }
-                    ExecutionResult::ParseError(error) => {
-                        println!("");
-                        println!("");
-                        println!("<------------------- PARSE ERROR ------------------->");
-                        println!("{:?}", error);
-                        println!("");
-                        println!("Please check your input");
-                        println!("<--------------------------------------------------->");
-                        println!("");
-                    }
-                    ExecutionResult::CommandError(error) => {
-                        println!("");
-                        println!("");
-                        println!("<------------------ COMMAND FAILED ------------------>");
-                        println!("{:?}", error);
-                        println!("");
-                        println!("<---------------------------------------------------->");
-                        println!("");
-                    }
-                }
+                self.print_result(&input, execution_result);
                 self.buffer.clear();
                 self.previous_lines.clear();
             }
@@ -1,11 +1,12 @@
 //! Public interface of table.
 
 use crate::DataType;
+use crate::ParseError;
 use std::collections::HashMap;
 
 // Enums
 /// Defines the supported file formats by the Database
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FileFormat {
     /// The only supported file for now is the SimpleColumnar, which is a naive ASCII format.
     /// Here is an example of this format:
@@ -58,11 +59,21 @@ pub enum ExecutionResult {
     TableResult(Box<dyn Table>),
     /// A result where a command was successfully executed, but with no output.
     VoidOK,
+    /// A result where a command successfully modified rows, holding how many.
+    /// This is returned by commands such as INSERT and UPDATE.
+    RowsAffected(usize),
     /// Parse error. The given input string was not valid for the parser.
-    ParseError(String),
+    /// The error locates the problem in the input.
+    ParseError(ParseError),
     /// Command error. Something went wrong when executing the command.
     /// Examples include `ColumnNotFound`, `TableNotFound` etc.
     CommandError(String),
+    /// The results of a script of several statements, in the order they were executed.
+    ScriptResult(Vec<ExecutionResult>),
+    /// A script of several statements stopped at a failing statement, holding its index, starting at 0,
+    /// and its `ParseError` or `CommandError`. The statements before it were executed,
+    /// unless the failure is a `ParseError`, as the whole script is parsed first.
+    ScriptError(usize, Box<ExecutionResult>),
 }
 
 /// The defined errors that might occur when loading or saving a table.
@@ -70,7 +81,7 @@ pub enum ExecutionResult {
 #[derive(Debug)]
 pub enum TableErrors {
     /// The table with the given name was not found.
-    TableNotFound,
+    TableNotFound(String),
     /// Attempted to save a table with a name that already exists.
     TableAlreadyExists,
     /// The select column was not found in the table.
@@ -0,0 +1,1728 @@
+//! Defines the structures produced by the parser.
+//! These are consumed by the SteelDB crate, which maps them into commands for its VirtualMachine.
+use std::fmt;
+use steeldb_core::DataType;
+
+/// Any statement supported by the parser.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    /// A SELECT statement, boxed since it is much larger than the other statements.
+    Select(Box<SelectStatement>),
+    /// A CREATE TABLE statement.
+    CreateTable(CreateTableStatement),
+    /// An INSERT INTO statement.
+    Insert(InsertStatement),
+    /// A DROP TABLE statement.
+    DropTable(DropTableStatement),
+    /// A TRUNCATE TABLE statement.
+    TruncateTable(TruncateTableStatement),
+    /// An ALTER TABLE statement.
+    AlterTable(AlterTableStatement),
+    /// An UPDATE statement.
+    Update(UpdateStatement),
+    /// A DELETE FROM statement.
+    Delete(DeleteStatement),
+    /// An EXPLAIN statement, in the format 'explain statement;', describing how the statement would be executed
+    /// instead of executing it.
+    Explain(Box<Statement>),
+}
+
+impl Statement {
+    /// Rebuilds this statement, replacing each parameter with the result of `map`,
+    /// see [Expression::map_parameters]. The values of INSERT rows are replaced when `map` gives a literal,
+    /// and renumbered when it gives another parameter.
+    pub fn map_parameters<E>(
+        &self,
+        map: &mut impl FnMut(Parameter) -> Result<Expression, E>,
+    ) -> Result<Statement, E> {
+        match self {
+            Statement::Select(statement) => {
+                return Ok(Statement::Select(Box::new(statement.map_parameters(map)?)));
+            }
+            Statement::Insert(statement) => {
+                let mut statement = statement.clone();
+                let mut parameters = Vec::<(usize, usize, Parameter)>::new();
+                for (row, column, parameter) in statement.parameters.iter() {
+                    match map(*parameter)? {
+                        Expression::Literal(value) => statement.rows[*row][*column] = value,
+                        Expression::Parameter(parameter) => {
+                            parameters.push((*row, *column, parameter))
+                        }
+                        _ => parameters.push((*row, *column, *parameter)),
+                    }
+                }
+                statement.parameters = parameters;
+                return Ok(Statement::Insert(statement));
+            }
+            Statement::Update(statement) => {
+                let mut statement = statement.clone();
+                for (_, expression) in statement.assignments.iter_mut() {
+                    *expression = expression.map_parameters(map)?;
+                }
+                if let Some(filter) = &statement.filter {
+                    statement.filter = Some(filter.map_parameters(map)?);
+                }
+                return Ok(Statement::Update(statement));
+            }
+            Statement::Delete(statement) => {
+                let mut statement = statement.clone();
+                if let Some(filter) = &statement.filter {
+                    statement.filter = Some(filter.map_parameters(map)?);
+                }
+                return Ok(Statement::Delete(statement));
+            }
+            Statement::Explain(statement) => {
+                return Ok(Statement::Explain(Box::new(statement.map_parameters(map)?)));
+            }
+            _ => return Ok(self.clone()),
+        }
+    }
+
+    /// Returns the number of parameters of this statement, which is the largest parameter number.
+    pub fn parameter_count(&self) -> usize {
+        let mut count = 0;
+        let _ = self.map_parameters(&mut |parameter| -> Result<Expression, ()> {
+            count = count.max(parameter.number());
+            return Ok(Expression::Parameter(parameter));
+        });
+        return count;
+    }
+}
+
+/// A parsed SELECT statement, in the format
+/// 'select [distinct] col1, col2 from table where predicate group by col1 having predicate order by col1 limit n offset m;'.
+/// It may be combined with other selects by set operations, as in 'select a from t union select a from u order by a;',
+/// and preceded by a WITH clause, as in 'with r as (select a from t) select a from r;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SelectStatement {
+    /// The WITH clause, naming the results of selects that the statement may read as tables, if any.
+    pub with: Option<With>,
+    /// Whether duplicate result rows are removed, as given by SELECT DISTINCT.
+    pub distinct: bool,
+    /// The items given in the SELECT clause.
+    pub columns: Vec<SelectItem>,
+    /// The table given in the FROM clause, which may join several tables.
+    /// This is `None` when the clause is omitted.
+    pub table: Option<TableReference>,
+    /// The predicate given in the WHERE clause, if any.
+    pub filter: Option<Expression>,
+    /// The grouping keys given in the GROUP BY clause.
+    pub group_by: Vec<Expression>,
+    /// The predicate given in the HAVING clause, which filters the groups.
+    pub having: Option<Expression>,
+    /// The sort keys given in the ORDER BY clause, from the most to the least significant.
+    pub order_by: Vec<OrderBy>,
+    /// The maximum number of rows given in the LIMIT clause, if any.
+    pub limit: Option<usize>,
+    /// The number of rows to skip given in the OFFSET clause, if any.
+    pub offset: Option<usize>,
+    /// The set operations combining the rows of this select with the rows of other selects, applied in order.
+    /// When there are any, the ORDER BY, LIMIT and OFFSET clauses apply to the combined rows.
+    pub set_operations: Vec<SetOperation>,
+}
+
+impl SelectStatement {
+    /// Returns the names of the result columns, as given in the SELECT clause.
+    /// An aliased expression is named by its alias.
+    pub fn column_names(&self) -> Vec<String> {
+        return self.columns.iter().map(|item| item.name()).collect();
+    }
+
+    /// Replaces the sort keys naming an alias of the SELECT clause with the aliased expression,
+    /// so that 'select a + b as total from t order by total' sorts by 'a + b'.
+    pub fn expand_order_by_aliases(&mut self) {
+        for order_by in self.order_by.iter_mut() {
+            let name = match &order_by.expression {
+                Expression::Column(name) => name,
+                _ => continue,
+            };
+            let aliased = self.columns.iter().find_map(|item| match item {
+                SelectItem::Expression(expression, Some(alias)) if alias == name => {
+                    Some(expression.clone())
+                }
+                _ => None,
+            });
+            if let Some(expression) = aliased {
+                order_by.expression = expression;
+            }
+        }
+    }
+
+    /// Whether the rows are reduced into groups, either by a GROUP BY or HAVING clause, or by using aggregates.
+    pub fn is_grouped(&self) -> bool {
+        let aggregated_item = self.columns.iter().any(|item| match item {
+            SelectItem::Wildcard => false,
+            SelectItem::Expression(expression, _) => expression.contains_aggregate(),
+        });
+        let aggregated_order = self
+            .order_by
+            .iter()
+            .any(|order_by| order_by.expression.contains_aggregate());
+        return !self.group_by.is_empty()
+            || self.having.is_some()
+            || aggregated_item
+            || aggregated_order;
+    }
+
+    /// Rebuilds this statement, replacing each parameter with the result of `map`,
+    /// in every clause, including the ones of its subqueries, set operations and WITH clause.
+    pub fn map_parameters<E>(
+        &self,
+        map: &mut impl FnMut(Parameter) -> Result<Expression, E>,
+    ) -> Result<SelectStatement, E> {
+        let mut statement = self.clone();
+        if let Some(with) = &mut statement.with {
+            for table in with.tables.iter_mut() {
+                table.select = table.select.map_parameters(map)?;
+            }
+        }
+        for item in statement.columns.iter_mut() {
+            if let SelectItem::Expression(expression, _) = item {
+                *expression = expression.map_parameters(map)?;
+            }
+        }
+        if let Some(table) = &statement.table {
+            statement.table = Some(table.map_parameters(map)?);
+        }
+        if let Some(filter) = &statement.filter {
+            statement.filter = Some(filter.map_parameters(map)?);
+        }
+        for key in statement.group_by.iter_mut() {
+            *key = key.map_parameters(map)?;
+        }
+        if let Some(having) = &statement.having {
+            statement.having = Some(having.map_parameters(map)?);
+        }
+        for key in statement.order_by.iter_mut() {
+            key.expression = key.expression.map_parameters(map)?;
+        }
+        for operation in statement.set_operations.iter_mut() {
+            operation.select = operation.select.map_parameters(map)?;
+        }
+        return Ok(statement);
+    }
+
+    /// Whether this statement reads the stored table with the given name, in any of its FROM clauses,
+    /// including the ones of its subqueries, set operations and WITH clause.
+    pub fn references_table(&self, name: &str) -> bool {
+        if let Some(table) = &self.table {
+            if table.table_names().contains(&name) {
+                return true;
+            }
+            if table
+                .subqueries()
+                .iter()
+                .any(|statement| statement.references_table(name))
+            {
+                return true;
+            }
+        }
+        let mut expressions = Vec::<&Expression>::new();
+        for item in self.columns.iter() {
+            if let SelectItem::Expression(expression, _) = item {
+                expressions.push(expression);
+            }
+        }
+        expressions.extend(self.filter.iter());
+        expressions.extend(self.having.iter());
+        expressions.extend(self.order_by.iter().map(|order_by| &order_by.expression));
+        for expression in expressions {
+            for subquery in expression.subqueries() {
+                let statement = match subquery {
+                    Expression::Subquery(statement)
+                    | Expression::Exists(statement)
+                    | Expression::InSubquery(_, statement, _) => statement,
+                    _ => continue,
+                };
+                if statement.references_table(name) {
+                    return true;
+                }
+            }
+        }
+        if let Some(with) = &self.with {
+            if with
+                .tables
+                .iter()
+                .any(|table| table.select.references_table(name))
+            {
+                return true;
+            }
+        }
+        return self
+            .set_operations
+            .iter()
+            .any(|operation| operation.select.references_table(name));
+    }
+
+    /// Returns the names of every column referenced by this statement, including its subqueries,
+    /// without duplicates and in the order they first appear.
+    /// Columns of a subquery may refer to this statement, so these are the columns its enclosing query may need.
+    pub fn referenced_columns(&self) -> Vec<String> {
+        let mut expressions = Vec::<&Expression>::new();
+        for item in self.columns.iter() {
+            if let SelectItem::Expression(expression, _) = item {
+                expressions.push(expression);
+            }
+        }
+        expressions.extend(self.filter.iter());
+        expressions.extend(self.group_by.iter());
+        expressions.extend(self.having.iter());
+        expressions.extend(self.order_by.iter().map(|order_by| &order_by.expression));
+        let mut columns = Vec::<String>::new();
+        if let Some(table) = &self.table {
+            expressions.extend(table.join_conditions());
+            for statement in table.subqueries() {
+                columns.extend(statement.referenced_columns());
+            }
+        }
+        for operation in self.set_operations.iter() {
+            columns.extend(operation.select.referenced_columns());
+        }
+        for expression in expressions {
+            expression.collect_columns(&mut columns);
+        }
+        let mut unique = Vec::<String>::new();
+        for column in columns {
+            if !unique.contains(&column) {
+                unique.push(column);
+            }
+        }
+        return unique;
+    }
+
+    /// Whether any clause of this statement, except FROM, contains a subquery.
+    pub fn has_subqueries(&self) -> bool {
+        let item_subquery = self.columns.iter().any(|item| match item {
+            SelectItem::Wildcard => false,
+            SelectItem::Expression(expression, _) => expression.contains_subquery(),
+        });
+        return item_subquery
+            || self.filter.iter().any(|filter| filter.contains_subquery())
+            || self.having.iter().any(|having| having.contains_subquery())
+            || self
+                .order_by
+                .iter()
+                .any(|order_by| order_by.expression.contains_subquery());
+    }
+
+    /// Checks the rules of aggregation, which the grammar alone cannot express.
+    /// Aggregates are not allowed in WHERE or GROUP BY, nor inside other aggregates.
+    /// Subqueries are not allowed in GROUP BY, JOIN conditions or aggregates.
+    /// In a grouped select, every column of the SELECT, HAVING and ORDER BY clauses
+    /// must either be a grouping key or be inside an aggregate.
+    /// The ORDER BY clause of a set operation sorts the combined rows, so it cannot use aggregates or subqueries.
+    /// Window functions are only allowed in the SELECT and ORDER BY clauses, and cannot be nested.
+    /// With DISTINCT, the rows can only be sorted by the selected columns, which are kept.
+    /// The tables of a WITH clause must have distinct names, see [With] for the form of recursive ones.
+    /// The parser does not check these rules, and the selects nested in subqueries, derived tables
+    /// and WITH clauses are not checked here, so each select must be validated before it is executed.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(with) = &self.with {
+            with.validate()?;
+        }
+        if let Some(filter) = &self.filter {
+            if filter.contains_aggregate() {
+                return Err("Aggregate functions are not allowed in WHERE".to_string());
+            }
+        }
+        for key in self.group_by.iter() {
+            if key.contains_aggregate() {
+                return Err("Aggregate functions are not allowed in GROUP BY".to_string());
+            }
+            if key.contains_subquery() {
+                return Err("Subqueries are not allowed in GROUP BY".to_string());
+            }
+        }
+        if let Some(table) = &self.table {
+            let aliases = table.aliases();
+            for (index, alias) in aliases.iter().enumerate() {
+                if aliases[..index].contains(alias) {
+                    return Err(format!("Table name {} specified more than once", alias));
+                }
+            }
+            for condition in table.join_conditions() {
+                if condition.contains_aggregate() {
+                    return Err(
+                        "Aggregate functions are not allowed in JOIN conditions".to_string()
+                    );
+                }
+                if condition.contains_subquery() {
+                    return Err("Subqueries are not allowed in JOIN conditions".to_string());
+                }
+            }
+        }
+        let mut expressions = Vec::<&Expression>::new();
+        for item in self.columns.iter() {
+            match item {
+                SelectItem::Wildcard => {
+                    if self.is_grouped() {
+                        return Err("Cannot select * from grouped rows".to_string());
+                    }
+                }
+                SelectItem::Expression(expression, _) => expressions.push(expression),
+            }
+        }
+        if self.distinct && self.set_operations.is_empty() {
+            let has_wildcard = self.columns.contains(&SelectItem::Wildcard);
+            for order_by in self.order_by.iter() {
+                let is_selected = expressions.contains(&&order_by.expression)
+                    || (has_wildcard && matches!(order_by.expression, Expression::Column(_)));
+                if !is_selected {
+                    return Err(
+                        "For SELECT DISTINCT, ORDER BY expressions must appear in the select list"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+        expressions.extend(self.having.iter());
+        if self.set_operations.is_empty() {
+            expressions.extend(self.order_by.iter().map(|order_by| &order_by.expression));
+        } else {
+            // the ORDER BY clause of a set operation sorts the combined rows
+            for order_by in self.order_by.iter() {
+                if order_by.expression.contains_aggregate() {
+                    return Err(
+                        "Aggregate functions are not allowed in the ORDER BY of a set operation"
+                            .to_string(),
+                    );
+                }
+                if order_by.expression.contains_subquery() {
+                    return Err(
+                        "Subqueries are not allowed in the ORDER BY of a set operation".to_string(),
+                    );
+                }
+            }
+        }
+        for operation in self.set_operations.iter() {
+            operation.select.validate()?;
+        }
+
+        // window functions are computed once the rows are filtered and grouped
+        let mut clauses = Vec::<(&str, &Expression)>::new();
+        clauses.extend(self.filter.iter().map(|filter| ("WHERE", filter)));
+        clauses.extend(self.group_by.iter().map(|key| ("GROUP BY", key)));
+        clauses.extend(self.having.iter().map(|having| ("HAVING", having)));
+        if let Some(table) = &self.table {
+            for condition in table.join_conditions() {
+                clauses.push(("JOIN conditions", condition));
+            }
+        }
+        if !self.set_operations.is_empty() {
+            for order_by in self.order_by.iter() {
+                clauses.push(("the ORDER BY of a set operation", &order_by.expression));
+            }
+        }
+        for (clause, expression) in clauses {
+            if expression.contains_window_function() {
+                return Err(format!("Window functions are not allowed in {}", clause));
+            }
+        }
+        for expression in expressions.iter() {
+            for window in expression.window_functions() {
+                let children = window.children();
+                if children
+                    .iter()
+                    .any(|child| child.contains_window_function())
+                {
+                    return Err("Window functions cannot be nested".to_string());
+                }
+            }
+        }
+
+        for expression in expressions.iter() {
+            for aggregate in expression.aggregates() {
+                if let Expression::Aggregate(_, Some(argument), _) = aggregate {
+                    if argument.contains_aggregate() {
+                        return Err("Aggregate functions cannot be nested".to_string());
+                    }
+                    if argument.contains_window_function() {
+                        return Err(
+                            "Window functions are not allowed in aggregate functions".to_string()
+                        );
+                    }
+                    if argument.contains_subquery() {
+                        return Err("Subqueries are not allowed in aggregate functions".to_string());
+                    }
+                }
+            }
+        }
+        if !self.is_grouped() {
+            return Ok(());
+        }
+        for expression in expressions {
+            if let Some(column) = expression.ungrouped_column(&self.group_by) {
+                return Err(format!(
+                    "Column {} must appear in the GROUP BY clause or be used in an aggregate function",
+                    column
+                ));
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Formats the statement back into SQL, which names the result of a scalar subquery.
+impl fmt::Display for SelectStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(with) = &self.with {
+            write!(f, "{} ", with)?;
+        }
+        let items: Vec<String> = self.columns.iter().map(|item| item.to_string()).collect();
+        let distinct = if self.distinct { "DISTINCT " } else { "" };
+        write!(f, "SELECT {}{}", distinct, items.join(", "))?;
+        if let Some(table) = &self.table {
+            write!(f, " FROM {}", table)?;
+        }
+        if let Some(filter) = &self.filter {
+            write!(f, " WHERE {}", filter)?;
+        }
+        if !self.group_by.is_empty() {
+            let keys: Vec<String> = self.group_by.iter().map(|key| key.to_string()).collect();
+            write!(f, " GROUP BY {}", keys.join(", "))?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, " HAVING {}", having)?;
+        }
+        for operation in self.set_operations.iter() {
+            write!(f, " {}", operation)?;
+        }
+        if !self.order_by.is_empty() {
+            let keys: Vec<String> = self.order_by.iter().map(|key| key.to_string()).collect();
+            write!(f, " ORDER BY {}", keys.join(", "))?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+        return Ok(());
+    }
+}
+
+/// A WITH clause, in the format 'with [recursive] name [(col1, col2)] as (select ...), ...'.
+/// Each table may be read by the tables after it and by the statement, under its name,
+/// which takes precedence over stored tables.
+///
+/// With RECURSIVE, a table that reads itself must be in the format 'initial union [all] recursive',
+/// where only the recursive select reads the table. The initial select gives the first rows,
+/// then the recursive select is executed with the rows of the previous step as the table,
+/// until it adds no new rows.
+#[derive(Debug, PartialEq, Clone)]
+pub struct With {
+    /// Whether the tables may read themselves, as given by WITH RECURSIVE.
+    pub recursive: bool,
+    /// The named selects, in the order they are given.
+    pub tables: Vec<CommonTableExpression>,
+}
+
+impl With {
+    /// Checks that the tables have distinct names and that recursive tables have the form described in [With].
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, table) in self.tables.iter().enumerate() {
+            if self.tables[..index]
+                .iter()
+                .any(|other| other.name == table.name)
+            {
+                return Err(format!(
+                    "WITH query name {} specified more than once",
+                    table.name
+                ));
+            }
+            if !self.recursive || !table.select.references_table(&table.name) {
+                continue;
+            }
+            let is_recursive_union = match table.select.set_operations.last() {
+                Some(operation) => operation.operator == SetOperator::Union,
+                None => false,
+            };
+            if !is_recursive_union || table.initial_select().references_table(&table.name) {
+                return Err(format!(
+                    "Recursive query {} must be in the form initial UNION [ALL] recursive, where only the recursive select refers to it",
+                    table.name
+                ));
+            }
+            let select = &table.select;
+            if !select.order_by.is_empty() || select.limit.is_some() || select.offset.is_some() {
+                return Err(format!(
+                    "Recursive query {} cannot have ORDER BY, LIMIT or OFFSET",
+                    table.name
+                ));
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for With {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tables: Vec<String> = self.tables.iter().map(|table| table.to_string()).collect();
+        let recursive = if self.recursive { "RECURSIVE " } else { "" };
+        return write!(f, "WITH {}{}", recursive, tables.join(", "));
+    }
+}
+
+/// A table of a WITH clause, known as a common table expression.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CommonTableExpression {
+    /// The name the table is read by.
+    pub name: String,
+    /// The names given to the columns of the select, if any. Otherwise, the columns keep their names.
+    pub columns: Vec<String>,
+    /// The select computing the rows of the table.
+    pub select: SelectStatement,
+}
+
+impl CommonTableExpression {
+    /// Returns the select without its last set operation, which gives the first rows of a recursive table.
+    pub fn initial_select(&self) -> SelectStatement {
+        let mut initial = self.select.clone();
+        initial.set_operations.pop();
+        return initial;
+    }
+}
+
+impl fmt::Display for CommonTableExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.columns.is_empty() {
+            write!(f, " ({})", self.columns.join(", "))?;
+        }
+        return write!(f, " AS ({})", self.select);
+    }
+}
+
+/// A table given in the FROM clause.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TableReference {
+    /// A stored table, in the format 'name [[as] alias]'.
+    Table(String, Option<String>),
+    /// The result of a subquery, known as a derived table, in the format '(select ...) [as] alias'.
+    Subquery(Box<SelectStatement>, String),
+    /// Two joined tables, in the format 'left join right on predicate'.
+    Join(
+        Box<TableReference>,
+        JoinKind,
+        Box<TableReference>,
+        Expression,
+    ),
+}
+
+impl TableReference {
+    /// Whether this is a single table without an alias, whose columns can be used unqualified.
+    pub fn is_plain_table(&self) -> bool {
+        return matches!(self, TableReference::Table(_, None));
+    }
+
+    /// Returns the names that qualify the columns of each table, which are either
+    /// the table aliases or the table names, from left to right.
+    pub fn aliases(&self) -> Vec<String> {
+        match self {
+            TableReference::Table(name, alias) => {
+                return vec![alias.clone().unwrap_or_else(|| name.clone())]
+            }
+            TableReference::Subquery(_, alias) => return vec![alias.clone()],
+            TableReference::Join(left, _, right, _) => {
+                let mut aliases = left.aliases();
+                aliases.extend(right.aliases());
+                return aliases;
+            }
+        }
+    }
+
+    /// Returns the predicates of the ON clauses, from left to right.
+    pub fn join_conditions(&self) -> Vec<&Expression> {
+        match self {
+            TableReference::Table(_, _) | TableReference::Subquery(_, _) => return vec![],
+            TableReference::Join(left, _, right, condition) => {
+                let mut conditions = left.join_conditions();
+                conditions.extend(right.join_conditions());
+                conditions.push(condition);
+                return conditions;
+            }
+        }
+    }
+
+    /// Returns the names of the stored tables, from left to right.
+    pub fn table_names(&self) -> Vec<&str> {
+        match self {
+            TableReference::Table(name, _) => return vec![name],
+            TableReference::Subquery(_, _) => return vec![],
+            TableReference::Join(left, _, right, _) => {
+                let mut names = left.table_names();
+                names.extend(right.table_names());
+                return names;
+            }
+        }
+    }
+
+    /// Rebuilds this table, replacing the parameters of its join conditions and derived tables
+    /// with the result of `map`, see [SelectStatement::map_parameters].
+    pub fn map_parameters<E>(
+        &self,
+        map: &mut impl FnMut(Parameter) -> Result<Expression, E>,
+    ) -> Result<TableReference, E> {
+        match self {
+            TableReference::Table(_, _) => return Ok(self.clone()),
+            TableReference::Subquery(statement, alias) => {
+                let statement = statement.map_parameters(map)?;
+                return Ok(TableReference::Subquery(Box::new(statement), alias.clone()));
+            }
+            TableReference::Join(left, kind, right, condition) => {
+                let left = left.map_parameters(map)?;
+                let right = right.map_parameters(map)?;
+                return Ok(TableReference::Join(
+                    Box::new(left),
+                    *kind,
+                    Box::new(right),
+                    condition.map_parameters(map)?,
+                ));
+            }
+        }
+    }
+
+    /// Returns the statements of the derived tables, from left to right.
+    pub fn subqueries(&self) -> Vec<&SelectStatement> {
+        match self {
+            TableReference::Table(_, _) => return vec![],
+            TableReference::Subquery(statement, _) => return vec![statement],
+            TableReference::Join(left, _, right, _) => {
+                let mut statements = left.subqueries();
+                statements.extend(right.subqueries());
+                return statements;
+            }
+        }
+    }
+}
+
+impl fmt::Display for TableReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableReference::Table(name, None) => return write!(f, "{}", name),
+            TableReference::Table(name, Some(alias)) => return write!(f, "{} AS {}", name, alias),
+            TableReference::Subquery(statement, alias) => {
+                return write!(f, "({}) AS {}", statement, alias)
+            }
+            TableReference::Join(left, kind, right, condition) => {
+                return write!(f, "{} {} {} ON {}", left, kind, right, condition)
+            }
+        }
+    }
+}
+
+/// The set operators, which combine the rows of two selects with the same number of columns.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SetOperator {
+    /// `union`, keeping the rows of both selects.
+    Union,
+    /// `intersect`, keeping the rows of the left select that are also in the right select.
+    Intersect,
+    /// `except`, keeping the rows of the left select that are not in the right select.
+    Except,
+}
+
+impl fmt::Display for SetOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = match self {
+            SetOperator::Union => "UNION",
+            SetOperator::Intersect => "INTERSECT",
+            SetOperator::Except => "EXCEPT",
+        };
+        return write!(f, "{}", keyword);
+    }
+}
+
+/// A set operation combining the rows before it with the rows of another select.
+/// INTERSECT binds tighter than UNION and EXCEPT, so the select may itself have set operations,
+/// as in 'select a from t union select a from u intersect select a from v'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SetOperation {
+    /// How the rows are combined.
+    pub operator: SetOperator,
+    /// Whether duplicate rows are kept, as given by `ALL`.
+    /// Otherwise, the combined rows have no duplicates.
+    pub all: bool,
+    /// The select whose rows are combined, which has no ORDER BY, LIMIT or OFFSET clauses.
+    pub select: SelectStatement,
+}
+
+impl fmt::Display for SetOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let all = if self.all { " ALL" } else { "" };
+        return write!(f, "{}{} {}", self.operator, all, self.select);
+    }
+}
+
+/// The supported kinds of join.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JoinKind {
+    /// `[inner] join`, keeping only the pairs of rows matching the predicate.
+    Inner,
+    /// `left [outer] join`, also keeping the unmatched rows of the left table, with NULLs on the right.
+    Left,
+    /// `right [outer] join`, also keeping the unmatched rows of the right table, with NULLs on the left.
+    Right,
+    /// `full [outer] join`, also keeping the unmatched rows of both tables.
+    Full,
+}
+
+impl fmt::Display for JoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keywords = match self {
+            JoinKind::Inner => "JOIN",
+            JoinKind::Left => "LEFT JOIN",
+            JoinKind::Right => "RIGHT JOIN",
+            JoinKind::Full => "FULL JOIN",
+        };
+        return write!(f, "{}", keywords);
+    }
+}
+
+/// An item given in the SELECT clause.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SelectItem {
+    /// `*`, selecting every column of the table.
+    Wildcard,
+    /// An expression, such as a column name, an aggregate or `price * quantity`,
+    /// with the alias given by `[as] alias`, if any.
+    Expression(Expression, Option<String>),
+}
+
+impl SelectItem {
+    /// Returns the name of the result column, which is the alias when there is one,
+    /// and otherwise the expression formatted back into SQL.
+    pub fn name(&self) -> String {
+        match self {
+            SelectItem::Expression(_, Some(alias)) => return alias.clone(),
+            _ => return self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SelectItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectItem::Wildcard => return write!(f, "*"),
+            SelectItem::Expression(expression, None) => return write!(f, "{}", expression),
+            SelectItem::Expression(expression, Some(alias)) => {
+                return write!(f, "{} AS {}", expression, alias)
+            }
+        }
+    }
+}
+
+/// A sort key given in the ORDER BY clause, in the format 'expression [asc|desc] [nulls first|last]'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OrderBy {
+    /// The expression computing the sort key of each row.
+    pub expression: Expression,
+    /// Whether the rows are sorted from the largest to the smallest key.
+    pub descending: bool,
+    /// Whether nulls come before the other values. This defaults to `descending`,
+    /// as if nulls were larger than any other value.
+    pub nulls_first: bool,
+}
+
+impl fmt::Display for OrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)?;
+        if self.descending {
+            write!(f, " DESC")?;
+        }
+        if self.nulls_first != self.descending {
+            let keywords = if self.nulls_first {
+                "NULLS FIRST"
+            } else {
+                "NULLS LAST"
+            };
+            write!(f, " {}", keywords)?;
+        }
+        return Ok(());
+    }
+}
+
+/// A parsed CREATE TABLE statement, in the format 'create table [if not exists] name (col type, ...);'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CreateTableStatement {
+    /// The name of the table to create.
+    pub table: String,
+    /// The columns of the table, in the order they were declared.
+    pub columns: Vec<ColumnDefinition>,
+    /// Whether the statement should succeed when the table already exists.
+    pub if_not_exists: bool,
+}
+
+/// A column declared in a CREATE TABLE statement.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnDefinition {
+    /// The column name.
+    pub name: String,
+    /// The column type, represented by a [DataType] holding a default value.
+    pub data_type: DataType,
+}
+
+/// A parsed INSERT statement, in the format 'insert into name (col, ...) values (value, ...), ...;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InsertStatement {
+    /// The name of the table to insert into.
+    pub table: String,
+    /// The columns given after the table name. When `None`, values follow the table schema order.
+    pub columns: Option<Vec<String>>,
+    /// The rows of literal values to insert.
+    pub rows: Vec<Vec<DataType>>,
+    /// The values given by parameters, as their row, their position in the row and the parameter.
+    /// Their values in `rows` are NULL until bound.
+    pub parameters: Vec<(usize, usize, Parameter)>,
+}
+
+/// A parsed DROP TABLE statement, in the format 'drop table [if exists] name;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DropTableStatement {
+    /// The name of the table to drop.
+    pub table: String,
+    /// Whether the statement should succeed when the table does not exist.
+    pub if_exists: bool,
+}
+
+/// A parsed TRUNCATE TABLE statement, in the format 'truncate table name;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TruncateTableStatement {
+    /// The name of the table to truncate.
+    pub table: String,
+}
+
+/// A parsed ALTER TABLE statement, in the format 'alter table name action;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AlterTableStatement {
+    /// The name of the table to alter.
+    pub table: String,
+    /// The change to apply to the table.
+    pub action: AlterTableAction,
+}
+
+/// The changes supported by ALTER TABLE.
+#[derive(Debug, PartialEq, Clone)]
+pub enum AlterTableAction {
+    /// `add column name type [default value]`, where existing rows get the default value, or NULL without one.
+    AddColumn(ColumnDefinition, Option<DataType>),
+    /// `drop column name`
+    DropColumn(String),
+    /// `rename column old_name to new_name`
+    RenameColumn(String, String),
+    /// `rename to new_table_name`
+    RenameTable(String),
+}
+
+/// A parsed UPDATE statement, in the format 'update name set col = expression, ... where predicate;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UpdateStatement {
+    /// The name of the table to update.
+    pub table: String,
+    /// The columns to change, with the expressions that compute their new values.
+    pub assignments: Vec<(String, Expression)>,
+    /// The predicate given in the WHERE clause. When `None`, every row is updated.
+    pub filter: Option<Expression>,
+}
+
+/// A parsed DELETE statement, in the format 'delete from name where predicate;'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeleteStatement {
+    /// The name of the table to delete from.
+    pub table: String,
+    /// The predicate given in the WHERE clause. When `None`, every row is deleted.
+    pub filter: Option<Expression>,
+}
+
+/// The comparison operators supported in expressions.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ComparisonOperator {
+    /// `=`
+    Equal,
+    /// `<>` or `!=`
+    NotEqual,
+    /// `<`
+    LessThan,
+    /// `<=`
+    LessThanOrEqual,
+    /// `>`
+    GreaterThan,
+    /// `>=`
+    GreaterThanOrEqual,
+}
+
+/// The aggregate functions, which reduce the rows of a group into a single value.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AggregateFunction {
+    /// `COUNT`, the number of rows.
+    Count,
+    /// `SUM`, the sum of a numeric expression.
+    Sum,
+    /// `AVG`, the mean of a numeric expression.
+    Avg,
+    /// `MIN`, the smallest value of an expression.
+    Min,
+    /// `MAX`, the largest value of an expression.
+    Max,
+}
+
+impl AggregateFunction {
+    /// Resolves an aggregate function by its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<AggregateFunction> {
+        match name.to_lowercase().as_str() {
+            "count" => return Some(AggregateFunction::Count),
+            "sum" => return Some(AggregateFunction::Sum),
+            "avg" => return Some(AggregateFunction::Avg),
+            "min" => return Some(AggregateFunction::Min),
+            "max" => return Some(AggregateFunction::Max),
+            _ => return None,
+        }
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+/// The window functions, which compute a value for each row from the rows of its window.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WindowFunction {
+    /// `ROW_NUMBER()`, the position of the row in its partition, starting at 1.
+    RowNumber,
+    /// `RANK()`, the position of the first row of the partition with the same sort keys,
+    /// so ties share a rank and leave gaps after them.
+    Rank,
+    /// `DENSE_RANK()`, the number of distinct sort keys up to the row, so ties leave no gaps.
+    DenseRank,
+    /// `LAG(value [, offset [, default]])`, the value of the row `offset` rows before, 1 by default.
+    Lag,
+    /// `LEAD(value [, offset [, default]])`, the value of the row `offset` rows after, 1 by default.
+    Lead,
+    /// `FIRST_VALUE(value)`, the value of the first row of the frame.
+    FirstValue,
+    /// An aggregate over the rows of the frame, e.g. a running `SUM(amount)`.
+    Aggregate(AggregateFunction),
+}
+
+impl WindowFunction {
+    /// Resolves a window function by its name, ignoring case. Aggregates are also window functions.
+    pub fn from_name(name: &str) -> Option<WindowFunction> {
+        match name.to_lowercase().as_str() {
+            "row_number" => return Some(WindowFunction::RowNumber),
+            "rank" => return Some(WindowFunction::Rank),
+            "dense_rank" => return Some(WindowFunction::DenseRank),
+            "lag" => return Some(WindowFunction::Lag),
+            "lead" => return Some(WindowFunction::Lead),
+            "first_value" => return Some(WindowFunction::FirstValue),
+            _ => return AggregateFunction::from_name(name).map(WindowFunction::Aggregate),
+        }
+    }
+
+    /// Checks the number of arguments given to the function, which may only be omitted for `COUNT(*)`.
+    pub fn check_arguments(&self, count: usize) -> Result<(), String> {
+        let (minimum, maximum) = match self {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => (0, 0),
+            WindowFunction::Lag | WindowFunction::Lead => (1, 3),
+            WindowFunction::FirstValue | WindowFunction::Aggregate(_) => (1, 1),
+        };
+        if count < minimum || count > maximum {
+            if minimum == maximum {
+                return Err(format!("{} expects {} argument(s)", self, minimum));
+            }
+            return Err(format!(
+                "{} expects {} to {} arguments",
+                self, minimum, maximum
+            ));
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WindowFunction::RowNumber => "ROW_NUMBER",
+            WindowFunction::Rank => "RANK",
+            WindowFunction::DenseRank => "DENSE_RANK",
+            WindowFunction::Lag => "LAG",
+            WindowFunction::Lead => "LEAD",
+            WindowFunction::FirstValue => "FIRST_VALUE",
+            WindowFunction::Aggregate(function) => return write!(f, "{}", function),
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+/// The rows a window function is computed over, in the format
+/// 'over (partition by a order by b rows between 1 preceding and current row)'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Window {
+    /// The keys given in the PARTITION BY clause. Rows with equal keys are in the same partition,
+    /// and the whole table is a single partition when there are none.
+    pub partition_by: Vec<Expression>,
+    /// The sort keys given in the ORDER BY clause, ordering the rows of each partition.
+    pub order_by: Vec<OrderBy>,
+    /// The frame given by the ROWS clause, if any. The default frame of an ordered window goes
+    /// from the start of the partition to the last row with the same sort keys as the current row,
+    /// while the frame of a window without ORDER BY is the whole partition.
+    pub frame: Option<WindowFrame>,
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut clauses = Vec::<String>::new();
+        if !self.partition_by.is_empty() {
+            let keys: Vec<String> = self
+                .partition_by
+                .iter()
+                .map(|key| key.to_string())
+                .collect();
+            clauses.push(format!("PARTITION BY {}", keys.join(", ")));
+        }
+        if !self.order_by.is_empty() {
+            let keys: Vec<String> = self.order_by.iter().map(|key| key.to_string()).collect();
+            clauses.push(format!("ORDER BY {}", keys.join(", ")));
+        }
+        if let Some(frame) = &self.frame {
+            clauses.push(frame.to_string());
+        }
+        return write!(f, "OVER ({})", clauses.join(" "));
+    }
+}
+
+/// A frame of rows around the current row, in the format 'rows between start and end'.
+/// The shorthand 'rows start' ends at the current row.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WindowFrame {
+    /// The first row of the frame, which cannot be `UNBOUNDED FOLLOWING`.
+    pub start: FrameBound,
+    /// The last row of the frame, which cannot be `UNBOUNDED PRECEDING`.
+    pub end: FrameBound,
+}
+
+impl fmt::Display for WindowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "ROWS BETWEEN {} AND {}", self.start, self.end);
+    }
+}
+
+/// A bound of a [WindowFrame], relative to the current row of its partition.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FrameBound {
+    /// `UNBOUNDED PRECEDING`, the first row of the partition.
+    UnboundedPreceding,
+    /// `n PRECEDING`, the row `n` rows before the current row.
+    Preceding(usize),
+    /// `CURRENT ROW`.
+    CurrentRow,
+    /// `n FOLLOWING`, the row `n` rows after the current row.
+    Following(usize),
+    /// `UNBOUNDED FOLLOWING`, the last row of the partition.
+    UnboundedFollowing,
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameBound::UnboundedPreceding => return write!(f, "UNBOUNDED PRECEDING"),
+            FrameBound::Preceding(rows) => return write!(f, "{} PRECEDING", rows),
+            FrameBound::CurrentRow => return write!(f, "CURRENT ROW"),
+            FrameBound::Following(rows) => return write!(f, "{} FOLLOWING", rows),
+            FrameBound::UnboundedFollowing => return write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+/// The arithmetic operators supported in expressions.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArithmeticOperator {
+    /// `+`
+    Add,
+    /// `-`
+    Subtract,
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `%`
+    Modulo,
+}
+
+impl fmt::Display for ArithmeticOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Subtract => "-",
+            ArithmeticOperator::Multiply => "*",
+            ArithmeticOperator::Divide => "/",
+            ArithmeticOperator::Modulo => "%",
+        };
+        return write!(f, "{}", symbol);
+    }
+}
+
+impl fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ComparisonOperator::Equal => "=",
+            ComparisonOperator::NotEqual => "<>",
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::LessThanOrEqual => "<=",
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::GreaterThanOrEqual => ">=",
+        };
+        return write!(f, "{}", symbol);
+    }
+}
+
+/// A parameter of a prepared statement, numbered from 1 by the position of its value when bound.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Parameter {
+    /// A parameter given by its number, e.g. `$1`.
+    Numbered(usize),
+    /// A parameter given by `?`. The parser numbers the `?` parameters in the order they are written,
+    /// and until then holds the byte offset where it is written.
+    Positional(usize),
+}
+
+impl Parameter {
+    /// Returns the number of this parameter.
+    pub fn number(&self) -> usize {
+        match self {
+            Parameter::Numbered(number) | Parameter::Positional(number) => return *number,
+        }
+    }
+}
+
+/// An expression tree, as found in the SELECT or WHERE clauses.
+/// Leaves are either column references or literal values.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression {
+    /// Reference to a column by its name, which may be qualified by a table, e.g. `a.x`.
+    Column(String),
+    /// A literal value, such as `1`, `2.5` or `'John'`.
+    Literal(DataType),
+    /// A parameter of a prepared statement, given by `?` or `$1`, whose value is bound when executed.
+    Parameter(Parameter),
+    /// Compares two expressions, e.g. `annual_salary > 1000`.
+    Comparison(Box<Expression>, ComparisonOperator, Box<Expression>),
+    /// Applies an arithmetic operator to two expressions, e.g. `price * quantity`.
+    Arithmetic(Box<Expression>, ArithmeticOperator, Box<Expression>),
+    /// Arithmetic negation of an expression, e.g. `-salary`.
+    Negate(Box<Expression>),
+    /// Logical conjunction of two expressions.
+    And(Box<Expression>, Box<Expression>),
+    /// Logical disjunction of two expressions.
+    Or(Box<Expression>, Box<Expression>),
+    /// Logical negation of an expression.
+    Not(Box<Expression>),
+    /// Whether an expression is NULL, e.g. `b.id IS NULL`, negated for `IS NOT NULL`.
+    IsNull(Box<Expression>, bool),
+    /// A conditional value, e.g. `CASE WHEN salary > 100 THEN 'high' ELSE 'low' END`.
+    /// Holds the operand of a simple CASE, e.g. `CASE dept WHEN 'eng' THEN 1 END`, the WHEN and THEN pairs,
+    /// and the ELSE value. A searched CASE, without operand, has conditions in its WHEN clauses,
+    /// while a simple CASE compares its operand with their values.
+    /// The value of the first matching branch is chosen, or else the ELSE value, which defaults to NULL.
+    Case(
+        Option<Box<Expression>>,
+        Vec<(Expression, Expression)>,
+        Option<Box<Expression>>,
+    ),
+    /// A call to a scalar function, e.g. `UPPER(name)`, whose name is kept in upper case.
+    /// Functions are resolved when executed, so the name may be unknown.
+    Function(String, Vec<Expression>),
+    /// An aggregate over the rows of a group, e.g. `AVG(salary)`.
+    /// The argument is `None` for `COUNT(*)`. The flag tells whether duplicate values are
+    /// aggregated only once, as in `COUNT(DISTINCT dept)`.
+    Aggregate(AggregateFunction, Option<Box<Expression>>, bool),
+    /// A window function with its arguments, computed for each row over the rows of its window,
+    /// e.g. `RANK() OVER (PARTITION BY dept ORDER BY salary DESC)`. `COUNT(*)` has no arguments.
+    WindowFunction(WindowFunction, Vec<Expression>, Box<Window>),
+    /// A scalar subquery, e.g. `(SELECT MAX(salary) FROM employees)`,
+    /// whose value is its only column of its only row, or NULL when it returns no rows.
+    Subquery(Box<SelectStatement>),
+    /// Whether a subquery returns any row, e.g. `EXISTS (SELECT id FROM employees)`.
+    Exists(Box<SelectStatement>),
+    /// Whether a value is returned by a subquery, e.g. `id IN (SELECT id FROM employees)`,
+    /// negated for `NOT IN`.
+    InSubquery(Box<Expression>, Box<SelectStatement>, bool),
+}
+
+impl Expression {
+    /// Returns the names of the columns referenced by this expression, without duplicates,
+    /// in the order they first appear.
+    pub fn referenced_columns(&self) -> Vec<String> {
+        let mut columns = Vec::<String>::new();
+        self.collect_columns(&mut columns);
+        return columns;
+    }
+
+    fn collect_columns(&self, columns: &mut Vec<String>) {
+        match self {
+            Expression::Column(name) => {
+                if !columns.contains(name) {
+                    columns.push(name.clone());
+                }
+            }
+            Expression::Literal(_) | Expression::Parameter(_) => {}
+            Expression::Comparison(left, _, right)
+            | Expression::Arithmetic(left, _, right)
+            | Expression::And(left, right)
+            | Expression::Or(left, right) => {
+                left.collect_columns(columns);
+                right.collect_columns(columns);
+            }
+            Expression::Not(inner) | Expression::Negate(inner) | Expression::IsNull(inner, _) => {
+                inner.collect_columns(columns)
+            }
+            Expression::Aggregate(_, argument, _) => {
+                if let Some(argument) = argument {
+                    argument.collect_columns(columns);
+                }
+            }
+            Expression::Function(_, _)
+            | Expression::Case(_, _, _)
+            | Expression::WindowFunction(_, _, _) => {
+                for child in self.children() {
+                    child.collect_columns(columns);
+                }
+            }
+            // the columns of a subquery may refer to the enclosing query
+            Expression::Subquery(statement) | Expression::Exists(statement) => {
+                for column in statement.referenced_columns() {
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+            }
+            Expression::InSubquery(value, statement, _) => {
+                value.collect_columns(columns);
+                for column in statement.referenced_columns() {
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the direct children of this expression.
+    /// The expressions of a subquery belong to another query, so they are not children.
+    pub fn children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Column(_)
+            | Expression::Literal(_)
+            | Expression::Parameter(_)
+            | Expression::Subquery(_)
+            | Expression::Exists(_) => return vec![],
+            Expression::InSubquery(value, _, _) => return vec![value],
+            Expression::Comparison(left, _, right)
+            | Expression::Arithmetic(left, _, right)
+            | Expression::And(left, right)
+            | Expression::Or(left, right) => return vec![left, right],
+            Expression::Not(inner) | Expression::Negate(inner) | Expression::IsNull(inner, _) => {
+                return vec![inner]
+            }
+            Expression::Aggregate(_, argument, _) => {
+                return argument.iter().map(|argument| argument.as_ref()).collect()
+            }
+            Expression::Function(_, arguments) => return arguments.iter().collect(),
+            Expression::WindowFunction(_, arguments, window) => {
+                let mut children: Vec<&Expression> = arguments.iter().collect();
+                children.extend(window.partition_by.iter());
+                children.extend(window.order_by.iter().map(|order_by| &order_by.expression));
+                return children;
+            }
+            Expression::Case(operand, branches, default) => {
+                let mut children: Vec<&Expression> =
+                    operand.iter().map(|operand| operand.as_ref()).collect();
+                for (condition, value) in branches.iter() {
+                    children.push(condition);
+                    children.push(value);
+                }
+                children.extend(default.iter().map(|default| default.as_ref()));
+                return children;
+            }
+        }
+    }
+
+    /// Rebuilds this expression, replacing each direct child with the result of `map`.
+    /// As in [Expression::children], the expressions of a subquery are kept as they are.
+    pub fn map_children<E>(
+        &self,
+        mut map: impl FnMut(&Expression) -> Result<Expression, E>,
+    ) -> Result<Expression, E> {
+        let mut map_box = |child: &Expression| -> Result<Box<Expression>, E> {
+            return Ok(Box::new(map(child)?));
+        };
+        match self {
+            Expression::Column(_)
+            | Expression::Literal(_)
+            | Expression::Parameter(_)
+            | Expression::Subquery(_)
+            | Expression::Exists(_) => return Ok(self.clone()),
+            Expression::Comparison(left, operator, right) => {
+                let left = map_box(left)?;
+                return Ok(Expression::Comparison(left, *operator, map_box(right)?));
+            }
+            Expression::Arithmetic(left, operator, right) => {
+                let left = map_box(left)?;
+                return Ok(Expression::Arithmetic(left, *operator, map_box(right)?));
+            }
+            Expression::Negate(inner) => return Ok(Expression::Negate(map_box(inner)?)),
+            Expression::And(left, right) => {
+                let left = map_box(left)?;
+                return Ok(Expression::And(left, map_box(right)?));
+            }
+            Expression::Or(left, right) => {
+                let left = map_box(left)?;
+                return Ok(Expression::Or(left, map_box(right)?));
+            }
+            Expression::Not(inner) => return Ok(Expression::Not(map_box(inner)?)),
+            Expression::IsNull(inner, negated) => {
+                return Ok(Expression::IsNull(map_box(inner)?, *negated));
+            }
+            Expression::Aggregate(function, argument, distinct) => {
+                let argument = match argument {
+                    Some(argument) => Some(map_box(argument)?),
+                    None => None,
+                };
+                return Ok(Expression::Aggregate(*function, argument, *distinct));
+            }
+            Expression::Function(name, arguments) => {
+                let mut mapped = Vec::<Expression>::new();
+                for argument in arguments.iter() {
+                    mapped.push(map(argument)?);
+                }
+                return Ok(Expression::Function(name.clone(), mapped));
+            }
+            Expression::WindowFunction(function, arguments, window) => {
+                let mut mapped = Vec::<Expression>::new();
+                for argument in arguments.iter() {
+                    mapped.push(map(argument)?);
+                }
+                let mut partition_by = Vec::<Expression>::new();
+                for key in window.partition_by.iter() {
+                    partition_by.push(map(key)?);
+                }
+                let mut order_by = Vec::<OrderBy>::new();
+                for key in window.order_by.iter() {
+                    order_by.push(OrderBy {
+                        expression: map(&key.expression)?,
+                        ..key.clone()
+                    });
+                }
+                let window = Window {
+                    partition_by,
+                    order_by,
+                    frame: window.frame,
+                };
+                return Ok(Expression::WindowFunction(
+                    *function,
+                    mapped,
+                    Box::new(window),
+                ));
+            }
+            Expression::Case(operand, branches, default) => {
+                let operand = match operand {
+                    Some(operand) => Some(map_box(operand)?),
+                    None => None,
+                };
+                let mut mapped = Vec::<(Expression, Expression)>::new();
+                for (condition, value) in branches.iter() {
+                    let condition = map_box(condition)?;
+                    mapped.push((*condition, *map_box(value)?));
+                }
+                let default = match default {
+                    Some(default) => Some(map_box(default)?),
+                    None => None,
+                };
+                return Ok(Expression::Case(operand, mapped, default));
+            }
+            Expression::InSubquery(value, statement, negated) => {
+                return Ok(Expression::InSubquery(
+                    map_box(value)?,
+                    statement.clone(),
+                    *negated,
+                ));
+            }
+        }
+    }
+
+    /// Rebuilds this expression, replacing each parameter with the result of `map`.
+    /// Unlike [Expression::map_children], this also replaces the parameters of subqueries.
+    pub fn map_parameters<E>(
+        &self,
+        map: &mut impl FnMut(Parameter) -> Result<Expression, E>,
+    ) -> Result<Expression, E> {
+        match self {
+            Expression::Parameter(parameter) => return map(*parameter),
+            Expression::Subquery(statement) => {
+                return Ok(Expression::Subquery(Box::new(
+                    statement.map_parameters(map)?,
+                )));
+            }
+            Expression::Exists(statement) => {
+                return Ok(Expression::Exists(Box::new(statement.map_parameters(map)?)));
+            }
+            Expression::InSubquery(value, statement, negated) => {
+                let value = value.map_parameters(map)?;
+                return Ok(Expression::InSubquery(
+                    Box::new(value),
+                    Box::new(statement.map_parameters(map)?),
+                    *negated,
+                ));
+            }
+            _ => return self.map_children(|child| child.map_parameters(map)),
+        }
+    }
+
+    /// Whether this expression contains a subquery.
+    pub fn contains_subquery(&self) -> bool {
+        if self.is_subquery() {
+            return true;
+        }
+        return self
+            .children()
+            .iter()
+            .any(|child| child.contains_subquery());
+    }
+
+    /// Whether this expression is evaluated by running a subquery.
+    pub fn is_subquery(&self) -> bool {
+        return matches!(
+            self,
+            Expression::Subquery(_) | Expression::Exists(_) | Expression::InSubquery(_, _, _)
+        );
+    }
+
+    /// Returns the outermost subqueries found in this expression, without duplicates,
+    /// in the order they first appear.
+    pub fn subqueries(&self) -> Vec<&Expression> {
+        if self.is_subquery() {
+            return vec![self];
+        }
+        let mut subqueries = Vec::<&Expression>::new();
+        for child in self.children() {
+            for subquery in child.subqueries() {
+                if !subqueries.contains(&subquery) {
+                    subqueries.push(subquery);
+                }
+            }
+        }
+        return subqueries;
+    }
+
+    /// Whether this expression contains an aggregate.
+    pub fn contains_aggregate(&self) -> bool {
+        if let Expression::Aggregate(_, _, _) = self {
+            return true;
+        }
+        return self
+            .children()
+            .iter()
+            .any(|child| child.contains_aggregate());
+    }
+
+    /// Returns the outermost aggregates found in this expression, without duplicates,
+    /// in the order they first appear.
+    pub fn aggregates(&self) -> Vec<&Expression> {
+        let mut aggregates = Vec::<&Expression>::new();
+        self.collect_aggregates(&mut aggregates);
+        return aggregates;
+    }
+
+    fn collect_aggregates<'a>(&'a self, aggregates: &mut Vec<&'a Expression>) {
+        if let Expression::Aggregate(_, _, _) = self {
+            if !aggregates.contains(&self) {
+                aggregates.push(self);
+            }
+            return;
+        }
+        for child in self.children() {
+            child.collect_aggregates(aggregates);
+        }
+    }
+
+    /// Whether this expression contains a window function.
+    pub fn contains_window_function(&self) -> bool {
+        if let Expression::WindowFunction(_, _, _) = self {
+            return true;
+        }
+        return self
+            .children()
+            .iter()
+            .any(|child| child.contains_window_function());
+    }
+
+    /// Returns the outermost window functions found in this expression, without duplicates,
+    /// in the order they first appear.
+    pub fn window_functions(&self) -> Vec<&Expression> {
+        if let Expression::WindowFunction(_, _, _) = self {
+            return vec![self];
+        }
+        let mut windows = Vec::<&Expression>::new();
+        for child in self.children() {
+            for window in child.window_functions() {
+                if !windows.contains(&window) {
+                    windows.push(window);
+                }
+            }
+        }
+        return windows;
+    }
+
+    /// Returns the first column that is neither part of a grouping key nor inside an aggregate,
+    /// which makes the expression invalid once the rows are grouped.
+    pub fn ungrouped_column(&self, group_by: &[Expression]) -> Option<String> {
+        if group_by.contains(self) {
+            return None;
+        }
+        match self {
+            Expression::Column(name) => return Some(name.clone()),
+            Expression::Aggregate(_, _, _) => return None,
+            _ => {
+                return self
+                    .children()
+                    .iter()
+                    .find_map(|child| child.ungrouped_column(group_by))
+            }
+        }
+    }
+}
+
+/// Formats the expression back into SQL, which also names the result columns it produces.
+/// Operands are wrapped in parentheses when their precedence or associativity requires it.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Column(name) => return write!(f, "{}", name),
+            Expression::Literal(DataType::String(s)) => {
+                return write!(f, "'{}'", s.replace('\'', "''"))
+            }
+            Expression::Literal(DataType::Integer32(i)) => return write!(f, "{}", i),
+            Expression::Literal(DataType::Float32(x)) => return write!(f, "{:?}", x),
+            Expression::Literal(DataType::Null) => return write!(f, "NULL"),
+            Expression::Parameter(parameter) => return write!(f, "${}", parameter.number()),
+            Expression::Comparison(left, operator, right) => {
+                write_operand(f, left, self.precedence())?;
+                write!(f, " {} ", operator)?;
+                return write_operand(f, right, self.precedence());
+            }
+            // arithmetic is left associative, so only the left operand may have the same precedence unwrapped
+            Expression::Arithmetic(left, operator, right) => {
+                write_operand(f, left, self.precedence() - 1)?;
+                write!(f, " {} ", operator)?;
+                return write_operand(f, right, self.precedence());
+            }
+            Expression::Negate(inner) => {
+                write!(f, "-")?;
+                return write_operand(f, inner, self.precedence());
+            }
+            Expression::And(left, right) | Expression::Or(left, right) => {
+                let keyword = if let Expression::And(_, _) = self {
+                    "AND"
+                } else {
+                    "OR"
+                };
+                write_operand(f, left, self.precedence())?;
+                write!(f, " {} ", keyword)?;
+                return write_operand(f, right, self.precedence());
+            }
+            Expression::Not(inner) => {
+                write!(f, "NOT ")?;
+                return write_operand(f, inner, self.precedence());
+            }
+            Expression::IsNull(inner, negated) => {
+                write_operand(f, inner, self.precedence())?;
+                let keyword = if *negated { "IS NOT NULL" } else { "IS NULL" };
+                return write!(f, " {}", keyword);
+            }
+            Expression::Function(name, arguments) => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect();
+                return write!(f, "{}({})", name, arguments.join(", "));
+            }
+            Expression::Case(operand, branches, default) => {
+                write!(f, "CASE ")?;
+                if let Some(operand) = operand {
+                    write!(f, "{} ", operand)?;
+                }
+                for (condition, value) in branches.iter() {
+                    write!(f, "WHEN {} THEN {} ", condition, value)?;
+                }
+                if let Some(default) = default {
+                    write!(f, "ELSE {} ", default)?;
+                }
+                return write!(f, "END");
+            }
+            Expression::Aggregate(function, None, _) => return write!(f, "{}(*)", function),
+            Expression::Aggregate(function, Some(argument), false) => {
+                return write!(f, "{}({})", function, argument)
+            }
+            Expression::Aggregate(function, Some(argument), true) => {
+                return write!(f, "{}(DISTINCT {})", function, argument)
+            }
+            Expression::WindowFunction(WindowFunction::Aggregate(function), arguments, window)
+                if arguments.is_empty() =>
+            {
+                return write!(f, "{}(*) {}", function, window)
+            }
+            Expression::WindowFunction(function, arguments, window) => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect();
+                return write!(f, "{}({}) {}", function, arguments.join(", "), window);
+            }
+            Expression::Subquery(statement) => return write!(f, "({})", statement),
+            Expression::Exists(statement) => return write!(f, "EXISTS ({})", statement),
+            Expression::InSubquery(value, statement, negated) => {
+                write_operand(f, value, self.precedence())?;
+                let keyword = if *negated { "NOT IN" } else { "IN" };
+                return write!(f, " {} ({})", keyword, statement);
+            }
+        }
+    }
+}
+
+impl Expression {
+    /// The binding strength of the expression, matching the grammar.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Or(_, _) => 1,
+            Expression::And(_, _) => 2,
+            Expression::Not(_) => 3,
+            Expression::Comparison(_, _, _)
+            | Expression::IsNull(_, _)
+            | Expression::InSubquery(_, _, _) => 4,
+            Expression::Arithmetic(
+                _,
+                ArithmeticOperator::Add | ArithmeticOperator::Subtract,
+                _,
+            ) => 5,
+            Expression::Arithmetic(_, _, _) => 6,
+            Expression::Negate(_) => 7,
+            _ => 8,
+        }
+    }
+}
+
+/// Writes an operand, wrapped in parentheses when it binds no stronger than its parent.
+fn write_operand(f: &mut fmt::Formatter<'_>, operand: &Expression, parent: u8) -> fmt::Result {
+    if operand.precedence() <= parent && operand.precedence() < 8 {
+        return write!(f, "({})", operand);
+    }
+    return write!(f, "{}", operand);
+}
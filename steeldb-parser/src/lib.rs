@@ -1,104 +1,309 @@
 #![warn(missing_docs)]
 //! # SteelDB Parser
 //! This crate exposes functions that parse a subset of SQL, used by the SteelDB project.
-//! 
-//! 
+//!
+//!
 //! You can find more information about the Database here: <https://github.com/paolorechia/steeldb>
-//! 
-//! 
-//! Since this is still work in progress, not much is implemented.
-//! 
-//! 
-//! Currently, the only exposed function is [parse_select], which takes an input string and returns the 
-//! columns that were given in the SELECT clause.
-//! 
-//! 
+//!
+//!
+//! The subset covers queries (SELECT, with joins, subqueries and WITH clauses), the definition of tables
+//! (CREATE, DROP, TRUNCATE and ALTER TABLE), changes to their rows (INSERT, UPDATE and DELETE) and EXPLAIN.
+//! Statements may hold parameters, given by `?` or `$1`.
+//!
+//!
+//! The main exposed function is [parse_statement], which takes an input string and returns a [Statement].
+//! A script of several statements is parsed by [parse_script].
+//! For instance, [parse_select] returns a [SelectStatement] with the items that were given in the SELECT clause,
+//! the table in the FROM clause and the predicate of the WHERE clause, represented as an [Expression] tree.
+//!
+//!
 //! This crate relies on lalrpop library: <https://github.com/lalrpop/lalrpop>
-//! 
-//! 
+//!
+//!
 //! # Examples
 //! Good examples of this crate usage are found in the unit tests in lib.rs
 //! For instance:
-//! 
+//!
 //! ```rust
 //!     #[test]
 //!     fn test_parse_select() {
-//!         let result = parse_select("select brigadeiro, churros;".to_string()).unwrap();
+//!         let result = parse_select("select brigadeiro, churros from sweets;".to_string()).unwrap();
 //!         let v = vec!["brigadeiro".to_string(), "churros".to_string()];
-//!         assert_eq!(v, result);
+//!         assert_eq!(v, result.column_names());
+//!         assert_eq!(Some(TableReference::Table("sweets".to_string(), None)), result.table);
 //!     }
 //! ````
 //!
 //! # Grammar Files  
 //! Note that `lalrpop` reads a file of the format `.lalrpop` where the parser grammar is defined,
 //! and generated during compilation-time the actual parser code, which is not displayed in the source code repository.
-//! 
-//! Here's the first implementation of the select clause:
-//! 
+//!
+//! The grammar is defined in `src/sql.lalrpop`. For instance, here is the rule of a statement,
+//! which ends with `;` and may be explained:
+//!
 //! ```txt
-//! grammar(v: &mut Vec<String>);
-//! 
-//! pub Select: () = {
-//!     SELECT <c:Columns> SEMICOLON => {}
+//! pub Statement: Statement = {
+//!     <StatementBody> ";",
+//!     "explain" <StatementBody> ";" => Statement::Explain(Box::new(<>)),
+//! };
+//!
+//! StatementBody: Statement = {
+//!     <SelectBody> => Statement::Select(Box::new(<>)),
+//!     <CreateTableBody> => Statement::CreateTable(<>),
+//!     <InsertBody> => Statement::Insert(<>),
+//!     <DropTableBody> => Statement::DropTable(<>),
+//!     <TruncateTableBody> => Statement::TruncateTable(<>),
+//!     <AlterTableBody> => Statement::AlterTable(<>),
+//!     <UpdateBody> => Statement::Update(<>),
+//!     <DeleteBody> => Statement::Delete(<>),
 //! };
-//! 
-//! Columns: () = {
-//!     <l:LITERAL> => v.push(l),
-//!     Columns "," <l:LITERAL> => {
-//!         v.push(l);
-//!     }
-//! }
-//! 
-//! SELECT: String = <s:r"select "> => s.to_string();
-//! LITERAL: String = <s:r"[a-z\*_0-9]+"> => s.to_string();
-//! SEMICOLON: String = <s:r";"> => s.to_string();
 //! ```
 
+// the example above shows a unit test, which is not meant to run as a doctest
+#![allow(clippy::test_attr_in_doctest)]
+
 use lalrpop_util::lalrpop_mod;
+use lalrpop_util::lexer::Token;
+
+pub mod ast;
+
+pub use ast::{
+    AggregateFunction, AlterTableAction, AlterTableStatement, ArithmeticOperator, ColumnDefinition,
+    CommonTableExpression, ComparisonOperator, CreateTableStatement, DeleteStatement,
+    DropTableStatement, Expression, FrameBound, InsertStatement, JoinKind, OrderBy, Parameter,
+    SelectItem, SelectStatement, SetOperation, SetOperator, Statement, TableReference,
+    TruncateTableStatement, UpdateStatement, Window, WindowFrame, WindowFunction, With,
+};
+/// The parse error is defined in [steeldb_core], so that it can be carried by its `ExecutionResult`.
+pub use steeldb_core::{ParseError, ParseErrorKind};
+
+lalrpop_mod!(
+    #[allow(clippy::all)]
+    sql
+); // synthesized by LALRPOP
+
+/// The error type produced by the lalrpop generated parsers.
+type LalrpopError<'input> = lalrpop_util::ParseError<usize, Token<'input>, (usize, String)>;
+
+/// Maps a lalrpop error into a [ParseError], locating it in the input.
+fn convert_error(input: &str, error: LalrpopError) -> ParseError {
+    match error {
+        LalrpopError::InvalidToken { location } => {
+            let token = input[location..].chars().next().map(|c| c.to_string());
+            return ParseError::new(ParseErrorKind::InvalidToken, input, location, token, vec![]);
+        }
+        LalrpopError::UnrecognizedEof { location, expected } => {
+            return ParseError::new(
+                ParseErrorKind::UnexpectedEndOfInput,
+                input,
+                location,
+                None,
+                expected,
+            );
+        }
+        LalrpopError::UnrecognizedToken {
+            token: (start, token, _),
+            expected,
+        } => {
+            return ParseError::new(
+                ParseErrorKind::UnrecognizedToken,
+                input,
+                start,
+                Some(token.1.to_string()),
+                expected,
+            );
+        }
+        LalrpopError::ExtraToken {
+            token: (start, token, _),
+        } => {
+            return ParseError::new(
+                ParseErrorKind::ExtraToken,
+                input,
+                start,
+                Some(token.1.to_string()),
+                vec![],
+            );
+        }
+        LalrpopError::User {
+            error: (location, message),
+        } => {
+            return ParseError::new(
+                ParseErrorKind::InvalidValue(message),
+                input,
+                location,
+                None,
+                vec![],
+            );
+        }
+    }
+}
+
+/// Parses any supported statement, which must end with a `;`.
+///
+/// Example:
+/// ```rust
+/// use steeldb_parser::{parse_statement, Statement};
+///
+/// let result = parse_statement("create table sweets (name text, price float);".to_string()).unwrap();
+/// match result {
+///     Statement::CreateTable(create) => assert_eq!(create.table, "sweets"),
+///     _ => panic!("Expected a CREATE TABLE statement"),
+/// }
+/// ```
+pub fn parse_statement(input: String) -> Result<Statement, ParseError> {
+    let parser = sql::StatementParser::new();
+    return parse_text(&parser, &input);
+}
+
+/// Parses a single statement with the given parser, numbering its parameters, see [number_parameters].
+fn parse_text(parser: &sql::StatementParser, input: &str) -> Result<Statement, ParseError> {
+    match parser.parse(input) {
+        Ok(statement) => {
+            return number_parameters(input, statement);
+        }
+        Err(error) => {
+            return Err(convert_error(input, error));
+        }
+    }
+}
 
-lalrpop_mod!(select); // synthesized by LALRPOP
+/// Numbers the `?` parameters of a parsed statement in the order they are written, starting at 1,
+/// since the parser gives each one the offset where it is written. Numbered parameters keep their number.
+/// A statement cannot mix `?` with numbered parameters such as `$1`, which is reported at the first `?`.
+fn number_parameters(input: &str, statement: Statement) -> Result<Statement, ParseError> {
+    let mut offsets = Vec::<usize>::new();
+    let mut is_numbered = false;
+    let _ = statement.map_parameters(&mut |parameter| -> Result<Expression, ()> {
+        match parameter {
+            Parameter::Numbered(_) => is_numbered = true,
+            Parameter::Positional(offset) => offsets.push(offset),
+        }
+        return Ok(Expression::Parameter(parameter));
+    });
+    if offsets.is_empty() {
+        return Ok(statement);
+    }
+    // a parameter is visited more than once when an ORDER BY alias repeats its expression
+    offsets.sort();
+    offsets.dedup();
+    if is_numbered {
+        let message = "Cannot mix ? with numbered parameters".to_string();
+        let kind = ParseErrorKind::InvalidValue(message);
+        return Err(ParseError::new(kind, input, offsets[0], None, vec![]));
+    }
+    return statement.map_parameters(&mut |parameter| -> Result<Expression, ParseError> {
+        match parameter {
+            Parameter::Positional(offset) => {
+                // every '?' was collected, so it is always found
+                let index = offsets.binary_search(&offset).unwrap();
+                return Ok(Expression::Parameter(Parameter::Positional(index + 1)));
+            }
+            Parameter::Numbered(_) => return Ok(Expression::Parameter(parameter)),
+        }
+    });
+}
+
+/// Splits a script into its statements, each ending with its `;`, with the byte offset where it starts.
+/// A `;` inside a string literal does not end a statement.
+/// Empty statements, such as the one between `;;`, are skipped.
+/// The text after the last `;` is kept as a final statement, unless it is blank.
+///
+/// Example:
+/// ```rust
+/// use steeldb_parser::split_script;
+///
+/// let statements = split_script("select ';' from t;; select b from u;");
+/// assert_eq!(statements, vec![(0, "select ';' from t;"), (19, " select b from u;")]);
+/// ```
+pub fn split_script(input: &str) -> Vec<(usize, &str)> {
+    let mut statements = Vec::<(usize, &str)>::new();
+    let mut start = 0;
+    let mut in_string = false;
+    for (index, character) in input.char_indices() {
+        match character {
+            // an escaped quote toggles twice, so it stays inside the string
+            '\'' => in_string = !in_string,
+            ';' if !in_string => {
+                if !input[start..index].trim().is_empty() {
+                    statements.push((start, &input[start..=index]));
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if !input[start..].trim().is_empty() {
+        statements.push((start, &input[start..]));
+    }
+    return statements;
+}
 
-/// Enum used for propagating the parse error.
-/// At the moment it only contains one generic Error.
-/// Internally, this library just forwards the lalrpop error as a formatted string:
+/// Parses a script of one or more statements, each ending with a `;`, see [split_script].
+/// Every statement is parsed before any is returned, so an invalid script gives no statements.
+/// The error is returned with the index of the invalid statement, starting at 0,
+/// and locates the problem in the whole script.
+///
+/// Example:
 /// ```rust
-/// Err(error) => {
-///     let error = format!("{:?}", error);
-///     return Err(ParseError::Error(format!(
-///         "Failed to parse, error: {}",
-///         error
-///     )));
-/// } 
+/// use steeldb_parser::parse_script;
+///
+/// let statements = parse_script("create table t (a int); select a from t;".to_string()).unwrap();
+/// assert_eq!(statements.len(), 2);
+/// let (index, error) = parse_script("select a from t; select from t;".to_string()).unwrap_err();
+/// assert_eq!((index, error.column), (1, 25));
 /// ```
-#[derive(Debug)]
-pub enum ParseError {
-    /// Generic parser error case.
-    Error(String),
+pub fn parse_script(input: String) -> Result<Vec<Statement>, (usize, ParseError)> {
+    let pieces = split_script(&input);
+    if pieces.is_empty() {
+        // a blank script is reported as a statement ending too early
+        match parse_statement(input) {
+            Ok(statement) => return Ok(vec![statement]),
+            Err(error) => return Err((0, error)),
+        }
+    }
+    let parser = sql::StatementParser::new();
+    let mut statements = Vec::<Statement>::new();
+    for (index, (start, text)) in pieces.into_iter().enumerate() {
+        match parse_text(&parser, text) {
+            Ok(statement) => statements.push(statement),
+            Err(error) => {
+                let error = ParseError::new(
+                    error.kind,
+                    &input,
+                    start + error.offset,
+                    error.token,
+                    error.expected,
+                );
+                return Err((index, error));
+            }
+        }
+    }
+    return Ok(statements);
 }
 
-/// Parses a select clause in the format 'select col1, col2;'.
-/// 
+/// Parses a select clause in the format 'select col1, col2 from table where predicate;'.
+/// The FROM and WHERE clauses are optional, in which case the respective fields are `None`.
+/// The same goes for the GROUP BY, HAVING, ORDER BY, LIMIT and OFFSET clauses, where lists are left empty.
+///
 /// Example:
 /// ```rust
-/// let result = parse_select("select brigadeiro, churros;".to_string()).unwrap();
+/// use steeldb_parser::{parse_select, TableReference};
+///
+/// let result = parse_select("select brigadeiro, churros from sweets;".to_string()).unwrap();
 /// let v = vec!["brigadeiro".to_string(), "churros".to_string()];
-/// assert_eq!(v, result);
+/// assert_eq!(v, result.column_names());
+/// assert_eq!(Some(TableReference::Table("sweets".to_string(), None)), result.table);
 /// ```
-/// Notice that this function does not yet support the FROM clause.
-pub fn parse_select(input: String) -> Result<Vec<String>, ParseError> {
-    let mut result: Vec<String> = vec![];
-    let parser = select::SelectParser::new();
-    let maybe_error = parser.parse(&mut result, input.as_str());
+pub fn parse_select(input: String) -> Result<SelectStatement, ParseError> {
+    let parser = sql::SelectParser::new();
+    let maybe_error = parser.parse(input.as_str());
     match maybe_error {
-        Ok(_) => {
-            return Ok(result);
-        }
+        Ok(statement) => match number_parameters(&input, Statement::Select(Box::new(statement)))? {
+            Statement::Select(statement) => return Ok(*statement),
+            _ => unreachable!("numbering the parameters keeps the kind of statement"),
+        },
         Err(error) => {
-            let error = format!("{:?}", error);
-            return Err(ParseError::Error(format!(
-                "Failed to parse, error: {}",
-                error
-            )));
+            return Err(convert_error(input.as_str(), error));
         }
     };
 }
@@ -106,39 +311,1401 @@ pub fn parse_select(input: String) -> Result<Vec<String>, ParseError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use steeldb_core::DataType;
 
     #[test]
     fn test_select_parser_single_column() {
-        let mut result: Vec<String> = vec![];
-        let parser = select::SelectParser::new();
-        assert!(parser.parse(&mut result, "select churros;").is_ok());
+        let parser = sql::SelectParser::new();
+        let result = parser.parse("select churros;").unwrap();
         let v = vec!["churros".to_string()];
-        assert_eq!(v, result);
+        assert_eq!(v, result.column_names());
+        assert_eq!(None, result.table);
     }
 
     #[test]
     fn test_select_parser_multiple_columns() {
-        let mut result: Vec<String> = vec![];
-        let parser = select::SelectParser::new();
-        parser
-            .parse(&mut result, "select brigadeiro, churros;")
-            .unwrap();
+        let parser = sql::SelectParser::new();
+        let result = parser.parse("select brigadeiro, churros;").unwrap();
         let v = vec!["brigadeiro".to_string(), "churros".to_string()];
-        assert_eq!(v, result);
+        assert_eq!(v, result.column_names());
     }
 
     #[test]
     fn test_select_support_star() {
-        let mut result: Vec<String> = vec![];
-        let parser = select::SelectParser::new();
-        assert!(parser.parse(&mut result, "select *;").is_ok());
-        assert_eq!(result, vec!["*".to_string()]);
+        let parser = sql::SelectParser::new();
+        let result = parser.parse("select *;").unwrap();
+        assert_eq!(result.columns, vec![SelectItem::Wildcard]);
+    }
+
+    #[test]
+    fn test_select_parser_from() {
+        let parser = sql::SelectParser::new();
+        let result = parser.parse("SELECT a, b FROM orders;").unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert_eq!(
+            result.table,
+            Some(TableReference::Table("orders".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn test_select_parser_keyword_prefixed_identifiers() {
+        let parser = sql::SelectParser::new();
+        let result = parser.parse("select selected from fromage;").unwrap();
+        assert_eq!(result.column_names(), vec!["selected".to_string()]);
+        assert_eq!(
+            result.table,
+            Some(TableReference::Table("fromage".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn test_select_parser_missing_table() {
+        let parser = sql::SelectParser::new();
+        assert!(parser.parse("select a from;").is_err());
     }
 
     #[test]
     fn test_parse_select() {
-        let result = parse_select("select brigadeiro, churros;".to_string()).unwrap();
+        let result = parse_select("select brigadeiro, churros from sweets;".to_string()).unwrap();
         let v = vec!["brigadeiro".to_string(), "churros".to_string()];
-        assert_eq!(v, result);
+        assert_eq!(v, result.column_names());
+        assert_eq!(
+            Some(TableReference::Table("sweets".to_string(), None)),
+            result.table
+        );
+    }
+
+    #[test]
+    fn test_select_parser_where_comparison() {
+        let parser = sql::SelectParser::new();
+        let result = parser
+            .parse("select name from employees where annual_salary >= 1000;")
+            .unwrap();
+        let expected = Expression::Comparison(
+            Box::new(Expression::Column("annual_salary".to_string())),
+            ComparisonOperator::GreaterThanOrEqual,
+            Box::new(Expression::Literal(DataType::Integer32(1000))),
+        );
+        assert_eq!(result.filter, Some(expected));
+    }
+
+    #[test]
+    fn test_select_parser_where_precedence() {
+        let parser = sql::SelectParser::new();
+        let result = parser
+            .parse("select a from t where not a = 1 or b <> 'x' and (c < -2.5);")
+            .unwrap();
+        let a_equals_one = Expression::Comparison(
+            Box::new(Expression::Column("a".to_string())),
+            ComparisonOperator::Equal,
+            Box::new(Expression::Literal(DataType::Integer32(1))),
+        );
+        let b_not_x = Expression::Comparison(
+            Box::new(Expression::Column("b".to_string())),
+            ComparisonOperator::NotEqual,
+            Box::new(Expression::Literal(DataType::String("x".to_string()))),
+        );
+        let c_less = Expression::Comparison(
+            Box::new(Expression::Column("c".to_string())),
+            ComparisonOperator::LessThan,
+            Box::new(Expression::Literal(DataType::Float32(-2.5))),
+        );
+        let expected = Expression::Or(
+            Box::new(Expression::Not(Box::new(a_equals_one))),
+            Box::new(Expression::And(Box::new(b_not_x), Box::new(c_less))),
+        );
+        assert_eq!(result.filter, Some(expected));
+    }
+
+    #[test]
+    fn test_select_parser_string_literal_escape() {
+        let parser = sql::SelectParser::new();
+        let result = parser
+            .parse("select a from t where name = 'O''Brien';")
+            .unwrap();
+        let expected = Expression::Comparison(
+            Box::new(Expression::Column("name".to_string())),
+            ComparisonOperator::Equal,
+            Box::new(Expression::Literal(DataType::String("O'Brien".to_string()))),
+        );
+        assert_eq!(result.filter, Some(expected));
+    }
+
+    #[test]
+    fn test_select_parser_integer_out_of_range() {
+        let parser = sql::SelectParser::new();
+        assert!(parser
+            .parse("select a from t where a = 99999999999;")
+            .is_err());
+    }
+
+    #[test]
+    fn test_referenced_columns() {
+        let parser = sql::ExpressionParser::new();
+        let expression = parser.parse("a > 1 and (b = a or not c = 'a')").unwrap();
+        assert_eq!(
+            expression.referenced_columns(),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_error_unrecognized_token() {
+        let error = parse_select("select a from where;".to_string()).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnrecognizedToken);
+        assert_eq!(error.offset, 14);
+        assert_eq!((error.line, error.column), (1, 15));
+        assert_eq!(error.token, Some("where".to_string()));
+        assert_eq!(
+            error.expected,
+            vec!["\"(\"".to_string(), "IDENTIFIER".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_error_multiline() {
+        let error = parse_select("select a\nfrom t\nwhere a = ;".to_string()).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnrecognizedToken);
+        assert_eq!((error.line, error.column), (3, 11));
+        assert_eq!(error.token, Some(";".to_string()));
+        assert!(error.expected.contains(&"INTEGER".to_string()));
+    }
+
+    #[test]
+    fn test_parse_error_end_of_input() {
+        let error = parse_select("select a from t".to_string()).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedEndOfInput);
+        assert_eq!(error.offset, 15);
+        assert_eq!(error.token, None);
+        assert!(error.expected.contains(&"\";\"".to_string()));
+    }
+
+    #[test]
+    fn test_parse_error_invalid_token() {
+        let error = parse_select("select a from t where a = #;".to_string()).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidToken);
+        assert_eq!(error.column, 27);
+        assert_eq!(error.token, Some("#".to_string()));
+    }
+
+    #[test]
+    fn test_parse_error_invalid_value() {
+        let error = parse_select("select a from t where a = 99999999999;".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("Integer out of range: 99999999999".to_string())
+        );
+        assert_eq!(error.column, 27);
+    }
+
+    #[test]
+    fn test_parse_create_table() {
+        let result =
+            parse_statement("CREATE TABLE orders (id INT, price Float, item text);".to_string())
+                .unwrap();
+        let expected = CreateTableStatement {
+            table: "orders".to_string(),
+            columns: vec![
+                ColumnDefinition {
+                    name: "id".to_string(),
+                    data_type: DataType::Integer32(0),
+                },
+                ColumnDefinition {
+                    name: "price".to_string(),
+                    data_type: DataType::Float32(0.0),
+                },
+                ColumnDefinition {
+                    name: "item".to_string(),
+                    data_type: DataType::String(String::new()),
+                },
+            ],
+            if_not_exists: false,
+        };
+        assert_eq!(result, Statement::CreateTable(expected));
+    }
+
+    #[test]
+    fn test_parse_create_table_if_not_exists() {
+        let result =
+            parse_statement("create table if not exists orders (id i32);".to_string()).unwrap();
+        match result {
+            Statement::CreateTable(create) => assert!(create.if_not_exists),
+            _ => panic!("Expected a CREATE TABLE statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_create_table_unknown_type() {
+        let error = parse_statement("create table orders (id bigint);".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("Unknown type: bigint".to_string())
+        );
+        assert_eq!(error.column, 25);
+    }
+
+    #[test]
+    fn test_parse_statement_select() {
+        let result = parse_statement("select a from t;".to_string()).unwrap();
+        match result {
+            Statement::Select(select) => assert_eq!(
+                select.table,
+                Some(TableReference::Table("t".to_string(), None))
+            ),
+            _ => panic!("Expected a SELECT statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_create_table_duplicate_column() {
+        let error = parse_statement("create table t (a int, a text);".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("Duplicate column name: a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_insert() {
+        let result =
+            parse_statement("INSERT INTO t (a, b) VALUES (1, 'x'), (-2, 'y');".to_string())
+                .unwrap();
+        let expected = InsertStatement {
+            table: "t".to_string(),
+            columns: Some(vec!["a".to_string(), "b".to_string()]),
+            rows: vec![
+                vec![DataType::Integer32(1), DataType::String("x".to_string())],
+                vec![DataType::Integer32(-2), DataType::String("y".to_string())],
+            ],
+            parameters: vec![],
+        };
+        assert_eq!(result, Statement::Insert(expected));
+    }
+
+    #[test]
+    fn test_parse_insert_without_columns() {
+        let result = parse_statement("insert into t values (1.5);".to_string()).unwrap();
+        match result {
+            Statement::Insert(insert) => {
+                assert_eq!(insert.columns, None);
+                assert_eq!(insert.rows, vec![vec![DataType::Float32(1.5)]]);
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_drop_table() {
+        let result = parse_statement("DROP TABLE orders;".to_string()).unwrap();
+        let expected = DropTableStatement {
+            table: "orders".to_string(),
+            if_exists: false,
+        };
+        assert_eq!(result, Statement::DropTable(expected));
+
+        let result = parse_statement("drop table if exists orders;".to_string()).unwrap();
+        match result {
+            Statement::DropTable(drop) => assert!(drop.if_exists),
+            _ => panic!("Expected a DROP TABLE statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_truncate_table() {
+        let result = parse_statement("truncate table orders;".to_string()).unwrap();
+        let expected = TruncateTableStatement {
+            table: "orders".to_string(),
+        };
+        assert_eq!(result, Statement::TruncateTable(expected));
+    }
+
+    #[test]
+    fn test_parse_alter_table() {
+        let cases = vec![
+            (
+                "alter table t add column c float default 1.5;",
+                AlterTableAction::AddColumn(
+                    ColumnDefinition {
+                        name: "c".to_string(),
+                        data_type: DataType::Float32(0.0),
+                    },
+                    Some(DataType::Float32(1.5)),
+                ),
+            ),
+            (
+                "ALTER TABLE t ADD c text;",
+                AlterTableAction::AddColumn(
+                    ColumnDefinition {
+                        name: "c".to_string(),
+                        data_type: DataType::String(String::new()),
+                    },
+                    None,
+                ),
+            ),
+            (
+                "alter table t drop column c;",
+                AlterTableAction::DropColumn("c".to_string()),
+            ),
+            (
+                "alter table t rename column a to b;",
+                AlterTableAction::RenameColumn("a".to_string(), "b".to_string()),
+            ),
+            (
+                "alter table t rename a to b;",
+                AlterTableAction::RenameColumn("a".to_string(), "b".to_string()),
+            ),
+            (
+                "alter table t rename to u;",
+                AlterTableAction::RenameTable("u".to_string()),
+            ),
+        ];
+        for (input, action) in cases {
+            let result = parse_statement(input.to_string()).unwrap();
+            let expected = AlterTableStatement {
+                table: "t".to_string(),
+                action,
+            };
+            assert_eq!(result, Statement::AlterTable(expected));
+        }
+    }
+
+    #[test]
+    fn test_parse_update() {
+        let result =
+            parse_statement("UPDATE t SET a = 1, b = c WHERE c <> 'x';".to_string()).unwrap();
+        let expected = UpdateStatement {
+            table: "t".to_string(),
+            assignments: vec![
+                ("a".to_string(), Expression::Literal(DataType::Integer32(1))),
+                ("b".to_string(), Expression::Column("c".to_string())),
+            ],
+            filter: Some(Expression::Comparison(
+                Box::new(Expression::Column("c".to_string())),
+                ComparisonOperator::NotEqual,
+                Box::new(Expression::Literal(DataType::String("x".to_string()))),
+            )),
+        };
+        assert_eq!(result, Statement::Update(expected));
+    }
+
+    #[test]
+    fn test_parse_delete() {
+        let result = parse_statement("delete from t where a > 1;".to_string()).unwrap();
+        let expected = DeleteStatement {
+            table: "t".to_string(),
+            filter: Some(Expression::Comparison(
+                Box::new(Expression::Column("a".to_string())),
+                ComparisonOperator::GreaterThan,
+                Box::new(Expression::Literal(DataType::Integer32(1))),
+            )),
+        };
+        assert_eq!(result, Statement::Delete(expected));
+
+        let result = parse_statement("DELETE FROM t;".to_string()).unwrap();
+        let expected = DeleteStatement {
+            table: "t".to_string(),
+            filter: None,
+        };
+        assert_eq!(result, Statement::Delete(expected));
+        assert!(parse_statement("delete t;".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_order_by_limit_offset() {
+        let result = parse_select(
+            "select a, b from t order by a desc, b nulls first limit 10 offset 5;".to_string(),
+        )
+        .unwrap();
+        let expected = vec![
+            OrderBy {
+                expression: Expression::Column("a".to_string()),
+                descending: true,
+                nulls_first: true,
+            },
+            OrderBy {
+                expression: Expression::Column("b".to_string()),
+                descending: false,
+                nulls_first: true,
+            },
+        ];
+        assert_eq!(result.order_by, expected);
+        assert_eq!(result.limit, Some(10));
+        assert_eq!(result.offset, Some(5));
+
+        let result =
+            parse_select("SELECT a FROM t ORDER BY a ASC NULLS LAST OFFSET 2;".to_string())
+                .unwrap();
+        assert!(!result.order_by[0].descending);
+        assert!(!result.order_by[0].nulls_first);
+        assert_eq!(result.limit, None);
+        assert_eq!(result.offset, Some(2));
+
+        let result = parse_select("select a from t;".to_string()).unwrap();
+        assert!(result.order_by.is_empty());
+        assert!(parse_select("select a from t limit -1;".to_string()).is_err());
+        assert!(parse_select("select a from t offset 1 limit 1;".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_group_by() {
+        let result = parse_select(
+            "select dept, COUNT(*), avg(salary) from t group by dept order by max(salary);"
+                .to_string(),
+        )
+        .unwrap();
+        let average = Expression::Aggregate(
+            AggregateFunction::Avg,
+            Some(Box::new(Expression::Column("salary".to_string()))),
+            false,
+        );
+        assert_eq!(
+            result.columns,
+            vec![
+                SelectItem::Expression(Expression::Column("dept".to_string()), None),
+                SelectItem::Expression(
+                    Expression::Aggregate(AggregateFunction::Count, None, false),
+                    None
+                ),
+                SelectItem::Expression(average, None),
+            ]
+        );
+        assert_eq!(
+            result.column_names(),
+            vec!["dept", "COUNT(*)", "AVG(salary)"]
+        );
+        assert_eq!(
+            result.group_by,
+            vec![Expression::Column("dept".to_string())]
+        );
+        assert_eq!(result.order_by[0].expression.to_string(), "MAX(salary)");
+        assert!(result.is_grouped());
+
+        let result = parse_select(
+            "select dept from t group by dept having count(*) > 5 and dept <> 'ops';".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.having.unwrap().to_string(),
+            "COUNT(*) > 5 AND dept <> 'ops'"
+        );
+
+        // aggregates without GROUP BY reduce every row into a single group
+        let result = parse_select("select count(*) from t;".to_string()).unwrap();
+        assert!(result.group_by.is_empty());
+        assert!(result.is_grouped());
+    }
+
+    #[test]
+    fn test_parse_group_by_errors() {
+        let cases = vec![
+            (
+                "select name, count(*) from t group by dept;",
+                "Column name must appear in the GROUP BY clause or be used in an aggregate function",
+            ),
+            (
+                "select sum(a) from t order by b;",
+                "Column b must appear in the GROUP BY clause or be used in an aggregate function",
+            ),
+            ("select * from t group by a;", "Cannot select * from grouped rows"),
+            (
+                "select dept, count(*) from t group by dept having salary > 10;",
+                "Column salary must appear in the GROUP BY clause or be used in an aggregate function",
+            ),
+            (
+                "select count(*) from t having max(min(a)) > 1;",
+                "Aggregate functions cannot be nested",
+            ),
+            (
+                "select a from t where count(*) > 1;",
+                "Aggregate functions are not allowed in WHERE",
+            ),
+            (
+                "select count(*) from t group by count(*);",
+                "Aggregate functions are not allowed in GROUP BY",
+            ),
+            (
+                "select max(count(*)) from t;",
+                "Aggregate functions cannot be nested",
+            ),
+        ];
+        // these rules are checked once the select is parsed
+        for (input, message) in cases {
+            let statement = parse_select(input.to_string()).unwrap();
+            assert_eq!(statement.validate().unwrap_err(), message, "{}", input);
+        }
+        let cases = vec![
+            ("select sum(*) from t;", "SUM does not accept *"),
+            ("select sum(a, b) from t;", "SUM expects a single argument"),
+        ];
+        for (input, message) in cases {
+            let error = parse_select(input.to_string()).unwrap_err();
+            assert_eq!(
+                error.kind,
+                ParseErrorKind::InvalidValue(message.to_string()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_expression_display() {
+        let expression = sql::ExpressionParser::new()
+            .parse("not (a > -1 or b = 'it''s') and (c < 2.5 and d <> e)")
+            .unwrap();
+        assert_eq!(
+            expression.to_string(),
+            "NOT (a > -1 OR b = 'it''s') AND (c < 2.5 AND d <> e)"
+        );
+    }
+
+    #[test]
+    fn test_parse_join() {
+        let result = parse_select(
+            "select a.x, y from first_table as a join second b on a.id = b.a_id inner join c on b.z = c.z;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(result.column_names(), vec!["a.x", "y"]);
+        let first_join = TableReference::Join(
+            Box::new(TableReference::Table(
+                "first_table".to_string(),
+                Some("a".to_string()),
+            )),
+            JoinKind::Inner,
+            Box::new(TableReference::Table(
+                "second".to_string(),
+                Some("b".to_string()),
+            )),
+            Expression::Comparison(
+                Box::new(Expression::Column("a.id".to_string())),
+                ComparisonOperator::Equal,
+                Box::new(Expression::Column("b.a_id".to_string())),
+            ),
+        );
+        let table = result.table.unwrap();
+        match &table {
+            TableReference::Join(left, JoinKind::Inner, right, condition) => {
+                assert_eq!(**left, first_join);
+                assert_eq!(**right, TableReference::Table("c".to_string(), None));
+                assert_eq!(condition.to_string(), "b.z = c.z");
+            }
+            _ => panic!("Expected a join"),
+        }
+        assert_eq!(table.aliases(), vec!["a", "b", "c"]);
+
+        let result = parse_select("select x from t join t on t.x = t.y;".to_string()).unwrap();
+        assert_eq!(
+            result.validate().unwrap_err(),
+            "Table name t specified more than once"
+        );
+        assert!(parse_select("select x from a join b;".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_outer_join() {
+        let cases = vec![
+            ("left join", JoinKind::Left),
+            ("LEFT OUTER JOIN", JoinKind::Left),
+            ("right join", JoinKind::Right),
+            ("right outer join", JoinKind::Right),
+            ("full join", JoinKind::Full),
+            ("full outer join", JoinKind::Full),
+        ];
+        for (keywords, expected) in cases {
+            let query = format!("select a.x from t a {} u b on a.id = b.id;", keywords);
+            let result = parse_select(query).unwrap();
+            match result.table.unwrap() {
+                TableReference::Join(_, kind, _, _) => assert_eq!(kind, expected),
+                _ => panic!("Expected a join"),
+            }
+        }
+        assert!(parse_select("select x from a outer join b on a.x = b.x;".to_string()).is_err());
+
+        let result = parse_select(
+            "select a.x from t a left join u b on a.id = b.id where b.id is null or not a.x is not null;"
+                .to_string(),
+        )
+        .unwrap();
+        let filter = result.filter.unwrap();
+        assert_eq!(
+            filter,
+            Expression::Or(
+                Box::new(Expression::IsNull(
+                    Box::new(Expression::Column("b.id".to_string())),
+                    false
+                )),
+                Box::new(Expression::Not(Box::new(Expression::IsNull(
+                    Box::new(Expression::Column("a.x".to_string())),
+                    true
+                )))),
+            )
+        );
+        assert_eq!(filter.to_string(), "b.id IS NULL OR NOT a.x IS NOT NULL");
+
+        // NULL may also be written as a value
+        let result =
+            parse_select("select null, case when a > 0 then a else null end from t;".to_string())
+                .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["NULL", "CASE WHEN a > 0 THEN a ELSE NULL END"]
+        );
+        let result = parse_statement("insert into t values (1, null);".to_string()).unwrap();
+        match result {
+            Statement::Insert(insert) => {
+                assert_eq!(
+                    insert.rows,
+                    vec![vec![DataType::Integer32(1), DataType::Null]]
+                );
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+        assert!(parse_statement("update t set a = null where b is null;".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_subqueries() {
+        let result = parse_select(
+            "select name, (select max(salary) from employees) from (select name, dept_id from employees) as e \
+            where dept_id not in (select id from departments where floor > 2) \
+            and exists (select id from departments d where d.id = e.dept_id);"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["name", "(SELECT MAX(salary) FROM employees)"]
+        );
+        match result.table.as_ref().unwrap() {
+            TableReference::Subquery(statement, alias) => {
+                assert_eq!(alias, "e");
+                assert_eq!(statement.column_names(), vec!["name", "dept_id"]);
+            }
+            _ => panic!("Expected a derived table"),
+        }
+        let filter = result.filter.as_ref().unwrap();
+        assert_eq!(
+            filter.to_string(),
+            "dept_id NOT IN (SELECT id FROM departments WHERE floor > 2) \
+            AND EXISTS (SELECT id FROM departments AS d WHERE d.id = e.dept_id)"
+        );
+        assert_eq!(filter.subqueries().len(), 2);
+        assert!(result.has_subqueries());
+        assert_eq!(
+            result.referenced_columns(),
+            vec![
+                "name",
+                "dept_id",
+                "salary",
+                "id",
+                "floor",
+                "d.id",
+                "e.dept_id"
+            ]
+        );
+        assert_eq!(
+            result.table.unwrap().to_string(),
+            "(SELECT name, dept_id FROM employees) AS e"
+        );
+
+        let result = parse_select(
+            "select x from t where y > (select avg(y) from t order by avg(y) desc nulls last limit 1 offset 2);"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.filter.unwrap().to_string(),
+            "y > (SELECT AVG(y) FROM t ORDER BY AVG(y) DESC NULLS LAST LIMIT 1 OFFSET 2)"
+        );
+
+        let cases = vec![
+            (
+                "select x from t group by (select y from u);",
+                "Subqueries are not allowed in GROUP BY",
+            ),
+            (
+                "select count((select y from u)) from t;",
+                "Subqueries are not allowed in aggregate functions",
+            ),
+            (
+                "select t.x from t join u on t.x in (select y from v);",
+                "Subqueries are not allowed in JOIN conditions",
+            ),
+        ];
+        for (query, message) in cases {
+            let statement = parse_select(query.to_string()).unwrap();
+            assert_eq!(statement.validate().unwrap_err(), message);
+        }
+        // a subquery is validated on its own
+        let result =
+            parse_select("select x from t where exists (select y from u group by z);".to_string())
+                .unwrap();
+        assert!(result.validate().is_ok());
+        match result.filter.unwrap() {
+            Expression::Exists(statement) => assert_eq!(
+                statement.validate().unwrap_err(),
+                "Column y must appear in the GROUP BY clause or be used in an aggregate function"
+            ),
+            _ => panic!("Expected EXISTS"),
+        }
+        // derived tables need an alias
+        assert!(parse_select("select x from (select x from t);".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_computed_columns() {
+        let result = parse_select(
+            "select a + b * 2 as total, -c d, (a - b) / 2, x % 3 from t order by total desc, d;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["total", "d", "(a - b) / 2", "x % 3"]
+        );
+        assert_eq!(
+            result.columns[0],
+            SelectItem::Expression(
+                Expression::Arithmetic(
+                    Box::new(Expression::Column("a".to_string())),
+                    ArithmeticOperator::Add,
+                    Box::new(Expression::Arithmetic(
+                        Box::new(Expression::Column("b".to_string())),
+                        ArithmeticOperator::Multiply,
+                        Box::new(Expression::Literal(DataType::Integer32(2))),
+                    )),
+                ),
+                Some("total".to_string()),
+            )
+        );
+        // sort keys naming an alias are replaced by the aliased expression
+        let keys: Vec<String> = result.order_by.iter().map(|key| key.to_string()).collect();
+        assert_eq!(keys, vec!["a + b * 2 DESC", "-c"]);
+        assert_eq!(
+            result.to_string(),
+            "SELECT a + b * 2 AS total, -c AS d, (a - b) / 2, x % 3 FROM t ORDER BY a + b * 2 DESC, -c"
+        );
+
+        let cases = vec![
+            ("a - b - c", "a - b - c"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("(a + b) * -(c % d)", "(a + b) * -(c % d)"),
+            ("- -a", "-(-a)"),
+            ("a - -1 * 2.5", "a - -1 * 2.5"),
+            ("a + 1 > b * 2 and not c", "a + 1 > b * 2 AND NOT c"),
+        ];
+        for (input, expected) in cases {
+            let expression = sql::ExpressionParser::new().parse(input).unwrap();
+            assert_eq!(expression.to_string(), expected);
+        }
+        // a negated number is a literal
+        assert_eq!(
+            sql::ExpressionParser::new().parse("-(2)").unwrap(),
+            Expression::Literal(DataType::Integer32(-2))
+        );
+    }
+
+    #[test]
+    fn test_parse_function_calls() {
+        let result = parse_select(
+            "select upper(name), substr(name, 2, len - 1), coalesce(a, b, 0), now(), max(abs(x)) \
+            from t group by name, len, a, b;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec![
+                "UPPER(name)",
+                "SUBSTR(name, 2, len - 1)",
+                "COALESCE(a, b, 0)",
+                "NOW()",
+                "MAX(ABS(x))"
+            ]
+        );
+        assert_eq!(
+            result.columns[2],
+            SelectItem::Expression(
+                Expression::Function(
+                    "COALESCE".to_string(),
+                    vec![
+                        Expression::Column("a".to_string()),
+                        Expression::Column("b".to_string()),
+                        Expression::Literal(DataType::Integer32(0)),
+                    ]
+                ),
+                None
+            )
+        );
+        // functions are resolved when executed, so unknown functions are parsed
+        let result = parse_select("select durp(a) from t;".to_string()).unwrap();
+        assert_eq!(result.column_names(), vec!["DURP(a)"]);
+        assert_eq!(result.referenced_columns(), vec!["a"]);
+
+        let error = parse_select("select upper(*) from t;".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("UPPER does not accept *".to_string())
+        );
+        let result = parse_select("select lower(a) from t group by b;".to_string()).unwrap();
+        assert_eq!(
+            result.validate().unwrap_err(),
+            "Column a must appear in the GROUP BY clause or be used in an aggregate function"
+        );
+    }
+
+    #[test]
+    fn test_parse_case() {
+        let result = parse_select(
+            "select case when salary > 100 then 'high' when salary > 50 then 'mid' else 'low' end as band, \
+            case dept when 'eng' then 1 end from t order by case dept when 'ops' then 0 else 1 end;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["band", "CASE dept WHEN 'eng' THEN 1 END"]
+        );
+        assert_eq!(
+            result.columns[0].to_string(),
+            "CASE WHEN salary > 100 THEN 'high' WHEN salary > 50 THEN 'mid' ELSE 'low' END AS band"
+        );
+        assert_eq!(
+            result.order_by[0].expression,
+            Expression::Case(
+                Some(Box::new(Expression::Column("dept".to_string()))),
+                vec![(
+                    Expression::Literal(DataType::String("ops".to_string())),
+                    Expression::Literal(DataType::Integer32(0))
+                )],
+                Some(Box::new(Expression::Literal(DataType::Integer32(1))))
+            )
+        );
+        assert_eq!(result.referenced_columns(), vec!["salary", "dept"]);
+
+        let result = parse_select(
+            "select sum(case when a is null then 0 else a end) from t \
+            where case when b > 0 then b else -b end * 2 > 10;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.filter.unwrap().to_string(),
+            "CASE WHEN b > 0 THEN b ELSE -b END * 2 > 10"
+        );
+        // a CASE needs at least one WHEN clause
+        assert!(parse_select("select case else 1 end from t;".to_string()).is_err());
+        let result =
+            parse_select("select case when a > 0 then b end from t group by a;".to_string())
+                .unwrap();
+        assert_eq!(
+            result.validate().unwrap_err(),
+            "Column b must appear in the GROUP BY clause or be used in an aggregate function"
+        );
+    }
+
+    #[test]
+    fn test_parse_distinct() {
+        let result = parse_select("select distinct a, b from t order by a;".to_string()).unwrap();
+        assert!(result.distinct);
+        assert_eq!(result.column_names(), vec!["a", "b"]);
+        assert_eq!(result.to_string(), "SELECT DISTINCT a, b FROM t ORDER BY a");
+        assert!(
+            !parse_select("select a from t;".to_string())
+                .unwrap()
+                .distinct
+        );
+
+        let result = parse_select(
+            "select count(distinct a), sum(distinct b + 1), count(a) from t;".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["COUNT(DISTINCT a)", "SUM(DISTINCT b + 1)", "COUNT(a)"]
+        );
+        assert_eq!(
+            result.columns[0],
+            SelectItem::Expression(
+                Expression::Aggregate(
+                    AggregateFunction::Count,
+                    Some(Box::new(Expression::Column("a".to_string()))),
+                    true
+                ),
+                None
+            )
+        );
+        let error = parse_select("select upper(distinct a) from t;".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("UPPER does not accept DISTINCT".to_string())
+        );
+        assert!(parse_select("select count(distinct *) from t;".to_string()).is_err());
+
+        // the duplicates are removed from the selected columns, so the rows can only be sorted by them
+        let cases = vec![
+            "select distinct g from t order by v;",
+            "select distinct g from t order by g + 1;",
+        ];
+        for query in cases {
+            let statement = parse_select(query.to_string()).unwrap();
+            assert_eq!(
+                statement.validate().unwrap_err(),
+                "For SELECT DISTINCT, ORDER BY expressions must appear in the select list",
+                "{}",
+                query
+            );
+        }
+        let cases = vec![
+            "select distinct g + 1 as h from t order by h desc;",
+            "select distinct * from t order by v;",
+            "select distinct g from t union select v from u order by g;",
+        ];
+        for query in cases {
+            let statement = parse_select(query.to_string()).unwrap();
+            assert!(statement.validate().is_ok(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_parse_set_operations() {
+        let result = parse_select(
+            "select a from t union all select b from u except select c from v order by a limit 2;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(result.column_names(), vec!["a"]);
+        assert_eq!(result.order_by.len(), 1);
+        assert_eq!(result.limit, Some(2));
+        let operators: Vec<(SetOperator, bool)> = result
+            .set_operations
+            .iter()
+            .map(|operation| (operation.operator, operation.all))
+            .collect();
+        assert_eq!(
+            operators,
+            vec![(SetOperator::Union, true), (SetOperator::Except, false)]
+        );
+        // the clauses after the last select belong to the combined rows
+        assert!(result.set_operations[1].select.order_by.is_empty());
+        assert_eq!(
+            result.to_string(),
+            "SELECT a FROM t UNION ALL SELECT b FROM u EXCEPT SELECT c FROM v ORDER BY a LIMIT 2"
+        );
+
+        // INTERSECT binds tighter than UNION
+        let result = parse_select(
+            "select a from t union select b from u intersect select c from v;".to_string(),
+        )
+        .unwrap();
+        assert_eq!(result.set_operations.len(), 1);
+        let right = &result.set_operations[0].select;
+        assert_eq!(right.column_names(), vec!["b"]);
+        assert_eq!(right.set_operations[0].operator, SetOperator::Intersect);
+        let result = parse_select(
+            "select a from t intersect all select b from u union select c from v;".to_string(),
+        )
+        .unwrap();
+        assert_eq!(result.set_operations.len(), 2);
+        assert_eq!(result.set_operations[0].operator, SetOperator::Intersect);
+        assert!(result.set_operations[0].all);
+
+        // set operations may be used in subqueries and derived tables
+        let result = parse_select(
+            "select x from (select a as x from t union select b from u) as s \
+            where x in (select c from v except select d from w);"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(result.column_names(), vec!["x"]);
+
+        let cases = vec![
+            (
+                "select a from t union select b from u order by count(*);",
+                "Aggregate functions are not allowed in the ORDER BY of a set operation",
+            ),
+            (
+                "select a from t union select b from u group by c;",
+                "Column b must appear in the GROUP BY clause or be used in an aggregate function",
+            ),
+        ];
+        for (query, expected) in cases {
+            let statement = parse_select(query.to_string()).unwrap();
+            assert_eq!(statement.validate().unwrap_err(), expected);
+        }
+        assert!(
+            parse_select("select a from t order by a union select b from u;".to_string()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_window_functions() {
+        let result = parse_select(
+            "select name, row_number() over (partition by dept order by salary desc), \
+            sum(salary) over (order by day rows between 2 preceding and current row) as moving, \
+            count(*) over (), lag(salary, 2, 0) over (order by day) from t order by rank() over (order by day);"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec![
+                "name",
+                "ROW_NUMBER() OVER (PARTITION BY dept ORDER BY salary DESC)",
+                "moving",
+                "COUNT(*) OVER ()",
+                "LAG(salary, 2, 0) OVER (ORDER BY day)"
+            ]
+        );
+        match &result.columns[2] {
+            SelectItem::Expression(Expression::WindowFunction(function, arguments, window), _) => {
+                assert_eq!(*function, WindowFunction::Aggregate(AggregateFunction::Sum));
+                assert_eq!(arguments.len(), 1);
+                assert_eq!(
+                    window.frame,
+                    Some(WindowFrame {
+                        start: FrameBound::Preceding(2),
+                        end: FrameBound::CurrentRow
+                    })
+                );
+            }
+            item => panic!("Expected a window function, found {:?}", item),
+        }
+        assert_eq!(
+            result.order_by[0].expression.to_string(),
+            "RANK() OVER (ORDER BY day)"
+        );
+        // window functions are not aggregates, so the rows are not grouped
+        assert!(!result.is_grouped());
+
+        // the shorthand frame ends at the current row
+        let result = parse_select(
+            "select first_value(a) over (rows unbounded preceding) from t;".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.column_names(),
+            vec!["FIRST_VALUE(a) OVER (ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW)"]
+        );
+
+        let cases = vec![
+            (
+                "select upper(a) over () from t;",
+                "UPPER is not a window function",
+            ),
+            (
+                "select rank(a) over () from t;",
+                "RANK expects 0 argument(s)",
+            ),
+            (
+                "select lag() over () from t;",
+                "LAG expects 1 to 3 arguments",
+            ),
+            ("select sum(*) over () from t;", "SUM does not accept *"),
+            (
+                "select sum(a) over (rows between unbounded following and current row) from t;",
+                "Frame cannot start at UNBOUNDED FOLLOWING",
+            ),
+            (
+                "select sum(a) over (rows 1 following) from t;",
+                "Frame cannot start at 1 FOLLOWING",
+            ),
+        ];
+        for (query, expected) in cases {
+            let error = parse_select(query.to_string()).unwrap_err();
+            assert_eq!(
+                error.kind,
+                ParseErrorKind::InvalidValue(expected.to_string())
+            );
+        }
+        let cases = vec![
+            (
+                "select a from t where row_number() over () > 1;",
+                "Window functions are not allowed in WHERE",
+            ),
+            (
+                "select sum(rank() over (order by a)) from t;",
+                "Window functions are not allowed in aggregate functions",
+            ),
+            (
+                "select sum(rank() over (order by a)) over () from t;",
+                "Window functions cannot be nested",
+            ),
+            (
+                "select a, rank() over (order by b) from t group by a;",
+                "Column b must appear in the GROUP BY clause or be used in an aggregate function",
+            ),
+        ];
+        for (query, expected) in cases {
+            let statement = parse_select(query.to_string()).unwrap();
+            assert_eq!(statement.validate().unwrap_err(), expected);
+        }
+        // ranking the groups by an aggregate is allowed
+        assert!(parse_select(
+            "select a, rank() over (order by sum(b) desc) from t group by a;".to_string()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_parse_with() {
+        let result = parse_select(
+            "with high as (select name, dept from t where salary > 100), \
+            depts (name, total) as (select dept, count(*) from high group by dept) \
+            select name from depts where total > 1;"
+                .to_string(),
+        )
+        .unwrap();
+        let with = result.with.as_ref().unwrap();
+        assert!(!with.recursive);
+        assert_eq!(with.tables.len(), 2);
+        assert_eq!(with.tables[1].name, "depts");
+        assert_eq!(with.tables[1].columns, vec!["name", "total"]);
+        assert!(with.tables[1].select.references_table("high"));
+        assert!(result.references_table("depts"));
+        // the tables read by the WITH clause are also read by the statement
+        assert!(result.references_table("t"));
+        assert_eq!(
+            result.to_string(),
+            "WITH high AS (SELECT name, dept FROM t WHERE salary > 100), \
+            depts (name, total) AS (SELECT dept, COUNT(*) FROM high GROUP BY dept) \
+            SELECT name FROM depts WHERE total > 1"
+        );
+
+        let result = parse_select(
+            "with recursive chain (id, depth) as (select id, 0 from t where manager is null \
+            union all select t.id, c.depth + 1 from t join chain c on t.manager = c.id) select id from chain;"
+                .to_string(),
+        )
+        .unwrap();
+        let with = result.with.unwrap();
+        assert!(with.recursive);
+        assert_eq!(with.tables[0].initial_select().set_operations.len(), 0);
+        assert_eq!(
+            with.tables[0].select.set_operations[0].operator,
+            SetOperator::Union
+        );
+        // a WITH clause may be given in subqueries
+        assert!(parse_select(
+            "select a from t where a in (with b as (select a from u) select a from b);".to_string()
+        )
+        .is_ok());
+
+        let recursive_form =
+            "Recursive query r must be in the form initial UNION [ALL] recursive, \
+            where only the recursive select refers to it";
+        let cases = vec![
+            (
+                "with a as (select x from t), a as (select x from u) select x from a;",
+                "WITH query name a specified more than once",
+            ),
+            (
+                "with recursive r as (select x from r) select x from r;",
+                recursive_form,
+            ),
+            (
+                "with recursive r as (select x from r union select x from t) select x from r;",
+                recursive_form,
+            ),
+            (
+                "with recursive r as (select x from t union select x from r limit 3) select x from r;",
+                "Recursive query r cannot have ORDER BY, LIMIT or OFFSET",
+            ),
+        ];
+        for (query, expected) in cases {
+            let statement = parse_select(query.to_string()).unwrap();
+            assert_eq!(statement.validate().unwrap_err(), expected);
+        }
+        // without RECURSIVE, a table of the same name is read instead
+        assert!(parse_select("with r as (select x from r) select x from r;".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = "create table t (a text);\ninsert into t values ('x;y');\nselect a from t;\n";
+        let pieces: Vec<&str> = split_script(script)
+            .into_iter()
+            .map(|(_, text)| text.trim())
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                "create table t (a text);",
+                "insert into t values ('x;y');",
+                "select a from t;"
+            ]
+        );
+        let statements = parse_script(script.to_string()).unwrap();
+        assert!(matches!(statements[0], Statement::CreateTable(_)));
+        assert!(matches!(statements[1], Statement::Insert(_)));
+        assert!(matches!(statements[2], Statement::Select(_)));
+
+        // errors locate the problem in the whole script
+        let (index, error) =
+            parse_script("select a from t;\nselect a from t where;".to_string()).unwrap_err();
+        assert_eq!(index, 1);
+        assert_eq!(error.kind, ParseErrorKind::UnrecognizedToken);
+        assert_eq!((error.line, error.column), (2, 22));
+
+        let (index, error) = parse_script("select a from t; select b".to_string()).unwrap_err();
+        assert_eq!(index, 1);
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedEndOfInput);
+        let (index, error) = parse_script("  ".to_string()).unwrap_err();
+        assert_eq!(index, 0);
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedEndOfInput);
+
+        // empty statements are skipped, and errors are still located in the whole script
+        let statements =
+            parse_script(";select a from t;; ;\nselect a from t;;".to_string()).unwrap();
+        assert_eq!(statements.len(), 2);
+        let (index, error) =
+            parse_script("select a from t;;\nselect a from t where;".to_string()).unwrap_err();
+        assert_eq!(index, 1);
+        assert_eq!((error.line, error.column), (2, 22));
+        let (index, error) = parse_script(";;".to_string()).unwrap_err();
+        assert_eq!(index, 0);
+        assert_eq!(error.kind, ParseErrorKind::UnrecognizedToken);
+    }
+
+    #[test]
+    fn test_parse_parameters() {
+        let result = parse_select(
+            "select a + ? as total from t where b = ? and c in (select d from u where e > ?) order by total;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.to_string(),
+            "SELECT a + $1 AS total FROM t WHERE b = $2 AND c IN (SELECT d FROM u WHERE e > $3) ORDER BY a + $1"
+        );
+        let statement =
+            parse_statement("select '?' from t where a = $2 or b = $1;".to_string()).unwrap();
+        assert_eq!(statement.parameter_count(), 2);
+
+        let result = parse_statement("insert into t values (1, ?), (?, 'x');".to_string()).unwrap();
+        match &result {
+            Statement::Insert(insert) => {
+                let expected = vec![
+                    (0, 1, Parameter::Positional(1)),
+                    (1, 0, Parameter::Positional(2)),
+                ];
+                assert_eq!(insert.parameters, expected);
+                assert_eq!(insert.rows[0], vec![DataType::Integer32(1), DataType::Null]);
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+        let bound = result
+            .map_parameters(&mut |parameter| -> Result<Expression, ()> {
+                let number = parameter.number() as i32;
+                return Ok(Expression::Literal(DataType::Integer32(number * 10)));
+            })
+            .unwrap();
+        match bound {
+            Statement::Insert(insert) => {
+                assert!(insert.parameters.is_empty());
+                assert_eq!(insert.rows[1][0], DataType::Integer32(20));
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+        let result = parse_statement("insert into t values ($2, $1);".to_string()).unwrap();
+        match &result {
+            Statement::Insert(insert) => {
+                let expected = vec![
+                    (0, 0, Parameter::Numbered(2)),
+                    (0, 1, Parameter::Numbered(1)),
+                ];
+                assert_eq!(insert.parameters, expected);
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+
+        let error =
+            parse_statement("select a from t where a = ? and b = $1;".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("Cannot mix ? with numbered parameters".to_string())
+        );
+        assert_eq!(error.column, 27);
+        // the text of a string literal is not a parameter
+        let result =
+            parse_select("select '$1' from t where a = ? or b = '?';".to_string()).unwrap();
+        assert_eq!(result.filter.unwrap().to_string(), "a = $1 OR b = '?'");
+        let error = parse_statement("select $0 from t;".to_string()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::InvalidValue("Invalid parameter number: $0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_explain() {
+        let result = parse_statement("EXPLAIN delete from t;".to_string()).unwrap();
+        let expected = DeleteStatement {
+            table: "t".to_string(),
+            filter: None,
+        };
+        assert_eq!(
+            result,
+            Statement::Explain(Box::new(Statement::Delete(expected)))
+        );
+
+        let result = parse_statement("explain select a from t where a > ?;".to_string()).unwrap();
+        match &result {
+            Statement::Explain(statement) => {
+                assert!(matches!(**statement, Statement::Select(_)));
+            }
+            _ => panic!("Expected an EXPLAIN statement"),
+        }
+        assert_eq!(result.parameter_count(), 1);
+
+        // an EXPLAIN statement cannot be explained
+        let error = parse_statement("explain explain delete from t;".to_string()).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnrecognizedToken);
+    }
+
+    #[test]
+    fn test_parse_integer_limits() {
+        let cases = vec![
+            ("select a from t where a = -2147483648;", i32::MIN),
+            ("select a from t where a = 2147483647;", i32::MAX),
+            ("select a from t where a = - -5;", 5),
+        ];
+        for (query, value) in cases {
+            let result = parse_select(query.to_string()).unwrap();
+            let expected = Expression::Comparison(
+                Box::new(Expression::Column("a".to_string())),
+                ComparisonOperator::Equal,
+                Box::new(Expression::Literal(DataType::Integer32(value))),
+            );
+            assert_eq!(result.filter, Some(expected));
+        }
+        let result = parse_statement("insert into t values (-2147483648, 2147483647);".to_string());
+        match result.unwrap() {
+            Statement::Insert(insert) => assert_eq!(
+                insert.rows,
+                vec![vec![
+                    DataType::Integer32(i32::MIN),
+                    DataType::Integer32(i32::MAX)
+                ]]
+            ),
+            _ => panic!("Expected an INSERT statement"),
+        }
+
+        let cases = vec![
+            ("select a from t where a = 2147483648;", "2147483648", 27),
+            ("select a from t where a = -2147483649;", "-2147483649", 28),
+            ("insert into t values (-2147483649);", "-2147483649", 24),
+        ];
+        for (query, value, column) in cases {
+            let error = parse_statement(query.to_string()).unwrap_err();
+            let message = format!("Integer out of range: {}", value);
+            assert_eq!(error.kind, ParseErrorKind::InvalidValue(message));
+            assert_eq!(error.column, column);
+        }
     }
 }